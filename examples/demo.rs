@@ -3,7 +3,6 @@ use std::fmt::Debug;
 use std::sync::{Arc};
 use tokio::sync::Mutex;
 
-use axum::extract::State;
 use axum::response::IntoResponse;
 use axum::Json;
 use axum::Router;
@@ -13,7 +12,6 @@ use serde::{Deserialize, Serialize};
 use serde_json::from_str;
 use serde_json::Value;
 
-type RedisServiceImpl = SimpleRedisService;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct Response<T: Serialize> {
     code: u16,
@@ -43,7 +41,7 @@ impl RValue {
             serde_json::Value::Number(n) => Self::Number(n.as_u64().unwrap()),
             serde_json::Value::Bool(b) => Self::Boolean(b),
             serde_json::Value::Object(map) => Self::HashMap(map.into_iter().map(|(k, v)| (k, Self::from_json(v))).collect()),
-            serde_json::Value::Array(vec) => Self::Array(vec.into_iter().map(|v| Self::from_json(v)).collect()),
+            serde_json::Value::Array(vec) => Self::Array(vec.into_iter().map(Self::from_json).collect()),
             serde_json::Value::Null => Self::Null,
         }
     }
@@ -59,7 +57,7 @@ impl RValue {
     }
 }
 
-pub trait RedisService: Debug + Send + Sync {
+trait RedisService: Debug + Send + Sync {
     async fn set(&self, topic: String, key: String, value: &RValue);
     async fn get(&self, topic: String, key: String) -> Option<RValue>;
     async fn list_by_topic(&self, topic: String) -> HashMap<String, RValue>;
@@ -102,6 +100,11 @@ impl RedisService for SimpleRedisService {
     }
 }
 
+#[derive(Controller)]
+struct SimpleRedisController {
+    service: Arc<SimpleRedisService>,
+}
+
 #[controller("/redis")]
 impl SimpleRedisController {
     #[get("/list")]
@@ -138,7 +141,7 @@ impl SimpleRedisController {
 
     #[get("/set")]
     async fn set(service: dyn RedisService, #[q] topic: String, #[q] key: String, #[q] value: String) {
-        service.set(topic, key, &RValue::String(value));
+        service.set(topic, key, &RValue::String(value)).await;
         Json(Response::<Option<RValue>> {
             code: 200,
             msg: "success".to_string(),
@@ -184,7 +187,7 @@ impl SimpleRedisController {
                 data: None,
             });
         };
-        service.set(final_topic.to_string(), final_key.to_string(), &final_value);
+        service.set(final_topic.to_string(), final_key.to_string(), &final_value).await;
         Json(Response::<Value> {
             code: 200,
             msg: "success".to_string(),
@@ -194,7 +197,7 @@ impl SimpleRedisController {
 
     #[get("/del")]
     async fn del(service: dyn RedisService, #[q] topic: String, #[q] key: String) {
-        service.del(topic, key);
+        service.del(topic, key).await;
         Json(Response::<Option<RValue>> {
             code: 200,
             msg: "success".to_string(),
@@ -204,7 +207,7 @@ impl SimpleRedisController {
 
     #[get("/clear")]
     async fn clear(service: SimpleRedisService, #[q] topic: String) {
-        service.clear(topic);
+        service.clear(topic).await;
         Json(Response::<Option<RValue>> {
             code: 200,
             msg: "success".to_string(),
@@ -213,8 +216,8 @@ impl SimpleRedisController {
     }
 
     #[delete("/destroy")]
-    async fn destroy(service: SimpleRedisService) {
-        service.destroy();
+    async fn destroy(service: Arc<SimpleRedisService>) {
+        service.destroy().await;
         Json(Response::<Option<RValue>> {
             code: 200,
             msg: "success".to_string(),
@@ -236,7 +239,8 @@ impl SimpleRedisController {
     }
 
     #[get("/status")]
-    async fn status(service: SimpleRedisService, #[q] topic: Option<String>) {
+    async fn status(&self, #[q] topic: Option<String>) {
+        let service = &self.service;
         match topic {
             Some(topic) => {
                 let list = service.list_by_topic(topic.clone()).await;
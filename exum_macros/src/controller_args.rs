@@ -0,0 +1,139 @@
+use quote::quote;
+use syn::{
+    bracketed,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    Attribute, Expr, Ident, LitStr, Token, Type,
+};
+
+use crate::middleware::RouteLayers;
+
+/// Parses either a single item or a bracketed list, e.g. `guard = is_admin` or `guard = [a, b]`.
+fn one_or_many<T: Parse>(input: ParseStream) -> syn::Result<Vec<T>> {
+    if input.peek(syn::token::Bracket) {
+        let content;
+        bracketed!(content in input);
+        Ok(Punctuated::<T, Token![,]>::parse_terminated(&content)?.into_iter().collect())
+    } else {
+        Ok(vec![input.parse()?])
+    }
+}
+
+/// Settings of `#[controller(..)]`, shared by every route in the impl.
+///
+/// Accepts a bare prefix (`#[controller("/users")]`) optionally followed by
+/// `prefix`, `tag`/`tags`, `layers`, `middleware`, `guard`, `error`, `children`, `version`,
+/// `group` and `profile`.
+#[derive(Default)]
+pub struct ControllerArgs {
+    pub prefix: String,
+    pub tags: Vec<LitStr>,
+    pub layers: RouteLayers,
+    pub error: Option<Type>,
+    pub children: Vec<Type>,
+    /// Default API version of the routes, see `exum::versioning`.
+    pub version: Option<LitStr>,
+    /// Mounts the controller only through `Application::mount_group`.
+    pub group: Option<LitStr>,
+    /// Environments the controller is registered in.
+    pub profile: Option<LitStr>,
+}
+
+impl Parse for ControllerArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut args = Self::default();
+        if input.peek(LitStr) {
+            args.prefix = input.parse::<LitStr>()?.value();
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            match key.to_string().as_str() {
+                "prefix" => args.prefix = input.parse::<LitStr>()?.value(),
+                "tag" | "tags" => args.tags.extend(one_or_many::<LitStr>(input)?),
+                "layers" => {
+                    let exprs = one_or_many::<Expr>(input)?;
+                    args.layers.layers.extend(exprs.iter().map(|e| quote! { #e }));
+                }
+                "middleware" => {
+                    let exprs = one_or_many::<Expr>(input)?;
+                    args.layers
+                        .layers
+                        .extend(exprs.iter().map(|e| quote! { axum::middleware::from_fn(#e) }));
+                }
+                "guard" => args.layers.guards.extend(one_or_many::<syn::Path>(input)?),
+                "error" => args.error = Some(input.parse()?),
+                "children" => args.children.extend(one_or_many::<Type>(input)?),
+                "version" => args.version = Some(input.parse()?),
+                "group" => args.group = Some(input.parse()?),
+                "profile" => args.profile = Some(input.parse()?),
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        &key,
+                        "expected `prefix`, `tag`, `layers`, `middleware`, `guard`, `error`, `children`, `version`, `group` or `profile`",
+                    ));
+                }
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        if !args.prefix.is_empty() && !args.prefix.starts_with('/') {
+            return Err(input.error("controller prefix must start with `/`"));
+        }
+        Ok(args)
+    }
+}
+
+/// Per-route overrides of the controller settings: `#[tag(..)]` and `#[error(T)]` replace the
+/// controller's tags and error type, `#[skip(layers, guard)]` opts out of its layers or guards.
+#[derive(Default)]
+pub struct RouteOverrides {
+    pub tags: Option<Vec<LitStr>>,
+    pub error: Option<Type>,
+    pub skip_layers: bool,
+    pub skip_guards: bool,
+}
+
+fn is_override_attr(attr: &Attribute) -> bool {
+    attr.path().is_ident("tag") || attr.path().is_ident("error") || attr.path().is_ident("skip")
+}
+
+impl RouteOverrides {
+    /// Removes the override attributes from `attrs` and collects them.
+    pub fn take(attrs: &mut Vec<Attribute>) -> syn::Result<Self> {
+        let mut this = Self::default();
+        for attr in attrs.iter().filter(|a| is_override_attr(a)) {
+            if attr.path().is_ident("tag") {
+                let tags = attr.parse_args_with(Punctuated::<LitStr, Token![,]>::parse_terminated)?;
+                this.tags.get_or_insert_default().extend(tags);
+            } else if attr.path().is_ident("error") {
+                this.error = Some(attr.parse_args()?);
+            } else {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("layers") {
+                        this.skip_layers = true;
+                    } else if meta.path.is_ident("guard") {
+                        this.skip_guards = true;
+                    } else {
+                        return Err(meta.error("expected `layers` or `guard`"));
+                    }
+                    Ok(())
+                })?;
+            }
+        }
+        attrs.retain(|a| !is_override_attr(a));
+        Ok(this)
+    }
+
+    /// The controller layers that still apply to this route.
+    pub fn controller_layers(&self, layers: &RouteLayers) -> RouteLayers {
+        RouteLayers {
+            layers: if self.skip_layers { Vec::new() } else { layers.layers.clone() },
+            guards: if self.skip_guards { Vec::new() } else { layers.guards.clone() },
+        }
+    }
+}
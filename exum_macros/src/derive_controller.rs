@@ -0,0 +1,87 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Fields};
+
+use crate::utils::inject_expr;
+
+fn field_value(field: &syn::Field) -> syn::Result<TokenStream> {
+    let mut use_default = false;
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("inject")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("default") {
+                use_default = true;
+                Ok(())
+            } else {
+                Err(meta.error("expected `default`"))
+            }
+        })?;
+    }
+    if use_default {
+        Ok(quote! { ::std::default::Default::default() })
+    } else {
+        Ok(inject_expr(&field.ty))
+    }
+}
+
+pub fn derive_controller(input: DeriveInput) -> syn::Result<TokenStream> {
+    let ident = &input.ident;
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(&input.generics, "#[derive(Controller)] does not support generics"));
+    }
+    let construct = match &input.data {
+        Data::Struct(s) => match &s.fields {
+            Fields::Named(named) => {
+                let fields = named
+                    .named
+                    .iter()
+                    .map(|f| {
+                        let name = f.ident.as_ref().unwrap();
+                        field_value(f).map(|v| quote! { #name: #v })
+                    })
+                    .collect::<syn::Result<Vec<_>>>()?;
+                quote! { #ident { #(#fields),* } }
+            }
+            Fields::Unnamed(unnamed) => {
+                let fields = unnamed.unnamed.iter().map(field_value).collect::<syn::Result<Vec<_>>>()?;
+                quote! { #ident(#(#fields),*) }
+            }
+            Fields::Unit => quote! { #ident },
+        },
+        _ => return Err(syn::Error::new_spanned(ident, "#[derive(Controller)] only supports structs")),
+    };
+
+    let init_fn_name = format_ident!("__exum_controller_init_{}", ident);
+    let def_fn_name = format_ident!("__exum_controller_def_{}", ident);
+    Ok(quote! {
+        impl ::exum::controller::Controller for #ident {}
+
+        #[allow(non_snake_case)]
+        fn #init_fn_name() -> ::std::pin::Pin<
+            ::std::boxed::Box<
+                dyn ::std::future::Future<
+                    Output = ::std::sync::Arc<
+                        dyn ::std::any::Any + Send + Sync
+                    >
+                > + Send
+            >
+        > {
+            Box::pin(async {
+                let val: #ident = #construct;
+                ::std::sync::Arc::new(val) as ::std::sync::Arc<dyn ::std::any::Any + Send + Sync>
+            })
+        }
+
+        #[allow(non_snake_case)]
+        fn #def_fn_name() -> ::exum::StateDef {
+            ::exum::StateDef {
+                type_id: ::std::any::TypeId::of::<#ident>(),
+                prewarm: false,
+                init_fn: #init_fn_name,
+            }
+        }
+
+        ::inventory::submit! {
+            ::exum::StateDefFn(#def_fn_name)
+        }
+    })
+}
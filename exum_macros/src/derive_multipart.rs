@@ -0,0 +1,144 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Expr, Fields, LitStr, Type};
+
+fn extract_inner(ty: &Type, wrapper: &str) -> Option<Type> {
+    if let Type::Path(type_path) = ty
+        && let Some(seg) = type_path.path.segments.last()
+        && seg.ident == wrapper
+        && let syn::PathArguments::AngleBracketed(args) = &seg.arguments
+        && let Some(syn::GenericArgument::Type(inner_ty)) = args.args.first()
+    {
+        return Some(inner_ty.clone());
+    }
+    None
+}
+
+fn is_uploaded_file(ty: &Type) -> bool {
+    matches!(ty, Type::Path(tp) if tp.path.segments.last().is_some_and(|s| s.ident == "UploadedFile"))
+}
+
+fn opt(e: Option<Expr>) -> TokenStream {
+    match e {
+        Some(e) => quote! { ::std::option::Option::Some((#e) as u64) },
+        None => quote! { ::std::option::Option::None },
+    }
+}
+
+pub fn derive_multipart(input: DeriveInput) -> syn::Result<TokenStream> {
+    let ident = &input.ident;
+    let fields = match &input.data {
+        Data::Struct(s) => match &s.fields {
+            Fields::Named(named) => &named.named,
+            _ => return Err(syn::Error::new_spanned(ident, "#[derive(MultipartForm)] only supports structs with named fields")),
+        },
+        _ => return Err(syn::Error::new_spanned(ident, "#[derive(MultipartForm)] only supports structs")),
+    };
+
+    let mut max_total_size = None;
+    let mut memory_threshold = None;
+    let mut deny_unknown_fields = false;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("multipart")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("max_total_size") {
+                max_total_size = Some(meta.value()?.parse::<Expr>()?);
+            } else if meta.path.is_ident("memory_threshold") {
+                memory_threshold = Some(meta.value()?.parse::<Expr>()?);
+            } else if meta.path.is_ident("deny_unknown_fields") {
+                deny_unknown_fields = true;
+            } else {
+                return Err(meta.error("expected `max_total_size`, `memory_threshold` or `deny_unknown_fields`"));
+            }
+            Ok(())
+        })?;
+    }
+    let max_total_size = opt(max_total_size);
+    let memory_threshold = match memory_threshold {
+        Some(e) => quote! { (#e) as u64 },
+        None => quote! { ::exum::multipart::DEFAULT_MEMORY_THRESHOLD },
+    };
+
+    let mut spec_arms = Vec::new();
+    let mut inits = Vec::new();
+    for field in fields {
+        let field_ident = field.ident.as_ref().unwrap();
+        let mut name = field_ident.to_string();
+        let mut max_size = None;
+        let mut content_types: Vec<LitStr> = Vec::new();
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("multipart")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    name = meta.value()?.parse::<LitStr>()?.value();
+                } else if meta.path.is_ident("max_size") {
+                    max_size = Some(meta.value()?.parse::<Expr>()?);
+                } else if meta.path.is_ident("content_types") {
+                    let content;
+                    let value = meta.value()?;
+                    syn::bracketed!(content in value);
+                    content_types.extend(
+                        content.parse_terminated(|p| p.parse::<LitStr>(), syn::Token![,])?,
+                    );
+                } else {
+                    return Err(meta.error("expected `rename`, `max_size` or `content_types`"));
+                }
+                Ok(())
+            })?;
+        }
+        let max_size = opt(max_size);
+
+        let ty = &field.ty;
+        let (is_file, init) = if is_uploaded_file(ty) {
+            (true, quote! { data.file(#name)? })
+        } else if extract_inner(ty, "Option").is_some_and(|t| is_uploaded_file(&t)) {
+            (true, quote! { data.file_opt(#name) })
+        } else if extract_inner(ty, "Vec").is_some_and(|t| is_uploaded_file(&t)) {
+            (true, quote! { data.files(#name) })
+        } else if let Some(inner) = extract_inner(ty, "Option") {
+            (false, quote! { data.text_opt::<#inner>(#name)? })
+        } else if let Some(inner) = extract_inner(ty, "Vec") {
+            (false, quote! { data.texts::<#inner>(#name)? })
+        } else {
+            (false, quote! { data.text::<#ty>(#name)? })
+        };
+        if !content_types.is_empty() && !is_file {
+            return Err(syn::Error::new_spanned(field_ident, "`content_types` only applies to `UploadedFile` fields"));
+        }
+        let kind = if is_file { quote! { File } } else { quote! { Text } };
+
+        spec_arms.push(quote! {
+            #name => ::std::option::Option::Some(::exum::multipart::FieldSpec {
+                kind: ::exum::multipart::FieldKind::#kind,
+                max_size: #max_size,
+                content_types: &[#(#content_types),*],
+            }),
+        });
+        inits.push(quote! { #field_ident: #init });
+    }
+
+    Ok(quote! {
+        impl ::exum::multipart::MultipartForm for #ident {
+            fn limits() -> ::exum::multipart::FormLimits {
+                ::exum::multipart::FormLimits {
+                    max_total_size: #max_total_size,
+                    memory_threshold: #memory_threshold,
+                    deny_unknown_fields: #deny_unknown_fields,
+                }
+            }
+
+            fn field_spec(name: &str) -> ::std::option::Option<::exum::multipart::FieldSpec> {
+                match name {
+                    #(#spec_arms)*
+                    _ => ::std::option::Option::None,
+                }
+            }
+
+            fn from_data(
+                mut data: ::exum::multipart::MultipartData,
+            ) -> ::std::result::Result<Self, ::exum::rejection::ExumRejection> {
+                ::std::result::Result::Ok(Self {
+                    #(#inits),*
+                })
+            }
+        }
+    })
+}
@@ -1,13 +1,14 @@
 use proc_macro::TokenStream;
-use syn::{parse_macro_input, LitStr};
+use quote::quote;
 
-use crate::route;
+use crate::{route, utils::parse_shorthand};
 
+/// `#[get("/path", key = value, ..)]` becomes `#[route(path = "/path", method = "GET", key = value, ..)]`.
 pub fn make_wrapper(attr: TokenStream, item: TokenStream, method: &str) -> TokenStream {
-    let lit = parse_macro_input!(attr as LitStr);
+    let (lit, rest) = match parse_shorthand(attr.into()) {
+        Ok(parsed) => parsed,
+        Err(e) => return e.to_compile_error().into(),
+    };
 
-    let new_attr = format!("path = \"{}\", method = \"{}\"", lit.value(), method);
-    let new_attr_ts: TokenStream = new_attr.parse().unwrap();
-
-    route(new_attr_ts, item)
-}
\ No newline at end of file
+    route(quote!(path = #lit, method = #method, #rest).into(), item)
+}
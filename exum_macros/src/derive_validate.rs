@@ -0,0 +1,138 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    Data, DeriveInput, Expr, Fields, LitStr, Type,
+    meta::ParseNestedMeta,
+};
+
+use crate::handle_input::extract_inner_option;
+
+fn parse_bounds(meta: &ParseNestedMeta) -> syn::Result<(Option<Expr>, Option<Expr>)> {
+    let mut min = None;
+    let mut max = None;
+    meta.parse_nested_meta(|inner| {
+        if inner.path.is_ident("min") {
+            min = Some(inner.value()?.parse::<Expr>()?);
+        } else if inner.path.is_ident("max") {
+            max = Some(inner.value()?.parse::<Expr>()?);
+        } else if inner.path.is_ident("equal") {
+            let v = inner.value()?.parse::<Expr>()?;
+            min = Some(v.clone());
+            max = Some(v);
+        } else {
+            return Err(inner.error("expected `min`, `max` or `equal`"));
+        }
+        Ok(())
+    })?;
+    Ok((min, max))
+}
+
+fn opt(e: Option<Expr>) -> TokenStream {
+    match e {
+        Some(e) => quote! { ::std::option::Option::Some(#e) },
+        None => quote! { ::std::option::Option::None },
+    }
+}
+
+/// Parses the contents of `#[validate(...)]` into checks against a `value` binding.
+fn parse_rules(meta: &ParseNestedMeta, checks: &mut Vec<TokenStream>) -> syn::Result<()> {
+    if meta.path.is_ident("length") {
+        let (min, max) = parse_bounds(meta)?;
+        let (min, max) = (opt(min), opt(max));
+        checks.push(quote! { ::exum::validate::check_length(value, #min, #max, &path, errors); });
+    } else if meta.path.is_ident("range") {
+        let (min, max) = parse_bounds(meta)?;
+        let (min, max) = (opt(min), opt(max));
+        checks.push(quote! { ::exum::validate::check_range(value, #min, #max, &path, errors); });
+    } else if meta.path.is_ident("regex") {
+        let lit: LitStr = meta.value()?.parse()?;
+        checks.push(quote! {
+            {
+                static RE: ::std::sync::LazyLock<::exum::validate::Regex> =
+                    ::std::sync::LazyLock::new(|| ::exum::validate::Regex::new(#lit).expect("invalid regex in #[validate]"));
+                ::exum::validate::check_regex(value, &RE, &path, errors);
+            }
+        });
+    } else if meta.path.is_ident("email") {
+        checks.push(quote! { ::exum::validate::check_email(value, &path, errors); });
+    } else if meta.path.is_ident("nested") {
+        checks.push(quote! { ::exum::validate::Validate::validate_into(value, &path, errors); });
+    } else if meta.path.is_ident("custom") {
+        let f: syn::Path = meta.value()?.parse()?;
+        checks.push(quote! { ::exum::validate::check_custom(value, #f, &path, errors); });
+    } else {
+        return Err(meta.error("unknown validation rule — supported: length, range, regex, email, nested, custom"));
+    }
+    Ok(())
+}
+
+fn serde_rename(field: &syn::Field) -> Option<String> {
+    let mut rename = None;
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("serde")) {
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                rename = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.input.peek(syn::Token![=]) {
+                let _: Expr = meta.value()?.parse()?;
+            }
+            Ok(())
+        });
+    }
+    rename
+}
+
+fn field_checks(field_expr: TokenStream, ty: &Type, name: &str, checks: Vec<TokenStream>) -> TokenStream {
+    if checks.is_empty() {
+        return quote! {};
+    }
+    let guarded = if extract_inner_option(ty).is_some() {
+        quote! {
+            if let ::std::option::Option::Some(value) = &#field_expr {
+                #(#checks)*
+            }
+        }
+    } else {
+        quote! {
+            let value = &#field_expr;
+            #(#checks)*
+        }
+    };
+    quote! {
+        {
+            let path = ::exum::validate::join_path(prefix, #name);
+            #guarded
+        }
+    }
+}
+
+pub fn derive_validate(input: DeriveInput) -> syn::Result<TokenStream> {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let fields = match &input.data {
+        Data::Struct(s) => match &s.fields {
+            Fields::Named(named) => &named.named,
+            _ => return Err(syn::Error::new_spanned(ident, "#[derive(Validate)] only supports structs with named fields")),
+        },
+        _ => return Err(syn::Error::new_spanned(ident, "#[derive(Validate)] only supports structs")),
+    };
+
+    let mut body = Vec::new();
+    for field in fields {
+        let mut checks = Vec::new();
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("validate")) {
+            attr.parse_nested_meta(|meta| parse_rules(&meta, &mut checks))?;
+        }
+        let field_ident = field.ident.as_ref().unwrap();
+        let name = serde_rename(field).unwrap_or_else(|| field_ident.to_string());
+        body.push(field_checks(quote! { self.#field_ident }, &field.ty, &name, checks));
+    }
+
+    Ok(quote! {
+        impl #impl_generics ::exum::validate::Validate for #ident #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn validate_into(&self, prefix: &str, errors: &mut ::exum::validate::ValidationErrors) {
+                #(#body)*
+            }
+        }
+    })
+}
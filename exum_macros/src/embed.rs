@@ -0,0 +1,95 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use quote::quote;
+use syn::LitStr;
+
+/// Extensions of precompressed siblings and the encodings they map to.
+const ENCODED: &[(&str, &str)] = &[("br", "Brotli"), ("gz", "Gzip"), ("zst", "Zstd")];
+
+fn walk(dir: &Path, rel: &str, files: &mut BTreeSet<String>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let rel = if rel.is_empty() { name } else { format!("{}/{}", rel, name) };
+        let path = entry.path();
+        if path.is_dir() {
+            walk(&path, &rel, files)?;
+        } else if path.is_file() {
+            files.insert(rel);
+        }
+    }
+    Ok(())
+}
+
+/// FNV-1a, stable across compilers so builds of the same assets get the same tags.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, b| (hash ^ *b as u64).wrapping_mul(0x100000001b3))
+}
+
+fn content_type(path: &str) -> String {
+    let mime = mime_guess::from_path(path).first_or_octet_stream();
+    if mime.type_() == mime_guess::mime::TEXT {
+        format!("{}; charset=utf-8", mime)
+    } else {
+        mime.to_string()
+    }
+}
+
+/// `embed!("./dist")`, relative to the manifest directory of the calling crate.
+pub fn expand(dir: LitStr) -> syn::Result<proc_macro2::TokenStream> {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let root = PathBuf::from(manifest_dir).join(dir.value());
+    let root = root
+        .canonicalize()
+        .map_err(|e| syn::Error::new(dir.span(), format!("cannot embed `{}`: {}", root.display(), e)))?;
+    if !root.is_dir() {
+        return Err(syn::Error::new(dir.span(), format!("`{}` is not a directory", root.display())));
+    }
+    let mut files = BTreeSet::new();
+    walk(&root, "", &mut files)
+        .map_err(|e| syn::Error::new(dir.span(), format!("cannot read `{}`: {}", root.display(), e)))?;
+
+    let is_variant = |rel: &str| {
+        ENCODED.iter().any(|(ext, _)| {
+            rel.strip_suffix(ext)
+                .and_then(|base| base.strip_suffix('.'))
+                .is_some_and(|base| files.contains(base))
+        })
+    };
+    let mut entries = Vec::new();
+    for rel in files.iter().filter(|rel| !is_variant(rel)) {
+        let abs = root.join(rel);
+        let bytes = std::fs::read(&abs)
+            .map_err(|e| syn::Error::new(dir.span(), format!("cannot read `{}`: {}", abs.display(), e)))?;
+        let modified = std::fs::metadata(&abs)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs());
+        let abs = abs.to_string_lossy().into_owned();
+        let etag = format!("\"{:016x}\"", fnv1a(&bytes));
+        let content_type = content_type(rel);
+        let encoded = ENCODED.iter().filter(|(ext, _)| files.contains(&format!("{}.{}", rel, ext))).map(|(ext, encoding)| {
+            let encoding = syn::Ident::new(encoding, dir.span());
+            let abs = format!("{}.{}", abs, ext);
+            quote! { (::exum::layers::Encoding::#encoding, include_bytes!(#abs)) }
+        });
+        entries.push(quote! {
+            ::exum::layers::embed::EmbeddedFile {
+                path: #rel,
+                bytes: include_bytes!(#abs),
+                content_type: #content_type,
+                etag: #etag,
+                modified: #modified,
+                encoded: &[#(#encoded),*],
+            }
+        });
+    }
+    let root = root.to_string_lossy().into_owned();
+    Ok(quote! {
+        ::exum::layers::embed::EmbeddedAssets::new(#root, &[#(#entries),*], cfg!(debug_assertions))
+    })
+}
@@ -1,22 +1,83 @@
+use convert_case::{Case, Casing};
 use quote::{format_ident, quote};
-use syn::{parse_quote, FnArg, Ident, Meta, Pat, PatType, Stmt, Type};
-
-fn extract_inner_option(ty: &Type) -> Option<Type> {
-    if let Type::Path(type_path) = ty {
-        if let Some(seg) = type_path.path.segments.last() {
-            if seg.ident == "Option" {
-                if let syn::PathArguments::AngleBracketed(args) = &seg.arguments {
-                    if let Some(syn::GenericArgument::Type(inner_ty)) = args.args.first() {
-                        return Some(inner_ty.clone());
-                    }
-                }
+use syn::{parse_quote, Attribute, Expr, FnArg, GenericArgument, Ident, Item, LitStr, Meta, Pat, PatType, PathArguments, Stmt, Type};
+
+use crate::{route_core::PathConstraintSpec, utils::is_arc_type};
+
+pub fn extract_inner_option(ty: &Type) -> Option<Type> {
+    if let Type::Path(type_path) = ty
+        && let Some(seg) = type_path.path.segments.last()
+        && seg.ident == "Option"
+        && let syn::PathArguments::AngleBracketed(args) = &seg.arguments
+        && let Some(syn::GenericArgument::Type(inner_ty)) = args.args.first()
+    {
+        return Some(inner_ty.clone());
+    }
+    None
+}
+
+/// Whether `ty` is a primitive or string, possibly in an `Option`, `Vec` or reference, which
+/// `nested` validation cannot apply to.
+fn is_scalar_type(ty: &Type) -> bool {
+    match ty {
+        Type::Reference(reference) => is_scalar_type(&reference.elem),
+        Type::Path(tp) => {
+            let Some(seg) = tp.path.segments.last() else {
+                return false;
+            };
+            if let PathArguments::AngleBracketed(args) = &seg.arguments {
+                return (seg.ident == "Option" || seg.ident == "Vec")
+                    && matches!(args.args.first(), Some(GenericArgument::Type(inner)) if is_scalar_type(inner));
             }
+            [
+                "String", "str", "bool", "char", "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32",
+                "u64", "u128", "usize", "f32", "f64",
+            ]
+            .iter()
+            .any(|name| seg.ident == name)
         }
+        _ => false,
     }
-    None
 }
 
-pub fn handle_q_attr(pat_type: &PatType, q_fields: &mut Vec<syn::Field>) {
+/// Parses `#[q(validate)]` / `#[q(validate(...))]` into the `#[validate]` attribute of the generated field.
+///
+/// The bare form means `nested` and is rejected on scalars, which have no fields to validate.
+fn q_validate_attr(pat_type: &PatType) -> Option<Attribute> {
+    let mut rules = None;
+    for attr in pat_type.attrs.iter().filter(|a| a.path().is_ident("q")) {
+        if let Meta::Path(_) = &attr.meta {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("validate") {
+                if meta.input.peek(syn::token::Paren) {
+                    let content;
+                    syn::parenthesized!(content in meta.input);
+                    rules = Some(content.parse::<proc_macro2::TokenStream>()?);
+                } else if is_scalar_type(&pat_type.ty) {
+                    let ty = &pat_type.ty;
+                    return Err(meta.error(format!(
+                        "bare #[q(validate)] validates the fields of a nested struct, `{}` needs explicit rules, e.g. #[q(validate(length(min = 1)))] or #[q(validate(range(min = 1)))]",
+                        quote! { #ty }.to_string().replace(' ', "")
+                    )));
+                } else {
+                    rules = Some(quote! { nested });
+                }
+                Ok(())
+            } else {
+                Err(meta.error("#[q] unknown option — supported: validate"))
+            }
+        })
+        .unwrap_or_else(|e| panic!("{}", e));
+    }
+    rules.map(|rules| parse_quote! { #[validate(#rules)] })
+}
+
+/// Returns `true` when the parameter asked for validation.
+pub fn handle_q_attr(pat_type: &PatType, q_fields: &mut Vec<syn::Field>) -> bool {
+    let validate_attr = q_validate_attr(pat_type);
+    let validate = validate_attr.is_some();
     match &*pat_type.pat {
         Pat::Ident(pat_ident) => {
             let name = pat_ident.ident.clone();
@@ -25,7 +86,7 @@ pub fn handle_q_attr(pat_type: &PatType, q_fields: &mut Vec<syn::Field>) {
                 Some(inner_ty) => parse_quote! { Option<#inner_ty> },
                 None => ty,
             };
-            q_fields.push(parse_quote! { pub #name: #ty });
+            q_fields.push(parse_quote! { #validate_attr pub #name: #ty });
         }
         Pat::Tuple(tuple) => {
             if let Type::Tuple(ty_tuple) = &*pat_type.ty {
@@ -44,22 +105,114 @@ pub fn handle_q_attr(pat_type: &PatType, q_fields: &mut Vec<syn::Field>) {
                         );
                     }
                 }
+                if validate {
+                    panic!("#[q(validate)] is not supported on tuple patterns, annotate each parameter instead");
+                }
             } else {
                 panic!("#[q] tuple pattern must be typed, e.g. (a: String, b: i32)");
             }
         }
         _ => panic!("#[q] only supports simple ident or tuple patterns"),
     }
+    validate
+}
+
+/// Emits the marker type that names a generated extractor for `::exum::rejection::Param`.
+/// With `error`, rejections are converted into that type before becoming a response.
+pub fn param_marker(marker: &Ident, name: &str, error: Option<&Type>) -> Vec<Item> {
+    let respond = error.map(|error| {
+        quote! {
+            fn respond(rejection: ::exum::rejection::ExumRejection) -> axum::response::Response {
+                ::exum::rejection::respond_with::<#error>(rejection)
+            }
+        }
+    });
+    vec![
+        parse_quote! {
+            #[doc(hidden)]
+            struct #marker;
+        },
+        parse_quote! {
+            impl ::exum::rejection::ParamName for #marker {
+                const NAME: &'static str = #name;
+                #respond
+            }
+        },
+    ]
+}
+
+/// Routes an extractor argument's rejection through `::exum::rejection::Param`.
+pub fn wrap_param(arg: FnArg, marker: &Ident) -> FnArg {
+    match arg {
+        FnArg::Typed(PatType { pat, ty, .. }) => parse_quote! {
+            ::exum::rejection::Param(#pat, _): ::exum::rejection::Param<#ty, #marker>
+        },
+        receiver => receiver,
+    }
+}
+
+pub struct PartsField {
+    pub ident: Ident,
+    pub ty: Type,
+    pub init: Expr,
+}
+
+fn parts_param_name(pat_type: &PatType, attr_name: &str) -> Option<String> {
+    let attr = pat_type.attrs.iter().find(|a| a.path().is_ident(attr_name))?;
+    match &attr.meta {
+        Meta::Path(_) => None,
+        _ => Some(attr.parse_args::<LitStr>().unwrap_or_else(|_| {
+            panic!("#[{attr_name}] expects an optional string literal, e.g. #[{attr_name}(\"name\")]")
+        }).value()),
+    }
+}
+
+pub fn handle_h_attr(pat_type: &PatType, parts_fields: &mut Vec<PartsField>) {
+    if let Pat::Ident(pat_ident) = &*pat_type.pat {
+        let ident = pat_ident.ident.clone();
+        let name = parts_param_name(pat_type, "h")
+            .unwrap_or_else(|| ident.to_string().to_case(Case::Kebab));
+        let ty = *pat_type.ty.clone();
+        let init = match extract_inner_option(&ty) {
+            Some(inner_ty) => parse_quote! { ::exum::extract::header_opt::<#inner_ty>(&parts.headers, #name)? },
+            None => parse_quote! { ::exum::extract::header::<#ty>(&parts.headers, #name)? },
+        };
+        parts_fields.push(PartsField { ident, ty, init });
+    } else {
+        panic!("#[h] only supports simple identifier pattern, e.g. `user_agent: String`");
+    }
+}
+
+pub fn handle_cookie_attr(pat_type: &PatType, parts_fields: &mut Vec<PartsField>) {
+    if let Pat::Ident(pat_ident) = &*pat_type.pat {
+        let ident = pat_ident.ident.clone();
+        let name = parts_param_name(pat_type, "cookie").unwrap_or_else(|| ident.to_string());
+        let ty = *pat_type.ty.clone();
+        let init = match extract_inner_option(&ty) {
+            Some(inner_ty) => parse_quote! { ::exum::extract::cookie_opt::<#inner_ty>(&parts.headers, #name)? },
+            None => parse_quote! { ::exum::extract::cookie::<#ty>(&parts.headers, #name)? },
+        };
+        parts_fields.push(PartsField { ident, ty, init });
+    } else {
+        panic!("#[cookie] only supports simple identifier pattern, e.g. `session: String`");
+    }
 }
 
 enum BodyType {
     Json,
     Form,
     Multipart,
+    Bytes,
+    Text,
+    MsgPack,
+    Cbor,
+    Xml,
+    Auto,
 }
 
-pub fn handle_b_attr(pat_type: &PatType, other_inputs: &mut Vec<FnArg>) {
+pub fn handle_b_attr(pat_type: &PatType) -> FnArg {
     let mut mode = BodyType::Json;
+    let mut validate = false;
     for attr in &pat_type.attrs {
         if attr.path().is_ident("b") {
             if let Meta::Path(_) = &attr.meta {
@@ -72,8 +225,22 @@ pub fn handle_b_attr(pat_type: &PatType, other_inputs: &mut Vec<FnArg>) {
                         mode = BodyType::Form;
                     } else if meta.path.is_ident("multipart") {
                         mode = BodyType::Multipart;
+                    } else if meta.path.is_ident("bytes") {
+                        mode = BodyType::Bytes;
+                    } else if meta.path.is_ident("text") {
+                        mode = BodyType::Text;
+                    } else if meta.path.is_ident("msgpack") {
+                        mode = BodyType::MsgPack;
+                    } else if meta.path.is_ident("cbor") {
+                        mode = BodyType::Cbor;
+                    } else if meta.path.is_ident("xml") {
+                        mode = BodyType::Xml;
+                    } else if meta.path.is_ident("auto") {
+                        mode = BodyType::Auto;
+                    } else if meta.path.is_ident("validate") {
+                        validate = true;
                     } else {
-                        panic!("#[b] unknown option — supported: json, form, multipart");
+                        panic!("#[b] unknown option — supported: json, form, multipart, bytes, text, msgpack, cbor, xml, auto, validate");
                     }
                     Ok(())
                 })
@@ -91,39 +258,93 @@ pub fn handle_b_attr(pat_type: &PatType, other_inputs: &mut Vec<FnArg>) {
         };
 
         let arg: FnArg = match mode {
+            BodyType::Json if validate => {
+                if is_option {
+                    parse_quote! { ::exum::validate::ValidJson(#name): ::exum::validate::ValidJson<Option<#ty>> }
+                } else {
+                    parse_quote! { ::exum::validate::ValidJson(#name): ::exum::validate::ValidJson<#ty> }
+                }
+            }
             BodyType::Json => {
                 if is_option {
-                    parse_quote! { axum::extract::Json(Option<#name>): axum::extract::Json<Option<#ty>> }
+                    parse_quote! { axum::extract::Json(#name): axum::extract::Json<Option<#ty>> }
                 } else {
                     parse_quote! { axum::extract::Json(#name): axum::extract::Json<#ty> }
                 }
             }
+            BodyType::Form if validate => {
+                if is_option {
+                    parse_quote! { ::exum::validate::ValidForm(#name): ::exum::validate::ValidForm<Option<#ty>> }
+                } else {
+                    parse_quote! { ::exum::validate::ValidForm(#name): ::exum::validate::ValidForm<#ty> }
+                }
+            }
             BodyType::Form => {
                 if is_option {
-                    parse_quote! { axum::extract::Form(Option<#name>): axum::extract::Form<Option<#ty>> }
+                    parse_quote! { axum::extract::Form(#name): axum::extract::Form<Option<#ty>> }
                 } else {
                     parse_quote! { axum::extract::Form(#name): axum::extract::Form<#ty> }
                 }
             }
+            BodyType::Multipart if validate => {
+                panic!("#[b(multipart)] does not support `validate`");
+            }
             BodyType::Multipart => {
                 if is_option {
-                    parse_quote! { axum::extract::Multipart(Option<#name>): axum::extract::Multipart<Option<#ty>> }
+                    panic!("#[b(multipart)] does not support Option<T>, use Option fields inside the form instead");
+                }
+                parse_quote! { ::exum::multipart::TypedMultipart(#name): ::exum::multipart::TypedMultipart<#ty> }
+            }
+            BodyType::Bytes | BodyType::Text if is_option || validate => {
+                panic!("#[b(bytes)] and #[b(text)] take the raw body and support neither Option<T> nor `validate`");
+            }
+            BodyType::Bytes | BodyType::Text => {
+                parse_quote! { #name: #ty }
+            }
+            BodyType::MsgPack | BodyType::Cbor | BodyType::Xml | BodyType::Auto if validate => {
+                panic!("`validate` is only supported for json and form bodies");
+            }
+            BodyType::MsgPack | BodyType::Cbor | BodyType::Xml | BodyType::Auto => {
+                let extractor = match mode {
+                    BodyType::MsgPack => quote! { ::exum::body::MsgPack },
+                    BodyType::Cbor => quote! { ::exum::body::Cbor },
+                    BodyType::Xml => quote! { ::exum::body::Xml },
+                    _ => quote! { ::exum::body::Auto },
+                };
+                if is_option {
+                    parse_quote! { #extractor(#name): #extractor<Option<#ty>> }
                 } else {
-                    parse_quote! { axum::extract::Multipart(#name): axum::extract::Multipart<#ty> }
+                    parse_quote! { #extractor(#name): #extractor<#ty> }
                 }
             }
         };
 
-        other_inputs.push(arg);
+        arg
     } else {
         panic!("#[b] only supports simple identifier pattern, e.g. `data: MyType`");
     }
 }
 
+pub fn handle_current_user_attr(pat_type: &PatType) -> FnArg {
+    if let Pat::Ident(pat_ident) = &*pat_type.pat {
+        let name = pat_ident.ident.clone();
+        match extract_inner_option(&pat_type.ty) {
+            Some(inner) => parse_quote! { ::exum::auth::MaybeUser(#name): ::exum::auth::MaybeUser<#inner> },
+            None => {
+                let ty = &pat_type.ty;
+                parse_quote! { ::exum::auth::CurrentUser(#name): ::exum::auth::CurrentUser<#ty> }
+            }
+        }
+    } else {
+        panic!("#[current_user] only supports simple identifier pattern, e.g. `user: Claims`");
+    }
+}
+
+/// Injects a dependency parameter as `Arc<T>`; a parameter written as `Arc<T>` resolves `T`.
 pub fn handle_dep_attr(pat_type: &PatType, inject_segs: &mut Vec<Stmt>) {
     if let Pat::Ident(pat_ident) = &*pat_type.pat {
         let name = pat_ident.ident.clone();
-        let ty = pat_type.ty.clone();
+        let ty = is_arc_type(&pat_type.ty).unwrap_or(&pat_type.ty);
         inject_segs.push(parse_quote!{
             let mut #name = ::exum::global_container().get::<#ty>().await;
         });
@@ -149,4 +370,64 @@ pub fn handle_trait_dep_attr(pat_type: &PatType, trait_ident: &Ident, inject_seg
             let mut #name = #name.clone();
         });
     }
-}
\ No newline at end of file
+}
+const PATH_TYPES: &[(&str, &str)] = &[
+    ("u8", "U8"), ("u16", "U16"), ("u32", "U32"), ("u64", "U64"), ("u128", "U128"), ("usize", "Usize"),
+    ("i8", "I8"), ("i16", "I16"), ("i32", "I32"), ("i64", "I64"), ("i128", "I128"), ("isize", "Isize"),
+    ("f32", "F32"), ("f64", "F64"), ("bool", "Bool"), ("uuid", "Uuid"), ("date", "Date"),
+];
+
+/// Emits the `::exum::path::PathCheck` argument validating the constraints declared in the path.
+/// Named constraints (`u64`, `date`, ..) map to `PathType`, anything else must be a valid regex.
+pub fn path_check_param(marker: &Ident, constraints: &[PathConstraintSpec]) -> (FnArg, Item) {
+    let rules = constraints.iter().map(|c| {
+        let name = &c.name;
+        match PATH_TYPES.iter().find(|(ty, _)| *ty == c.rule) {
+            Some((_, variant)) => {
+                let variant = format_ident!("{}", variant);
+                quote! { ::exum::path::PathRule::Type(::exum::path::PathType::#variant) }
+            }
+            None => {
+                if let Err(e) = regex::Regex::new(&c.rule) {
+                    panic!("invalid pattern for path parameter `{}`: {}", name, e);
+                }
+                let pattern = &c.rule;
+                quote! { ::exum::path::PathRule::Pattern(::exum::path::Pattern::new(#pattern)) }
+            }
+        }
+    });
+    let names = constraints.iter().map(|c| &c.name);
+    let len = constraints.len();
+    let item = parse_quote! {
+        impl ::exum::path::PathConstraints for #marker {
+            fn constraints() -> &'static [::exum::path::PathConstraint] {
+                static CONSTRAINTS: [::exum::path::PathConstraint; #len] = [
+                    #(::exum::path::PathConstraint::new(#names, #rules)),*
+                ];
+                &CONSTRAINTS
+            }
+        }
+    };
+    let arg = wrap_param(parse_quote! { _: ::exum::path::PathCheck<#marker> }, marker);
+    (arg, item)
+}
+
+/// Names of the `#[q]` parameters, the query string `url_for!` accepts.
+pub fn query_param_names<'a>(inputs: impl IntoIterator<Item = &'a FnArg>) -> Vec<String> {
+    let mut names = Vec::new();
+    for input in inputs {
+        let FnArg::Typed(pat_type) = input else { continue };
+        if !pat_type.attrs.iter().any(|a| a.path().is_ident("q")) {
+            continue;
+        }
+        match &*pat_type.pat {
+            Pat::Ident(ident) => names.push(ident.ident.to_string()),
+            Pat::Tuple(tuple) => names.extend(tuple.elems.iter().filter_map(|p| match p {
+                Pat::Ident(ident) => Some(ident.ident.to_string()),
+                _ => None,
+            })),
+            _ => {}
+        }
+    }
+    names
+}
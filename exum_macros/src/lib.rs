@@ -1,3 +1,5 @@
+use std::hash::{Hash, Hasher};
+
 use proc_macro::{TokenStream};
 use proc_macro2::Span;
 use quote::{format_ident, quote};
@@ -6,6 +8,8 @@ use syn::{ parse_macro_input, punctuated::Punctuated, ItemFn, ItemImpl, Meta, Pa
 mod handle_input;
 
 mod route_core;
+mod url_for;
+mod embed;
 
 
 
@@ -14,9 +18,9 @@ mod route_core;
 pub fn route(args: TokenStream, item: TokenStream) -> TokenStream {
     let args = parse_args(args);
     let mut input_fn = parse_macro_input!(item as ItemFn);
-    let (sig_token, collect_token, q_struct) = make_route(args, &mut input_fn);
+    let (sig_token, collect_token, extra_items) = make_route(args, &mut input_fn);
     quote! {
-        #q_struct
+        #(#extra_items)*
         #sig_token
         #collect_token
     }
@@ -26,7 +30,7 @@ pub fn route(args: TokenStream, item: TokenStream) -> TokenStream {
 mod derive_route_macro;
 use derive_route_macro::make_wrapper;
 
-use crate::{handle_input::handle_dep_attr, route_core::{controller_update_attr, make_route, make_route_from_impl_fn, parse_args}, utils::{RouteAttrType}};
+use crate::{handle_input::handle_dep_attr, route_core::{controller_update_attr, make_controller_route, make_route, parse_args, split_constraints, RouteRegistration}, utils::{RouteAttrType}};
 
 #[proc_macro_attribute]
 pub fn get(attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -161,33 +165,34 @@ pub fn service(args: TokenStream, input: TokenStream) -> TokenStream {
     } else { None };
 
     for item in &input_struct.items {
-        if let syn::ImplItem::Fn(method) = item {
-            if method.sig.ident == "new" {
-                let args = &method.sig.inputs;
-                for arg in args {
-                    if let syn::FnArg::Typed(pat) = arg {
-                        let arg_ident = match &*pat.pat {
-                            Pat::Ident(PatIdent { ident, .. }) => ident.clone(),
-                            _ => {
-                                panic!("service method new argument must be Typed")
-                            }
-                        };
-                        depend_get_stmts.push(quote! {
-                            let #arg_ident = ::exum::global_container().get::<#pat.ty>().await.clone();
-                        });
-                        // if let Some(inner_ty) = is_arc_type(&pat.ty) {
-                        //     depend_get_stmts.push(quote! {
-                        //         let #arg_ident = ::exum::global_container().get::<#inner_ty>().await.locl().await;
-                        //     })
-                        // }else {
-                        //     panic!("service method new argument must be Arc<T> type");
-                            // let ty = pat.ty.clone();
-                            // depend_get_stmts.push(quote! {
-                            //     let #arg_ident = ::exum::global_container().get::<#ty>().await.as_ref().clone();
-                            // })
-                        // }
-                        arg_idents.push(arg_ident);
-                    }
+        if let syn::ImplItem::Fn(method) = item
+            && method.sig.ident == "new"
+        {
+            let args = &method.sig.inputs;
+            for arg in args {
+                if let syn::FnArg::Typed(pat) = arg {
+                    let arg_ident = match &*pat.pat {
+                        Pat::Ident(PatIdent { ident, .. }) => ident.clone(),
+                        _ => {
+                            panic!("service method new argument must be Typed")
+                        }
+                    };
+                    let inject = utils::inject_expr(&pat.ty);
+                    depend_get_stmts.push(quote! {
+                        let #arg_ident = #inject;
+                    });
+                    // if let Some(inner_ty) = is_arc_type(&pat.ty) {
+                    //     depend_get_stmts.push(quote! {
+                    //         let #arg_ident = ::exum::global_container().get::<#inner_ty>().await.locl().await;
+                    //     })
+                    // }else {
+                    //     panic!("service method new argument must be Arc<T> type");
+                        // let ty = pat.ty.clone();
+                        // depend_get_stmts.push(quote! {
+                        //     let #arg_ident = ::exum::global_container().get::<#ty>().await.as_ref().clone();
+                        // })
+                    // }
+                    arg_idents.push(arg_ident);
                 }
             }
         }
@@ -252,82 +257,270 @@ pub fn service(args: TokenStream, input: TokenStream) -> TokenStream {
     }.into()
 }
 
+/// Builds the URL of a route from its handler: `url_for!(get_user, id = 5, page = 2)`.
+///
+/// Path parameters are required, `#[q]` parameters optional; unknown or missing names fail to compile.
+/// Controller routes are named through the controller: `url_for!(UserController::get_user, id = 5)`.
+#[proc_macro]
+pub fn url_for(input: TokenStream) -> TokenStream {
+    parse_macro_input!(input as url_for::UrlFor)
+        .expand()
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Embeds a directory into the binary: `embed!("./dist")`, relative to the crate's `Cargo.toml`.
+///
+/// Expands to an `exum::layers::embed::EmbeddedAssets` with the content type, `ETag` and
+/// precompressed siblings (`.br`, `.gz`, `.zst`) of every file, served by `EmbeddedFileService`.
+/// Only the embedded files are tracked by Cargo: add `cargo:rerun-if-changed=dist` to the
+/// `build.rs` so new files are picked up.
+#[proc_macro]
+pub fn embed(input: TokenStream) -> TokenStream {
+    embed::expand(parse_macro_input!(input as syn::LitStr))
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+#[proc_macro_attribute]
+pub fn rejection_handler(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    let input_fn = parse_macro_input!(item as ItemFn);
+    let fn_name = &input_fn.sig.ident;
+    if input_fn.sig.asyncness.is_some() || input_fn.sig.inputs.len() != 1 {
+        return syn::Error::new_spanned(
+            &input_fn.sig,
+            "rejection handler must be a sync fn taking a single `ExumRejection`",
+        )
+        .to_compile_error()
+        .into();
+    }
+    let shim_name = format_ident!("__exum_rejection_handler_{}", fn_name);
+
+    quote! {
+        #input_fn
+
+        #[doc(hidden)]
+        #[allow(non_snake_case)]
+        fn #shim_name(err: ::exum::rejection::ExumRejection) -> axum::response::Response {
+            axum::response::IntoResponse::into_response(#fn_name(err))
+        }
+
+        ::inventory::submit! {
+            ::exum::rejection::RejectionHandlerDef {
+                handler: #shim_name,
+            }
+        }
+    }
+    .into()
+}
+
+/// Defines a middleware (`#[middleware]`) or attaches middlewares to a route / controller
+/// (`#[middleware(auth_check, ..)]`).
+///
+/// A middleware is an `async fn(req: Request, next: Next, ..)`; any other argument that is not an
+/// axum extractor is injected from the dependency container, like handler arguments.
+#[proc_macro_attribute]
+pub fn middleware(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if attr.is_empty() {
+        let input_fn = parse_macro_input!(item as ItemFn);
+        return middleware::make_injected_fn(input_fn, "middleware")
+            .unwrap_or_else(|e| e.to_compile_error())
+            .into();
+    }
+    let item = parse_macro_input!(item as syn::Item);
+    middleware::defer_to_route("middleware", attr.into(), item).into()
+}
+
+/// Wraps a route or every route of a controller with arbitrary tower layers: `#[layer(TimeoutLayer::new(..))]`.
+#[proc_macro_attribute]
+pub fn layer(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(item as syn::Item);
+    middleware::defer_to_route("layer", attr.into(), item).into()
+}
+
+/// Defines a guard (`#[guard]`) or protects a route / controller with guards (`#[guard(is_admin, ..)]`).
+///
+/// A guard is an `async fn(parts: &Parts, ..) -> Result<(), impl IntoResponse>` that runs before the
+/// handler; other arguments are injected from the dependency container.
+#[proc_macro_attribute]
+pub fn guard(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if attr.is_empty() {
+        let input_fn = parse_macro_input!(item as ItemFn);
+        return middleware::make_injected_fn(input_fn, "guard")
+            .unwrap_or_else(|e| e.to_compile_error())
+            .into();
+    }
+    let item = parse_macro_input!(item as syn::Item);
+    middleware::defer_to_route("guard", attr.into(), item).into()
+}
+
+mod middleware;
+mod controller_args;
 mod utils;
 
+mod derive_validate;
+mod derive_multipart;
+mod derive_controller;
+
+#[proc_macro_derive(Validate, attributes(validate))]
+pub fn derive_validate(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as syn::DeriveInput);
+    derive_validate::derive_validate(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Registers a controller struct with the DI container. Every field is injected when the controller is
+/// first used (`Arc<T>` shares the instance, other types are cloned), `#[inject(default)]` uses
+/// `Default::default()` instead.
+#[proc_macro_derive(Controller, attributes(inject))]
+pub fn derive_controller(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as syn::DeriveInput);
+    derive_controller::derive_controller(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+#[proc_macro_derive(MultipartForm, attributes(multipart))]
+pub fn derive_multipart_form(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as syn::DeriveInput);
+    derive_multipart::derive_multipart(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Route arguments of a method inside a controller with the prefix applied, `None` for other attributes.
+fn controller_route_args(attr: &syn::Attribute, prefix: &str) -> syn::Result<Option<Punctuated<Meta, Token![,]>>> {
+    let Some(ident) = attr.path().get_ident() else {
+        return Ok(None);
+    };
+    let attr: syn::Attribute = match utils::valid_route_macro(&ident.to_string()) {
+        RouteAttrType::Route => {
+            let new_tokens = controller_update_attr(attr, prefix)?;
+            syn::parse_quote!(#[#ident(#new_tokens)])
+        }
+        RouteAttrType::Derive(method) => {
+            let (lit, rest) = utils::parse_shorthand(attr.meta.require_list()?.tokens.clone())?;
+            let joined = utils::join_path(prefix, &lit.value());
+            syn::parse_quote!(#[#ident(path = #joined, method = #method, #rest)])
+        }
+        RouteAttrType::Not => return Ok(None),
+    };
+    attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated).map(Some)
+}
+
 #[proc_macro_attribute]
 pub fn controller(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let prefix = if attr.is_empty() {
-        "".to_string()
-    }else {
-        parse_macro_input!(attr as syn::LitStr).value()
-    };
+    let mut controller_args = parse_macro_input!(attr as controller_args::ControllerArgs);
     let mut impl_block = parse_macro_input!(item as ItemImpl);
+    match middleware::RouteLayers::take(&mut impl_block.attrs) {
+        Ok(layers) => {
+            controller_args.layers.layers.extend(layers.layers);
+            controller_args.layers.guards.extend(layers.guards);
+        }
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let prefix = controller_args.prefix.clone();
     let mut outside_stmts = proc_macro2::TokenStream::new();
+    let mut url_items = Vec::new();
     let mut route_exprs: Vec<proc_macro2::TokenStream> = Vec::new();
-    let mut fns = Vec::new();
-    let controller_ident = &impl_block.self_ty;
+    let mut versioned_routes: Vec<proc_macro2::TokenStream> = Vec::new();
+    let mut handlers = Vec::new();
+    let controller_ident = &impl_block.self_ty.clone();
     let controller_name = match &**controller_ident {
         syn::Type::Path(tp) => tp.path.segments.last().unwrap().ident.to_string(),
         _ => "UnknownController".to_string(),
     };
     for item in &mut impl_block.items {
         if let syn::ImplItem::Fn(method) = item {
-            let mut is_route_fn = false;
-            let mut args = None;
-            for attr in &mut method.attrs {
-                if let Some(ident) = attr.path().get_ident() {
-                    let name = ident.to_string();
-                    match utils::valid_route_macro(&name) {
-                        RouteAttrType::Route => {
-                            is_route_fn = true;
-                            let new_tokens = controller_update_attr(attr, &prefix);
-                            *attr = syn::parse_quote!(#[#ident(#new_tokens)]);
-                            args = Some(attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated).unwrap_or_else(|e| {
-                                panic!("{}", e);
-                            }));
-                        }
-                        RouteAttrType::Derive(method) => {
-                            let lit = attr.parse_args::<syn::LitStr>().unwrap();
-                            let joined = utils::join_path(&prefix, &lit.value());
-                            is_route_fn = true;
-                            *attr = syn::parse_quote! {
-                                #[#ident(path = #joined, method = #method)]
-                            };
-                            args = Some(attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated).unwrap_or_else(|e| {
-                                panic!("{:?}", e);
-                            }));
-                        }
-                        RouteAttrType::Not => {}
-                    }
+            let mut route = None;
+            for (idx, attr) in method.attrs.iter().enumerate() {
+                match controller_route_args(attr, &prefix) {
+                    Ok(Some(args)) => route = Some((args, idx)),
+                    Ok(None) => {}
+                    Err(e) => return e.to_compile_error().into(),
                 }
             }
-            if is_route_fn {
-                let args = args.unwrap();
-                let (sig_token, router_expr, q_struct) = make_route_from_impl_fn(args, method);
+            if let Some((args, idx)) = route {
+                method.attrs.remove(idx);
+                let (handler, registration, extra_items, urls) =
+                    make_controller_route(args, method, controller_ident, &controller_args);
+                url_items.extend(urls);
                 outside_stmts.extend(quote! {
-                    #q_struct
+                    #(#extra_items)*
                 });
-                route_exprs.push(router_expr);
-                fns.push(sig_token);
-            } else {
-                fns.push(quote! { #method });
+                match registration {
+                    RouteRegistration::Plain(router_expr) => route_exprs.push(router_expr),
+                    RouteRegistration::Versioned(route) => versioned_routes.push(route),
+                }
+                handlers.push(handler);
             }
         }
     }
-    let mod_name = format!("__exum_generated_{}", controller_name);
+    // the hash keeps controllers with the same name in one module (or across crates) apart
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    quote!(#controller_ident).to_string().hash(&mut hasher);
+    let site = proc_macro::Span::call_site();
+    (site.file(), site.line(), site.column()).hash(&mut hasher);
+    let mod_name = format!("__exum_generated_{}_{:016x}", controller_name, hasher.finish());
     let mod_ident = syn::Ident::new(&mod_name, Span::call_site());
+    let children = &controller_args.children;
+    // constraints in the prefix are checked by the routes themselves
+    let prefix = split_constraints(&prefix).0;
+    let child_router = controller_args.layers.apply(quote! { child });
+    let profile_check = controller_args.profile.as_ref().map(|profile| {
+        quote! {
+            if !::exum::profile_active(#profile) {
+                return axum::Router::new();
+            }
+        }
+    });
+    let group = match &controller_args.group {
+        Some(group) => quote! { ::std::option::Option::Some(#group) },
+        None => quote! { ::std::option::Option::None },
+    };
+    let profile = match &controller_args.profile {
+        Some(profile) => quote! { ::std::option::Option::Some(#profile) },
+        None => quote! { ::std::option::Option::None },
+    };
+    let guards = controller_args.layers.guard_names();
     TokenStream::from(quote! {
+        #impl_block
+
+        #(#url_items)*
+
+        impl ::exum::controller::ControllerRoutes for #controller_ident {
+            const PREFIX: &'static str = #prefix;
+
+            fn routes() -> axum::Router {
+                #mod_ident::__collect_routes()
+            }
+        }
+
         #[doc(hidden)]
         #[allow(non_snake_case)]
         #[allow(dead_code)]
         mod #mod_ident {
             use super::*;
             #outside_stmts
-            #(#fns)*
+            #(#handlers)*
 
             pub fn __collect_routes() -> axum::Router {
+                #profile_check
                 let mut router = axum::Router::new();
                 #(#route_exprs;)*
+                let versioned: &[::exum::versioning::VersionedRoute] = &[#(#versioned_routes),*];
+                router = ::exum::versioning::route_versions(router, versioned);
+                #(
+                    // children inherit the controller layers and guards
+                    let child = ::exum::controller::mount(
+                        #prefix,
+                        <#children as ::exum::controller::ControllerRoutes>::routes(),
+                    );
+                    if child.has_routes() {
+                        router = router.merge(#child_router);
+                    }
+                )*
                 router
             }
         }
@@ -335,6 +528,13 @@ pub fn controller(attr: TokenStream, item: TokenStream) -> TokenStream {
         inventory::submit! {
             ::exum::controller::ControllerDef {
                 router: #mod_ident::__collect_routes,
+                type_id: ::std::any::TypeId::of::<#controller_ident>,
+                prefix: #prefix,
+                module_path: ::std::module_path!(),
+                children: &[#(::std::any::TypeId::of::<#children>),*],
+                guards: &[#(#guards),*],
+                group: #group,
+                profile: #profile,
             }
         }
     })
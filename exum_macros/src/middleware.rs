@@ -0,0 +1,168 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    parse_quote, punctuated::Punctuated, Attribute, Expr, FnArg, Item, ItemFn, Pat, Token, Type,
+    TypeParamBound,
+};
+
+use crate::{
+    handle_input::{handle_dep_attr, handle_trait_dep_attr},
+    route_core::NOT_DEPENCENCY_TYPE,
+    utils::{valid_route_macro, RouteAttrType},
+};
+
+fn is_layer_attr(attr: &Attribute) -> bool {
+    attr.path().is_ident("middleware") || attr.path().is_ident("layer") || attr.path().is_ident("guard")
+}
+
+/// Layers and guards declared with `#[middleware(..)]`, `#[layer(..)]` and `#[guard(..)]`.
+#[derive(Default, Clone)]
+pub struct RouteLayers {
+    /// Layer expressions in declaration order, the first one is the outermost.
+    pub layers: Vec<TokenStream>,
+    pub guards: Vec<syn::Path>,
+}
+
+impl RouteLayers {
+    /// Removes the layer attributes from `attrs` and collects them.
+    pub fn take(attrs: &mut Vec<Attribute>) -> syn::Result<Self> {
+        let mut this = Self::default();
+        for attr in attrs.iter().filter(|a| is_layer_attr(a)) {
+            if attr.path().is_ident("guard") {
+                let guards = attr.parse_args_with(Punctuated::<syn::Path, Token![,]>::parse_terminated)?;
+                if guards.is_empty() {
+                    return Err(syn::Error::new_spanned(attr, "expected at least one guard"));
+                }
+                this.guards.extend(guards);
+                continue;
+            }
+            let exprs = attr.parse_args_with(Punctuated::<Expr, Token![,]>::parse_terminated)?;
+            if exprs.is_empty() {
+                return Err(syn::Error::new_spanned(attr, "expected at least one middleware or layer"));
+            }
+            for expr in exprs {
+                if attr.path().is_ident("middleware") {
+                    this.layers.push(quote! { axum::middleware::from_fn(#expr) });
+                } else {
+                    this.layers.push(quote! { #expr });
+                }
+            }
+        }
+        attrs.retain(|a| !is_layer_attr(a));
+        Ok(this)
+    }
+
+    pub fn guard_names(&self) -> Vec<String> {
+        self.guards
+            .iter()
+            .map(|p| quote!(#p).to_string().replace(' ', ""))
+            .collect()
+    }
+
+    /// Wraps a router or method router expression with `route_layer` calls. Guards run inside
+    /// all other layers, one after another in declaration order.
+    pub fn apply(&self, expr: TokenStream) -> TokenStream {
+        let mut layers = self.layers.clone();
+        if !self.guards.is_empty() {
+            let guards = &self.guards;
+            layers.push(quote! {
+                axum::middleware::from_fn(|req: axum::extract::Request, next: axum::middleware::Next| async move {
+                    let (parts, body) = req.into_parts();
+                    #(
+                        if let ::std::result::Result::Err(e) = #guards(&parts).await {
+                            return axum::response::IntoResponse::into_response(e);
+                        }
+                    )*
+                    next.run(axum::extract::Request::from_parts(parts, body)).await
+                })
+            });
+        }
+        layers.iter().rev().fold(expr, |expr, layer| quote! { #expr.route_layer(#layer) })
+    }
+}
+
+fn is_target_attr(attr: &Attribute) -> bool {
+    let Some(seg) = attr.path().segments.last() else {
+        return false;
+    };
+    let name = seg.ident.to_string();
+    name == "controller" || !matches!(valid_route_macro(&name), RouteAttrType::Not)
+}
+
+/// `#[middleware(..)]` / `#[layer(..)]` / `#[guard(..)]` written above the route or controller attribute expand
+/// first; move them below it so the route macro picks them up.
+pub fn defer_to_route(name: &str, args: TokenStream, item: Item) -> TokenStream {
+    let ident = syn::Ident::new(name, proc_macro2::Span::call_site());
+    let attr: Attribute = parse_quote! { #[#ident(#args)] };
+    let attrs = match &item {
+        Item::Fn(f) => &f.attrs,
+        Item::Impl(i) => &i.attrs,
+        _ => {
+            return syn::Error::new_spanned(&item, format!("#[{}(..)] can only be used on route functions or #[controller] impls", name))
+                .to_compile_error();
+        }
+    };
+    let Some(pos) = attrs.iter().position(is_target_attr) else {
+        return syn::Error::new_spanned(
+            &item,
+            format!("#[{}(..)] must be used together with a route macro or #[controller]", name),
+        )
+        .to_compile_error();
+    };
+    let mut item = item;
+    match &mut item {
+        Item::Fn(f) => f.attrs.insert(pos + 1, attr),
+        Item::Impl(i) => i.attrs.insert(pos + 1, attr),
+        _ => unreachable!(),
+    }
+    quote! { #item }
+}
+
+fn is_passthrough(ty: &Type) -> bool {
+    match ty {
+        Type::Path(tp) => tp.path.segments.last().is_some_and(|seg| {
+            let name = seg.ident.to_string();
+            ["Request", "Next", "Parts"].contains(&name.as_str()) || NOT_DEPENCENCY_TYPE.contains(&name.as_str())
+        }),
+        _ => true,
+    }
+}
+
+/// Rewrites a `#[middleware]` / `#[guard]` fn so that arguments other than `Request`, `Next`,
+/// `&Parts` and the usual axum extractors are resolved from the DI container.
+pub fn make_injected_fn(mut input_fn: ItemFn, kind: &str) -> syn::Result<TokenStream> {
+    if input_fn.sig.asyncness.is_none() {
+        return Err(syn::Error::new_spanned(&input_fn.sig, format!("{} must be an async fn", kind)));
+    }
+    let mut inputs = Punctuated::<FnArg, Token![,]>::new();
+    let mut inject_segs = Vec::new();
+    for input in &input_fn.sig.inputs {
+        let FnArg::Typed(pat_type) = input else {
+            return Err(syn::Error::new_spanned(input, format!("{} cannot take `self`", kind)));
+        };
+        if !matches!(&*pat_type.pat, Pat::Ident(_)) {
+            inputs.push(input.clone());
+            continue;
+        }
+        if let Type::TraitObject(tt) = &*pat_type.ty {
+            if let Some(TypeParamBound::Trait(trait_bound)) = tt.bounds.last()
+                && let Some(seg) = trait_bound.path.segments.last()
+            {
+                handle_trait_dep_attr(pat_type, &seg.ident, &mut inject_segs);
+            }
+        } else if is_passthrough(&pat_type.ty) {
+            inputs.push(input.clone());
+        } else {
+            handle_dep_attr(pat_type, &mut inject_segs);
+        }
+    }
+    input_fn.sig.inputs = inputs;
+    let block = &input_fn.block;
+    input_fn.block = parse_quote! {
+        {
+            #(#inject_segs)*
+            #block
+        }
+    };
+    Ok(quote! { #input_fn })
+}
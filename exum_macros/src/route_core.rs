@@ -4,16 +4,18 @@ use proc_macro::TokenStream;
 use proc_macro2::Span;
 use quote::{quote};
 use syn::{
-    parse::Parser, parse_quote, punctuated::Punctuated, token::Comma, Attribute, Block, Expr, ExprLit, FnArg, Ident, ImplItemFn, ItemFn, ItemStruct, Lit, LitStr, Meta, MetaNameValue, Pat, Signature, Token, Type, TypeParamBound
+    parse::Parser, parse_quote, punctuated::Punctuated, token::Comma, Attribute, Block, Expr, ExprLit, FnArg, Ident, ImplItemFn, Item, ItemFn, ItemStruct, Lit, LitStr, Meta, MetaNameValue, Pat, PatType, Signature, Token, Type, TypeParamBound
 };
 
-static NOT_DEPENCENCY_TYPE: &[&str] = &[
+pub static NOT_DEPENCENCY_TYPE: &[&str] = &[
     // HTTP 核心类型
     "Method", "Uri", "Version", "HeaderMap",
     // 请求体类型
     "String", "Bytes", "Body",
     // axum 特定类型
     "OriginalUri", "MatchedPath", "RawQuery",
+    // exum 内容协商
+    "Accept",
     // JSON
     "Value"
 ];
@@ -45,12 +47,63 @@ pub fn collect_methods(expr: Expr) -> Vec<String> {
     }
 }
 
+/// Splits a path at `/`, keeping braced segments whole so patterns like `{slug:[a-z/]+}` survive.
+pub fn split_segments(path: &str) -> Vec<&str> {
+    let mut segments = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in path.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            '/' if depth == 0 => {
+                segments.push(&path[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    segments.push(&path[start..]);
+    segments
+}
+
+/// A constraint declared in the path, e.g. `{id:u64}` or `{slug:[a-z-]+}`.
+pub struct PathConstraintSpec {
+    pub name: String,
+    pub rule: String,
+}
+
+/// Removes the constraints from a normalized path, returning the path registered with axum.
+pub fn split_constraints(path: &str) -> (String, Vec<PathConstraintSpec>) {
+    let mut constraints = Vec::new();
+    let segments: Vec<String> = split_segments(path)
+        .into_iter()
+        .map(|seg| {
+            let Some(inner) = seg.strip_prefix('{').and_then(|s| s.strip_suffix('}')) else {
+                return seg.to_string();
+            };
+            match inner.split_once(':') {
+                Some((name, rule)) if !name.starts_with('*') => {
+                    if rule.is_empty() {
+                        panic!("empty constraint on path parameter `{}`", name);
+                    }
+                    constraints.push(PathConstraintSpec { name: name.to_string(), rule: rule.to_string() });
+                    format!("{{{}}}", name)
+                }
+                _ => seg.to_string(),
+            }
+        })
+        .collect();
+    (segments.join("/"), constraints)
+}
+
 pub fn extract_params(path: &str) -> Vec<String> {
-    path.split('/')
+    split_segments(path)
+        .into_iter()
         .filter_map(|s| {
             if s.starts_with('{') && s.ends_with('}') {
                 let inner = &s[1..s.len() - 1];
-                let name = inner.trim_start_matches('*').trim_start_matches('*');
+                let name = inner.split(':').next().unwrap_or_default().trim_start_matches('*');
                 Some(name.to_string())
             } else {
                 None
@@ -69,14 +122,14 @@ pub fn normalize_path(path: &str) -> String {
     }
 
     let mut out = String::new();
-    for seg in path.split('/') {
+    for seg in split_segments(path) {
         if seg.is_empty() {
             continue;
         }
         out.push('/');
 
-        if seg.starts_with(':') {
-            let name = seg[1..].trim();
+        if let Some(name) = seg.strip_prefix(':') {
+            let name = name.trim();
             if name.is_empty() {
                 out.push_str(seg);
             } else {
@@ -86,11 +139,10 @@ pub fn normalize_path(path: &str) -> String {
             }
         } else if seg.starts_with('{') && seg.ends_with('}') {
             out.push_str(seg);
-        } else if seg.starts_with('*') {
-            let name = &seg[1..];
-            if name.starts_with('*') {
+        } else if let Some(name) = seg.strip_prefix('*') {
+            if let Some(name) = name.strip_prefix('*') {
                 out.push_str("{**");
-                out.push_str(&name[1..]);
+                out.push_str(name);
                 out.push('}');
             } else {
                 out.push_str("{*");
@@ -118,20 +170,56 @@ pub fn extract_path(args: &Punctuated<Meta, Token![,]>) -> String {
             value,
             ..
         }) = meta
+            && path_meta.is_ident("path")
+            && let Expr::Lit(ExprLit {
+                lit: Lit::Str(s), ..
+            }) = value
         {
-            if path_meta.is_ident("path") {
-                if let Expr::Lit(ExprLit {
-                    lit: Lit::Str(s), ..
-                }) = value
-                {
-                    path = s.value();
-                }
-            }
+            path = s.value();
         }
     }
     normalize_path(&path)
 }
 
+/// A string argument such as `version = "2"`.
+pub fn extract_str(args: &Punctuated<Meta, Token![,]>, key: &str) -> Option<LitStr> {
+    args.iter().find_map(|meta| match meta {
+        Meta::NameValue(MetaNameValue {
+            path,
+            value: Expr::Lit(ExprLit { lit: Lit::Str(s), .. }),
+            ..
+        }) if path.is_ident(key) => Some(s.clone()),
+        _ => None,
+    })
+}
+
+/// The string options of a route: `version`, `group` and `profile`.
+#[derive(Default, Clone)]
+pub struct RouteOptions {
+    pub version: Option<LitStr>,
+    /// Routes with a group are only mounted through `Application::mount_group`.
+    pub group: Option<LitStr>,
+    /// Comma separated environments the route is registered in, see `exum::detect_env`.
+    pub profile: Option<LitStr>,
+}
+
+impl RouteOptions {
+    pub fn from_args(args: &Punctuated<Meta, Token![,]>) -> Self {
+        Self {
+            version: extract_str(args, "version"),
+            group: extract_str(args, "group"),
+            profile: extract_str(args, "profile"),
+        }
+    }
+}
+
+fn option_tokens(value: Option<&LitStr>) -> proc_macro2::TokenStream {
+    match value {
+        Some(v) => quote! { ::std::option::Option::Some(#v) },
+        None => quote! { ::std::option::Option::None },
+    }
+}
+
 pub fn extract_methods(args: &Punctuated<Meta, Token![,]>) -> Vec<String> {
     let mut methods = Vec::new();
     for meta in args {
@@ -140,10 +228,9 @@ pub fn extract_methods(args: &Punctuated<Meta, Token![,]>) -> Vec<String> {
             value,
             ..
         }) = meta
+            && path_meta.is_ident("method")
         {
-            if path_meta.is_ident("method") {
-                methods.extend(collect_methods(value.clone()));
-            }
+            methods.extend(collect_methods(value.clone()));
         }
     }
     if methods.is_empty() {
@@ -159,25 +246,41 @@ pub fn parse_args(args: TokenStream) -> Punctuated<Meta, Token![,]> {
 
 use crate::{
     handle_dep_attr,
-    handle_input::{handle_b_attr, handle_q_attr, handle_trait_dep_attr}, utils::join_path,
+    controller_args::{ControllerArgs, RouteOverrides},
+    middleware::RouteLayers,
+    handle_input::{handle_b_attr, handle_cookie_attr, handle_current_user_attr, handle_h_attr, handle_q_attr, handle_trait_dep_attr, param_marker, path_check_param, query_param_names, wrap_param, PartsField}, utils::{is_arc_type, join_path, url_ident},
 };
 
+fn marker_ident(fn_name: &Ident, role: &str) -> Ident {
+    Ident::new(
+        &format!("{}{}", fn_name.to_string().to_case(Case::Pascal), role),
+        Span::call_site(),
+    )
+}
+
 pub fn process_inputs(
     inputs: &Punctuated<FnArg, Token![,]>,
     path: &str,
+    constraints: &[PathConstraintSpec],
     fn_name: &Ident,
+    error: Option<&Type>,
 ) -> (
     Option<FnArg>,
     Vec<FnArg>,
-    Option<ItemStruct>,
+    Vec<Item>,
     Vec<syn::Stmt>,
 ) {
     let params = extract_params(path);
     let mut path_idents = Vec::new();
     let mut path_types = Vec::new();
+    let mut path_struct: Option<&PatType> = None;
     let mut other_inputs = Vec::new();
+    let mut body_input: Option<FnArg> = None;
     let mut q_fields: Vec<syn::Field> = Vec::new();
+    let mut q_validate = false;
+    let mut parts_fields: Vec<PartsField> = Vec::new();
     let mut inject_segs = Vec::new();
+    let mut extra_items: Vec<Item> = Vec::new();
 
     for input in inputs {
         if let FnArg::Typed(pat_type) = input {
@@ -189,7 +292,7 @@ pub fn process_inputs(
                     let path = &trait_bound.path;
                     if let Some(seg) = path.segments.last() {
                         let trait_ident = &seg.ident;
-                        handle_trait_dep_attr(&pat_type, trait_ident, &mut inject_segs);
+                        handle_trait_dep_attr(pat_type, trait_ident, &mut inject_segs);
                     }
                 }
                 // other_inputs.push(input.clone());
@@ -198,11 +301,41 @@ pub fn process_inputs(
 
             let has_q_attr = pat_type.attrs.iter().any(|a| a.path().is_ident("q"));
             let has_b_attr = pat_type.attrs.iter().any(|a| a.path().is_ident("b"));
+            let has_h_attr = pat_type.attrs.iter().any(|a| a.path().is_ident("h"));
+            let has_cookie_attr = pat_type.attrs.iter().any(|a| a.path().is_ident("cookie"));
+            let has_current_user_attr = pat_type.attrs.iter().any(|a| a.path().is_ident("current_user"));
+            let has_path_attr = pat_type.attrs.iter().any(|a| a.path().is_ident("path"));
             // let has_dep_attr = pat_type.attrs.iter().any(|a| a.path().is_ident("dep"));
-            if has_q_attr {
-                handle_q_attr(pat_type, &mut q_fields);
+            if has_path_attr {
+                if path_struct.is_some() {
+                    panic!("only one #[path] parameter is allowed per handler");
+                }
+                path_struct = Some(pat_type);
+            } else if has_q_attr {
+                q_validate |= handle_q_attr(pat_type, &mut q_fields);
+            } else if has_h_attr {
+                handle_h_attr(pat_type, &mut parts_fields);
+            } else if has_cookie_attr {
+                handle_cookie_attr(pat_type, &mut parts_fields);
+            } else if has_current_user_attr {
+                let marker = marker_ident(fn_name, "UserParam");
+                let name = match &*pat_type.pat {
+                    Pat::Ident(ident) => ident.ident.to_string(),
+                    _ => "user".to_string(),
+                };
+                extra_items.extend(param_marker(&marker, &name, error));
+                other_inputs.push(wrap_param(handle_current_user_attr(pat_type), &marker));
             } else if has_b_attr {
-                handle_b_attr(pat_type, &mut other_inputs);
+                let marker = marker_ident(fn_name, "BodyParam");
+                let name = match &*pat_type.pat {
+                    Pat::Ident(ident) => ident.ident.to_string(),
+                    _ => "body".to_string(),
+                };
+                extra_items.extend(param_marker(&marker, &name, error));
+                if body_input.is_some() {
+                    panic!("only one #[b] parameter is allowed per handler");
+                }
+                body_input = Some(wrap_param(handle_b_attr(pat_type), &marker));
             } else if let Pat::Ident(ident) = &*pat_type.pat {
                 let name = ident.ident.to_string();
                 if params.contains(&name) {
@@ -223,37 +356,124 @@ pub fn process_inputs(
         }
     }
 
-    let path_arg: Option<FnArg> = if !path_idents.is_empty() {
-        Some(parse_quote! {
+    let mut path_arg: Option<FnArg> = if let Some(pat_type) = path_struct {
+        if !path_idents.is_empty() {
+            panic!("path parameters cannot be taken both as arguments and through #[path]");
+        }
+        let marker = marker_ident(fn_name, "PathParam");
+        let name = match &*pat_type.pat {
+            Pat::Ident(ident) => ident.ident.to_string(),
+            _ => params.join(", "),
+        };
+        extra_items.extend(param_marker(&marker, &name, error));
+        let (pat, ty) = (&pat_type.pat, &pat_type.ty);
+        Some(wrap_param(parse_quote! {
+            axum::extract::Path(#pat): axum::extract::Path<#ty>
+        }, &marker))
+    } else if !path_idents.is_empty() {
+        let marker = marker_ident(fn_name, "PathParam");
+        let name = path_idents.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(", ");
+        extra_items.extend(param_marker(&marker, &name, error));
+        Some(wrap_param(parse_quote! {
             axum::extract::Path((#(#path_idents),*)): axum::extract::Path<(#(#path_types),*)>
-        })
+        }, &marker))
     } else {
         None
     };
 
-    let q_struct = if !q_fields.is_empty() {
+    if !q_fields.is_empty() {
         let struct_ident = Ident::new(
             &format!("{}Query", fn_name.to_string().to_case(Case::Pascal)),
             Span::call_site(),
         );
-        let q_struct: ItemStruct = parse_quote! {
-            #[derive(serde::Deserialize)]
+        let fields: Vec<Ident> = q_fields.iter().map(|f| f.ident.clone().unwrap()).collect();
+        let (q_struct, query_arg): (ItemStruct, FnArg) = if q_validate {
+            (
+                parse_quote! {
+                    #[derive(serde::Deserialize, ::exum::Validate)]
+                    struct #struct_ident {
+                        #(#q_fields),*
+                    }
+                },
+                parse_quote! {
+                    ::exum::validate::ValidQuery(#struct_ident { #(#fields),* }): ::exum::validate::ValidQuery<#struct_ident>
+                },
+            )
+        } else {
+            (
+                parse_quote! {
+                    #[derive(serde::Deserialize)]
+                    struct #struct_ident {
+                        #(#q_fields),*
+                    }
+                },
+                parse_quote! {
+                    axum::extract::Query(#struct_ident { #(#fields),* }): axum::extract::Query<#struct_ident>
+                },
+            )
+        };
+        let marker = marker_ident(fn_name, "QueryParam");
+        // rejections of several fields name the failing one themselves, when they can
+        let name = match fields.as_slice() {
+            [field] => field.to_string(),
+            _ => String::new(),
+        };
+        extra_items.extend(param_marker(&marker, &name, error));
+        other_inputs.insert(0, wrap_param(query_arg, &marker));
+        extra_items.push(Item::Struct(q_struct));
+    }
+
+    if !parts_fields.is_empty() {
+        let struct_ident = Ident::new(
+            &format!("{}Headers", fn_name.to_string().to_case(Case::Pascal)),
+            Span::call_site(),
+        );
+        let idents: Vec<&Ident> = parts_fields.iter().map(|f| &f.ident).collect();
+        let tys: Vec<&Type> = parts_fields.iter().map(|f| &f.ty).collect();
+        let inits: Vec<&Expr> = parts_fields.iter().map(|f| &f.init).collect();
+        extra_items.push(parse_quote! {
             struct #struct_ident {
-                #(#q_fields),*
+                #(pub #idents: #tys),*
             }
-        };
-        let fields: Vec<Ident> = q_fields.iter().map(|f| f.ident.clone().unwrap()).collect();
+        });
+        extra_items.push(parse_quote! {
+            impl<S: Send + Sync> axum::extract::FromRequestParts<S> for #struct_ident {
+                type Rejection = ::exum::extract::ParamRejection;
 
-        let query_arg: FnArg = parse_quote! {
-            axum::extract::Query(#struct_ident { #(#fields),* }): axum::extract::Query<#struct_ident>
+                async fn from_request_parts(
+                    parts: &mut axum::http::request::Parts,
+                    _state: &S,
+                ) -> Result<Self, Self::Rejection> {
+                    Ok(Self {
+                        #(#idents: #inits),*
+                    })
+                }
+            }
+        });
+        let headers_arg: FnArg = parse_quote! {
+            #struct_ident { #(#idents),* }: #struct_ident
         };
-        other_inputs.insert(0, query_arg);
-        Some(q_struct)
-    } else {
-        None
-    };
+        let marker = marker_ident(fn_name, "HeadersParam");
+        let name = idents.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(", ");
+        extra_items.extend(param_marker(&marker, &name, error));
+        other_inputs.insert(0, wrap_param(headers_arg, &marker));
+    }
 
-    (path_arg, other_inputs, q_struct, inject_segs)
+    // the body extractor consumes the request, so it has to come last
+    other_inputs.extend(body_input);
+
+    if !constraints.is_empty() {
+        // the constraints are checked before any other extractor runs
+        let marker = marker_ident(fn_name, "PathCheck");
+        let name = constraints.iter().map(|c| c.name.as_str()).collect::<Vec<_>>().join(", ");
+        extra_items.extend(param_marker(&marker, &name, error));
+        let (check_arg, check_item) = path_check_param(&marker, constraints);
+        extra_items.push(check_item);
+        other_inputs.splice(0..0, path_arg.take());
+        path_arg = Some(check_arg);
+    }
+
+    (path_arg, other_inputs, extra_items, inject_segs)
 }
 pub fn build_signature(
     path_arg: Option<FnArg>,
@@ -281,27 +501,171 @@ pub fn build_router_expr(
     methods: &[String],
     path: &str,
     fn_name: &Ident,
+    layers: &[&RouteLayers],
 ) -> proc_macro2::TokenStream {
     let path_lit = LitStr::new(path, Span::call_site());
     let mut router_expr = quote! { router };
     for m in methods {
         let method_ident = method_to_ident(m);
+        // `layers` is ordered outermost first, e.g. controller layers before the route's own
+        let method_router = layers.iter().rev().fold(
+            quote! { axum::routing::on(axum::routing::MethodFilter::#method_ident, #fn_name) },
+            |expr, layers| layers.apply(expr),
+        );
         router_expr = quote! {
-            #router_expr.route(#path_lit, axum::routing::on(axum::routing::MethodFilter::#method_ident, #fn_name))
+            #router_expr.route(#path_lit, #method_router)
         };
     }
     router_expr
 }
+/// A `VersionedRoute` for a route declared with `version = ".."`, resolved by the app's versioning strategy.
+pub fn build_versioned_route(
+    methods: &[String],
+    path: &str,
+    fn_name: &Ident,
+    layers: &[&RouteLayers],
+    options: &RouteOptions,
+) -> proc_macro2::TokenStream {
+    let path_lit = LitStr::new(path, Span::call_site());
+    let handler = fn_name.to_string();
+    let version = options.version.as_ref().expect("versioned route without a version");
+    let group = option_tokens(options.group.as_ref());
+    let profile = option_tokens(options.profile.as_ref());
+    let filters = methods.iter().map(|m| method_to_ident(m));
+    let method_router = layers.iter().rev().fold(
+        quote! { axum::routing::MethodRouter::new()#(.on(axum::routing::MethodFilter::#filters, #fn_name))* },
+        |expr, layers| layers.apply(expr),
+    );
+    quote! {
+        ::exum::versioning::VersionedRoute {
+            path: #path_lit,
+            version: #version,
+            route: || #method_router,
+            handler: #handler,
+            module_path: ::std::module_path!(),
+            group: #group,
+            profile: #profile,
+        }
+    }
+}
+
+/// How a route is added to the router.
+pub enum RouteRegistration {
+    /// A `router = router.route(..)` statement, or a `RouteDef` for free routes.
+    Plain(proc_macro2::TokenStream),
+    /// A `VersionedRoute` expression.
+    Versioned(proc_macro2::TokenStream),
+}
+
+/// Registers a `RouteMeta` entry per method so the route table can be inspected at runtime.
+pub fn route_meta_items(
+    methods: &[String],
+    path: &str,
+    fn_name: &Ident,
+    guards: &[String],
+    tags: &[LitStr],
+    options: &RouteOptions,
+    controller: Option<&Type>,
+) -> Vec<Item> {
+    let handler = fn_name.to_string();
+    let controller = match controller {
+        Some(ty) => quote! { ::std::option::Option::Some(::std::any::TypeId::of::<#ty>) },
+        None => quote! { ::std::option::Option::None },
+    };
+    let version = option_tokens(options.version.as_ref());
+    let group = option_tokens(options.group.as_ref());
+    let profile = option_tokens(options.profile.as_ref());
+    methods
+        .iter()
+        .map(|method| {
+            parse_quote! {
+                inventory::submit! {
+                    ::exum::RouteMeta {
+                        method: #method,
+                        path: #path,
+                        handler: #handler,
+                        module_path: ::std::module_path!(),
+                        guards: &[#(#guards),*],
+                        tags: &[#(#tags),*],
+                        version: #version,
+                        group: #group,
+                        profile: #profile,
+                        controller: #controller,
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
+/// Emits the hidden `url_ident` type `url_for!` resolves: an associated constant per parameter,
+/// so unknown names fail to compile, and a const check that every path parameter was given.
+pub fn route_url_items(
+    url_ident: &Ident,
+    label: &str,
+    path: &str,
+    query: &[String],
+    options: &RouteOptions,
+    controller: Option<&Type>,
+) -> Vec<Item> {
+    let params = extract_params(path);
+    let param_idents = params.iter().map(|p| Ident::new(p, Span::call_site()));
+    let query_idents = query
+        .iter()
+        .filter(|q| !params.contains(q))
+        .map(|q| Ident::new(q, Span::call_site()));
+    let missing = params
+        .iter()
+        .map(|p| format!("url_for!({}): missing path parameter `{}`", label, p));
+    let version = option_tokens(options.version.as_ref());
+    let controller = controller.map(|ty| {
+        quote! {
+            fn controller() -> ::std::option::Option<::std::any::TypeId> {
+                ::std::option::Option::Some(::std::any::TypeId::of::<#ty>())
+            }
+        }
+    });
+    vec![
+        parse_quote! {
+            #[doc(hidden)]
+            #[allow(non_camel_case_types, dead_code)]
+            pub struct #url_ident {}
+        },
+        parse_quote! {
+            #[allow(non_upper_case_globals, dead_code)]
+            impl #url_ident {
+                #(pub const #param_idents: ::exum::url::ParamKind = ::exum::url::ParamKind::Path;)*
+                #(pub const #query_idents: ::exum::url::ParamKind = ::exum::url::ParamKind::Query;)*
+
+                pub const fn __exum_check(given: &[&str]) {
+                    #(
+                        if !::exum::url::contains(given, #params) {
+                            panic!(#missing);
+                        }
+                    )*
+                }
+            }
+        },
+        parse_quote! {
+            impl ::exum::url::RouteUrl for #url_ident {
+                const PATH: &'static str = #path;
+                const VERSION: ::std::option::Option<&'static str> = #version;
+                #controller
+            }
+        },
+    ]
+}
+
 pub fn expand(
     new_sig: Signature,
     block: Box<Block>,
-    router_expr: proc_macro2::TokenStream,
-    q_struct: Option<ItemStruct>,
+    registration: RouteRegistration,
+    extra_items: Vec<Item>,
     inject_segs: Vec<syn::Stmt>,
 ) -> (
     proc_macro2::TokenStream,
     proc_macro2::TokenStream,
-    Option<ItemStruct>,
+    Vec<Item>,
 ) {
     let sig_token = quote! {
         #new_sig {
@@ -309,14 +673,19 @@ pub fn expand(
             #block
         }
     };
-    let collect_token = quote! {
-      inventory::submit! {
-          exum::RouteDef {
-              router: |router| #router_expr,
+    let collect_token = match registration {
+        RouteRegistration::Plain(route) => quote! {
+          inventory::submit! {
+              #route
           }
-      }
+        },
+        RouteRegistration::Versioned(route) => quote! {
+          inventory::submit! {
+              #route
+          }
+        },
     };
-    (sig_token, collect_token, q_struct)
+    (sig_token, collect_token, extra_items)
 }
 
 pub fn make_route(
@@ -325,75 +694,216 @@ pub fn make_route(
 ) -> (
     proc_macro2::TokenStream,
     proc_macro2::TokenStream,
-    Option<ItemStruct>,
+    Vec<Item>,
 ) {
-    let path = extract_path(&args);
+    let (path, constraints) = split_constraints(&extract_path(&args));
     let methods = extract_methods(&args);
+    let options = RouteOptions::from_args(&args);
+    let layers = RouteLayers::take(&mut input_fn.attrs).unwrap_or_else(|e| panic!("{}", e));
+    let fn_name = &input_fn.sig.ident;
+
+    let query = query_param_names(&input_fn.sig.inputs);
 
-    let (path_arg, other_inputs, q_struct, inject_segs) =
-        process_inputs(&input_fn.sig.inputs, &path, &input_fn.sig.ident);
+    let (path_arg, other_inputs, mut extra_items, inject_segs) =
+        process_inputs(&input_fn.sig.inputs, &path, &constraints, fn_name, None);
     let new_sig = build_signature(path_arg, other_inputs, &input_fn.sig);
+    let label = fn_name.to_string();
+    extra_items.extend(route_url_items(&url_ident(None, &label), &label, &path, &query, &options, None));
+    extra_items.extend(route_meta_items(&methods, &path, fn_name, &layers.guard_names(), &[], &options, None));
 
-    let router_expr = build_router_expr(&methods, &path, &input_fn.sig.ident);
+    let registration = if options.version.is_some() {
+        RouteRegistration::Versioned(build_versioned_route(&methods, &path, fn_name, &[&layers], &options))
+    } else {
+        let router_expr = build_router_expr(&methods, &path, fn_name, &[&layers]);
+        let handler = fn_name.to_string();
+        let group = option_tokens(options.group.as_ref());
+        let profile = option_tokens(options.profile.as_ref());
+        RouteRegistration::Plain(quote! {
+            exum::RouteDef {
+                router: |router| #router_expr,
+                handler: #handler,
+                module_path: ::std::module_path!(),
+                group: #group,
+                profile: #profile,
+            }
+        })
+    };
     expand(
         new_sig,
         input_fn.block.clone(),
-        router_expr,
-        q_struct,
+        registration,
+        extra_items,
         inject_segs,
     )
 }
 
-pub fn make_route_from_impl_fn(
+const PARAM_ATTRS: &[&str] = &["q", "b", "h", "cookie", "current_user", "path"];
+
+/// Type of the argument as seen by the controller method: injected dependencies arrive as `Arc<T>`
+/// (`Arc<impl Trait>` for trait objects), so a parameter declared as `T` is rewritten to `Arc<T>`.
+/// Parameters already declared as `Arc<T>` and everything else are passed through unchanged.
+fn controller_param_type(pat_type: &PatType, path_params: &[String]) -> Option<Type> {
+    if pat_type.attrs.iter().any(|a| PARAM_ATTRS.iter().any(|n| a.path().is_ident(n))) {
+        return None;
+    }
+    match &*pat_type.ty {
+        Type::TraitObject(tt) => {
+            let bounds = &tt.bounds;
+            Some(parse_quote! { ::std::sync::Arc<impl #bounds> })
+        }
+        Type::Path(tp) => {
+            let Pat::Ident(ident) = &*pat_type.pat else {
+                return None;
+            };
+            let type_ident = tp.path.segments.last().unwrap().ident.to_string();
+            if path_params.contains(&ident.ident.to_string())
+                || NOT_DEPENCENCY_TYPE.contains(&type_ident.as_str())
+                || is_arc_type(&pat_type.ty).is_some()
+            {
+                None
+            } else {
+                let ty = &pat_type.ty;
+                Some(parse_quote! { ::std::sync::Arc<#ty> })
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Builds the axum handler for a `#[controller]` method and turns the method itself into a plain
+/// method: the handler extracts the arguments, resolves the controller through the DI container
+/// when the method takes `&self`, and calls it. Controller settings apply unless the method
+/// overrides them. The last items back `url_for!` and belong next to the controller, outside
+/// the generated module.
+pub fn make_controller_route(
     args: Punctuated<Meta, Comma>,
-    input_fn: &mut ImplItemFn,
+    method: &mut ImplItemFn,
+    self_ty: &Type,
+    controller: &ControllerArgs,
 ) -> (
     proc_macro2::TokenStream,
-    proc_macro2::TokenStream,
-    Option<ItemStruct>,
+    RouteRegistration,
+    Vec<Item>,
+    Vec<Item>,
 ) {
-    let path = extract_path(&args);
+    let (path, constraints) = split_constraints(&extract_path(&args));
     let methods = extract_methods(&args);
+    let layers = RouteLayers::take(&mut method.attrs).unwrap_or_else(|e| panic!("{}", e));
+    let overrides = RouteOverrides::take(&mut method.attrs).unwrap_or_else(|e| panic!("{}", e));
+    let controller_layers = overrides.controller_layers(&controller.layers);
+    let error = overrides.error.as_ref().or(controller.error.as_ref());
+    let tags = overrides.tags.as_ref().unwrap_or(&controller.tags);
+    let mut options = RouteOptions::from_args(&args);
+    if let Some(group) = &options.group {
+        panic!(
+            "`group = {:?}` is not supported on controller routes, set it on the `#[controller]` instead",
+            group.value()
+        );
+    }
+    options.group = controller.group.clone();
+    options.version = options.version.or_else(|| controller.version.clone());
+    let fn_name = method.sig.ident.clone();
 
-    let (path_arg, other_inputs, q_struct, inject_segs) =
-        process_inputs(&input_fn.sig.inputs, &path, &input_fn.sig.ident);
-    let new_sig = build_signature(path_arg, other_inputs, &input_fn.sig);
-    let block = input_fn.block.clone();
-    let sig_token = quote! {
+    let query = query_param_names(&method.sig.inputs);
+    let controller_name = match self_ty {
+        Type::Path(tp) => tp.path.segments.last().unwrap().ident.to_string(),
+        _ => panic!("#[controller] expects a named type"),
+    };
+    let url_items = route_url_items(
+        &url_ident(Some(&controller_name), &fn_name.to_string()),
+        &format!("{}::{}", controller_name, fn_name),
+        &path,
+        &query,
+        &options,
+        Some(self_ty),
+    );
+
+    let (path_arg, other_inputs, mut extra_items, inject_segs) =
+        process_inputs(&method.sig.inputs, &path, &constraints, &fn_name, error);
+    let mut new_sig = build_signature(path_arg, other_inputs, &method.sig);
+    // the result may borrow the controller, so it is turned into a response while the instance is alive
+    new_sig.output = parse_quote!(-> axum::response::Response);
+    let mut guards = controller_layers.guard_names();
+    guards.extend(layers.guard_names());
+    extra_items.extend(route_meta_items(&methods, &path, &fn_name, &guards, tags, &options, Some(self_ty)));
+
+    let path_params = extract_params(&path);
+    let mut call_args = Vec::new();
+    for input in method.sig.inputs.iter_mut() {
+        if let FnArg::Typed(pat_type) = input {
+            let Pat::Ident(ident) = &*pat_type.pat else {
+                panic!("controller handler arguments must be simple identifiers, e.g. `id: u64`");
+            };
+            call_args.push(ident.ident.clone());
+            if let Some(ty) = controller_param_type(pat_type, &path_params) {
+                *pat_type.ty = ty;
+            }
+            pat_type.attrs.retain(|a| !PARAM_ATTRS.iter().any(|n| a.path().is_ident(n)));
+        }
+    }
+    if matches!(method.sig.output, syn::ReturnType::Default) {
+        method.sig.output = parse_quote!(-> impl axum::response::IntoResponse);
+    }
+
+    let await_token = method.sig.asyncness.map(|_| quote! { .await });
+    let call = match method.sig.receiver() {
+        Some(receiver) if receiver.reference.is_some() && receiver.mutability.is_none() => quote! {
+            let __exum_this = ::exum::controller::instance::<#self_ty>().await;
+            axum::response::IntoResponse::into_response(__exum_this.#fn_name(#(#call_args),*) #await_token)
+        },
+        Some(_) => panic!("controller handlers must take `&self`, found another receiver on `{}`", fn_name),
+        None => quote! {
+            axum::response::IntoResponse::into_response(<#self_ty>::#fn_name(#(#call_args),*) #await_token)
+        },
+    };
+    let handler = quote! {
         #new_sig {
             #(#inject_segs)*
-            #block
+            #call
         }
     };
-    let fn_name = &input_fn.sig.ident.clone();
-    let mut router_expr  = quote! { router };
-    for m in &methods {
-        let method_ident = method_to_ident(m);
-        router_expr = quote! {
-            #router_expr.route(#path, axum::routing::on(axum::routing::MethodFilter::#method_ident, #fn_name))
-        };
-    }
-    let router_expr = quote! {
-      router = #router_expr;
+    let layers = [&controller_layers, &layers];
+    let registration = if options.version.is_some() {
+        RouteRegistration::Versioned(build_versioned_route(&methods, &path, &fn_name, &layers, &options))
+    } else {
+        let router_expr = build_router_expr(&methods, &path, &fn_name, &layers);
+        match &options.profile {
+            Some(profile) => RouteRegistration::Plain(quote! {
+              if ::exum::profile_active(#profile) {
+                  router = #router_expr;
+              }
+            }),
+            None => RouteRegistration::Plain(quote! {
+              router = #router_expr;
+            }),
+        }
     };
-    (sig_token, router_expr, q_struct)
+    (handler, registration, extra_items, url_items)
 }
 
-pub fn controller_update_attr(attr: &Attribute, prefix: &str) -> proc_macro2::TokenStream {
+/// Rewrites the arguments of a `#[route(..)]` attribute inside a controller so the path carries
+/// the controller prefix; a missing path becomes the prefix itself.
+pub fn controller_update_attr(attr: &Attribute, prefix: &str) -> syn::Result<proc_macro2::TokenStream> {
     let mut new_tokens = proc_macro2::TokenStream::new();
     let mut has_path = false;
-    let _ = attr.parse_nested_meta(|meta| {
+    attr.parse_nested_meta(|meta| {
+        let value: Expr = meta.value()?.parse()?;
         if meta.path.is_ident("path") {
-            let lit: syn::LitStr = meta.value()?.parse()?;
-            let joined = join_path(&prefix, &lit.value());
+            let Expr::Lit(ExprLit { lit: Lit::Str(lit), .. }) = &value else {
+                return Err(syn::Error::new_spanned(&value, "expected a string literal path"));
+            };
+            let joined = join_path(prefix, &lit.value());
             new_tokens.extend(quote!(path = #joined,));
             has_path = true;
         } else {
             let path = meta.path;
-            let input: proc_macro2::TokenStream = meta.input.parse().unwrap();
-            new_tokens.extend(quote! {#path #input,});
+            new_tokens.extend(quote! {#path = #value,});
         }
         Ok(())
-    });
-    new_tokens
+    })?;
+    if !has_path {
+        let prefix = if prefix.is_empty() { "/" } else { prefix };
+        new_tokens.extend(quote!(path = #prefix,));
+    }
+    Ok(new_tokens)
 }
@@ -0,0 +1,69 @@
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream},
+    Expr, Ident, Token,
+};
+
+use crate::utils::url_ident;
+
+/// `url_for!(get_user, id = 5, page = 2)` or `url_for!(UserController::get_user, id = 5)`.
+pub struct UrlFor {
+    route: syn::Path,
+    args: Vec<(Ident, Expr)>,
+}
+
+impl Parse for UrlFor {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let route = input.parse()?;
+        let mut args = Vec::new();
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let name: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            args.push((name, input.parse()?));
+        }
+        Ok(Self { route, args })
+    }
+}
+
+impl UrlFor {
+    /// The generated type of the route: the last segment names the handler, the one before
+    /// it the controller when it starts with an uppercase letter.
+    fn url_type(&self) -> syn::Result<syn::Path> {
+        let mut path = self.route.clone();
+        let Some(handler) = path.segments.pop() else {
+            return Err(syn::Error::new_spanned(&self.route, "expected a handler name"));
+        };
+        let handler = handler.into_value().ident;
+        let controller = path
+            .segments
+            .last()
+            .map(|seg| seg.ident.to_string())
+            .filter(|name| name.starts_with(|c: char| c.is_uppercase()));
+        if controller.is_some() {
+            path.segments.pop();
+        }
+        let mut ident = url_ident(controller.as_deref(), &handler.to_string());
+        ident.set_span(handler.span());
+        path.segments.push(ident.into());
+        Ok(path)
+    }
+
+    pub fn expand(&self) -> syn::Result<proc_macro2::TokenStream> {
+        let ty = self.url_type()?;
+        let names: Vec<&Ident> = self.args.iter().map(|(name, _)| name).collect();
+        let name_strs: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+        let values = self.args.iter().map(|(_, value)| value);
+        Ok(quote! {
+            {
+                const _: () = #ty::__exum_check(&[#(#name_strs),*]);
+                ::exum::url::UrlBuilder::new::<#ty>()
+                    #(.param(#ty::#names, #name_strs, &(#values)))*
+                    .build()
+            }
+        })
+    }
+}
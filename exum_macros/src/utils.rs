@@ -1,3 +1,4 @@
+use quote::quote;
 use syn::{GenericArgument, PathArguments, Type};
 
 pub fn join_path(prefix: &str, sub: &str) -> String {
@@ -10,13 +11,26 @@ pub fn join_path(prefix: &str, sub: &str) -> String {
     res
 }
 
+/// Splits the arguments of the method shorthands (`"/path", key = value, ..`) into the path and the rest.
+pub fn parse_shorthand(tokens: proc_macro2::TokenStream) -> syn::Result<(syn::LitStr, proc_macro2::TokenStream)> {
+    let parser = |input: syn::parse::ParseStream| {
+        let lit: syn::LitStr = input.parse()?;
+        if !input.is_empty() {
+            input.parse::<syn::Token![,]>()?;
+        }
+        let rest: proc_macro2::TokenStream = input.parse()?;
+        Ok((lit, rest))
+    };
+    syn::parse::Parser::parse2(parser, tokens)
+}
+
 pub enum RouteAttrType {
     Route,
     Derive(String),
     Not,
 }
 pub fn valid_route_macro(name: &str) -> RouteAttrType {
-  if name == "route" {return RouteAttrType::Route;}
+  if name == "route" {RouteAttrType::Route}
   else if ["get", "post", "put", "delete", "patch", "head", "options", "trace"].contains(&name) {
     RouteAttrType::Derive(name.to_string())
   } else {
@@ -24,17 +38,31 @@ pub fn valid_route_macro(name: &str) -> RouteAttrType {
   }
 }
 
-pub fn _is_arc_type(ty: &Type) -> Option<&Type> {
-    if let Type::Path(tp) = ty {
-        if let Some(seg) = tp.path.segments.last() {
-            if seg.ident == "Arc" {
-                if let PathArguments::AngleBracketed(args) = &seg.arguments {
-                    if let Some(GenericArgument::Type(inner_ty)) = args.args.first() {
-                        return Some(inner_ty);
-                    }
-                }
-            }
-        }
+pub fn is_arc_type(ty: &Type) -> Option<&Type> {
+    if let Type::Path(tp) = ty
+        && let Some(seg) = tp.path.segments.last()
+        && seg.ident == "Arc"
+        && let PathArguments::AngleBracketed(args) = &seg.arguments
+        && let Some(GenericArgument::Type(inner_ty)) = args.args.first()
+    {
+        return Some(inner_ty);
     }
     None
-}
\ No newline at end of file
+}
+/// Resolves a value of type `ty` from the DI container: `Arc<T>` shares the instance, any other
+/// type is cloned out of it.
+pub fn inject_expr(ty: &Type) -> proc_macro2::TokenStream {
+    match is_arc_type(ty) {
+        Some(inner) => quote! { ::exum::global_container().get::<#inner>().await },
+        None => quote! { ::std::clone::Clone::clone(&*::exum::global_container().get::<#ty>().await) },
+    }
+}
+
+/// Name of the type `url_for!` resolves for a handler, `__exum_url_{Controller}_{method}` inside controllers.
+pub fn url_ident(controller: Option<&str>, fn_name: &str) -> syn::Ident {
+    let name = match controller {
+        Some(controller) => format!("__exum_url_{}_{}", controller, fn_name),
+        None => format!("__exum_url_{}", fn_name),
+    };
+    syn::Ident::new(&name, proc_macro2::Span::call_site())
+}
@@ -0,0 +1,456 @@
+#![cfg(feature = "auth")]
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt,
+    sync::Arc,
+};
+
+use axum::{
+    extract::FromRequestParts,
+    http::{header, request::Parts, HeaderMap, HeaderName, HeaderValue, StatusCode},
+};
+use base64::Engine;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+
+use crate::{
+    config::{get_config, get_value},
+    dependency_container::StateFuture,
+    global_container,
+    rejection::ExumRejection,
+    StateDef, StateDefFn,
+};
+
+/// The `[auth]` section of the config tree. A scheme is enabled when its table is present.
+///
+/// Secrets are usually `${VAR}` placeholders filled from the environment; a secret left empty
+/// or unresolved fails [`ConfigVerifier::from_config`].
+///
+/// ```toml
+/// [auth]
+/// realm = "my-app"
+///
+/// [auth.jwt]
+/// algorithm = "RS256"
+/// public_key_file = "keys/jwt.pub.pem"
+/// issuer = "https://auth.example.com"
+///
+/// [auth.basic.users]
+/// admin = "${ADMIN_PASSWORD}"
+///
+/// [auth.api_key]
+/// header = "x-api-key"
+/// keys = { reporting = "${REPORTING_KEY}" }
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AuthConfig {
+    pub realm: String,
+    pub jwt: Option<JwtConfig>,
+    pub basic: Option<BasicConfig>,
+    pub api_key: Option<ApiKeyConfig>,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            realm: "exum".to_string(),
+            jwt: None,
+            basic: None,
+            api_key: None,
+        }
+    }
+}
+
+impl AuthConfig {
+    pub fn load() -> Self {
+        get_value(get_config(), "auth").unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct JwtConfig {
+    /// `HS256` (uses `secret`) or `RS256` (uses `public_key` / `public_key_file`).
+    pub algorithm: String,
+    pub secret: Option<String>,
+    pub public_key: Option<String>,
+    pub public_key_file: Option<String>,
+    pub issuer: Option<String>,
+    pub audience: Option<Vec<String>>,
+    /// Clock skew tolerated for `exp` / `nbf`, in seconds.
+    pub leeway: u64,
+}
+
+impl Default for JwtConfig {
+    fn default() -> Self {
+        Self {
+            algorithm: "HS256".to_string(),
+            secret: None,
+            public_key: None,
+            public_key_file: None,
+            issuer: None,
+            audience: None,
+            leeway: 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct BasicConfig {
+    /// username -> password
+    pub users: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ApiKeyConfig {
+    pub header: String,
+    /// subject -> key
+    pub keys: HashMap<String, String>,
+}
+
+impl Default for ApiKeyConfig {
+    fn default() -> Self {
+        Self {
+            header: "x-api-key".to_string(),
+            keys: HashMap::new(),
+        }
+    }
+}
+
+/// Credentials found in a request.
+#[derive(Debug, Clone, Copy)]
+pub enum Credentials<'a> {
+    Bearer(&'a str),
+    Basic { username: &'a str, password: &'a str },
+    ApiKey(&'a str),
+}
+
+#[derive(Debug, Clone)]
+pub enum AuthError {
+    MissingCredentials,
+    InvalidToken(String),
+    InvalidCredentials,
+    InvalidClaims(String),
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthError::MissingCredentials => write!(f, "Missing credentials"),
+            AuthError::InvalidToken(e) => write!(f, "Invalid token: {}", e),
+            AuthError::InvalidCredentials => write!(f, "Invalid credentials"),
+            AuthError::InvalidClaims(e) => write!(f, "Invalid claims: {}", e),
+        }
+    }
+}
+
+impl AuthError {
+    fn code(&self) -> &'static str {
+        match self {
+            AuthError::MissingCredentials => "missing_credentials",
+            AuthError::InvalidToken(_) => "invalid_token",
+            AuthError::InvalidCredentials => "invalid_credentials",
+            AuthError::InvalidClaims(_) => "invalid_claims",
+        }
+    }
+}
+
+/// Checks credentials and returns the claims of the authenticated principal.
+///
+/// The claims are deserialized into the `#[current_user]` parameter type.
+pub trait Verifier: Send + Sync + 'static {
+    fn verify(&self, credentials: Credentials<'_>) -> Result<Value, AuthError>;
+}
+
+struct JwtVerifier {
+    key: DecodingKey,
+    validation: Validation,
+}
+
+impl JwtVerifier {
+    fn from_config(config: &JwtConfig) -> Result<Self, String> {
+        let (algorithm, key) = match config.algorithm.to_ascii_uppercase().as_str() {
+            "HS256" => {
+                let secret = config.secret.as_ref().ok_or("`secret` is required for HS256")?;
+                check_secret("`secret`", secret)?;
+                (Algorithm::HS256, DecodingKey::from_secret(secret.as_bytes()))
+            }
+            "RS256" => {
+                let pem = match (&config.public_key, &config.public_key_file) {
+                    (Some(pem), _) => {
+                        check_secret("`public_key`", pem)?;
+                        pem.clone()
+                    }
+                    (None, Some(path)) => std::fs::read_to_string(path)
+                        .map_err(|e| format!("failed to read `{}`: {}", path, e))?,
+                    (None, None) => return Err("`public_key` or `public_key_file` is required for RS256".to_string()),
+                };
+                let key = DecodingKey::from_rsa_pem(pem.as_bytes()).map_err(|e| e.to_string())?;
+                (Algorithm::RS256, key)
+            }
+            other => return Err(format!("unsupported algorithm `{}`", other)),
+        };
+        let mut validation = Validation::new(algorithm);
+        validation.leeway = config.leeway;
+        if let Some(issuer) = &config.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        match &config.audience {
+            Some(audience) => validation.set_audience(audience),
+            None => validation.validate_aud = false,
+        }
+        Ok(Self { key, validation })
+    }
+}
+
+/// Rejects a secret that is empty or still a `${VAR}` placeholder, which would otherwise be
+/// accepted as the literal credential when the variable is not set.
+fn check_secret(name: &str, secret: &str) -> Result<(), String> {
+    if secret.is_empty() {
+        return Err(format!("{} is empty", name));
+    }
+    if secret.starts_with("${") && secret.ends_with('}') {
+        return Err(format!(
+            "{} is the unresolved placeholder `{}`, set the environment variable `{}`",
+            name,
+            secret,
+            &secret[2..secret.len() - 1]
+        ));
+    }
+    Ok(())
+}
+
+/// Default [`Verifier`] backed by the `[auth]` config.
+pub struct ConfigVerifier {
+    jwt: Option<JwtVerifier>,
+    /// username -> password
+    users: HashMap<String, String>,
+    /// subject -> key
+    api_keys: HashMap<String, String>,
+}
+
+impl ConfigVerifier {
+    /// Like [`try_from_config`](Self::try_from_config), panicking on an invalid configuration.
+    pub fn from_config(config: &AuthConfig) -> Self {
+        Self::try_from_config(config).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Fails when a scheme is misconfigured or one of its secrets is empty or an unresolved
+    /// `${VAR}` placeholder.
+    pub fn try_from_config(config: &AuthConfig) -> Result<Self, String> {
+        let jwt = match &config.jwt {
+            Some(jwt) => Some(
+                JwtVerifier::from_config(jwt).map_err(|e| format!("Invalid [auth.jwt] configuration: {}", e))?,
+            ),
+            None => None,
+        };
+        let users = config.basic.as_ref().map(|b| b.users.clone()).unwrap_or_default();
+        for (username, password) in &users {
+            check_secret(&format!("password of `{}`", username), password)
+                .map_err(|e| format!("Invalid [auth.basic] configuration: {}", e))?;
+        }
+        let api_keys = config.api_key.as_ref().map(|k| k.keys.clone()).unwrap_or_default();
+        for (subject, key) in &api_keys {
+            check_secret(&format!("key of `{}`", subject), key)
+                .map_err(|e| format!("Invalid [auth.api_key] configuration: {}", e))?;
+        }
+        Ok(Self { jwt, users, api_keys })
+    }
+}
+
+/// Compares secrets without short-circuiting on the first differing byte.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+impl Verifier for ConfigVerifier {
+    fn verify(&self, credentials: Credentials<'_>) -> Result<Value, AuthError> {
+        match credentials {
+            Credentials::Bearer(token) => {
+                let jwt = self.jwt.as_ref().ok_or(AuthError::InvalidCredentials)?;
+                jsonwebtoken::decode::<Value>(token, &jwt.key, &jwt.validation)
+                    .map(|data| data.claims)
+                    .map_err(|e| AuthError::InvalidToken(e.to_string()))
+            }
+            Credentials::Basic { username, password } => match self.users.get(username) {
+                Some(expected) if constant_time_eq(expected.as_bytes(), password.as_bytes()) => {
+                    Ok(json!({ "sub": username }))
+                }
+                _ => Err(AuthError::InvalidCredentials),
+            },
+            Credentials::ApiKey(key) => self
+                .api_keys
+                .iter()
+                .find(|(_, k)| constant_time_eq(k.as_bytes(), key.as_bytes()))
+                .map(|(subject, _)| json!({ "sub": subject }))
+                .ok_or(AuthError::InvalidCredentials),
+        }
+    }
+}
+
+/// Authentication settings plus the verifier, resolved from the DI container by `#[current_user]`.
+///
+/// Swap it out in tests before the first request:
+///
+/// ```rust,ignore
+/// global_container().provide(AuthService::from_config(&AuthConfig::default()).with_verifier(FakeVerifier));
+/// ```
+pub struct AuthService {
+    realm: String,
+    bearer: bool,
+    basic: bool,
+    api_key_header: Option<HeaderName>,
+    verifier: Arc<dyn Verifier>,
+}
+
+impl AuthService {
+    pub fn from_config(config: &AuthConfig) -> Self {
+        let api_key_header = config.api_key.as_ref().map(|k| {
+            HeaderName::try_from(k.header.as_str())
+                .unwrap_or_else(|e| panic!("Invalid [auth.api_key] header `{}`: {}", k.header, e))
+        });
+        Self {
+            realm: config.realm.clone(),
+            bearer: config.jwt.is_some(),
+            basic: config.basic.is_some(),
+            api_key_header,
+            verifier: Arc::new(ConfigVerifier::from_config(config)),
+        }
+    }
+
+    pub fn with_verifier(mut self, verifier: impl Verifier) -> Self {
+        self.verifier = Arc::new(verifier);
+        self
+    }
+
+    /// Enables or disables the `Bearer`, `Basic` and API-key schemes independently of the config.
+    pub fn with_schemes(mut self, bearer: bool, basic: bool, api_key_header: Option<HeaderName>) -> Self {
+        self.bearer = bearer;
+        self.basic = basic;
+        self.api_key_header = api_key_header;
+        self
+    }
+
+    /// Returns the verified claims, or `None` when the request carries no credentials.
+    pub fn authenticate(&self, headers: &HeaderMap) -> Result<Option<Value>, AuthError> {
+        if let Some(auth) = headers.get(header::AUTHORIZATION) {
+            let auth = auth.to_str().map_err(|_| AuthError::InvalidCredentials)?;
+            let (scheme, rest) = auth.trim().split_once(' ').unwrap_or((auth, ""));
+            let rest = rest.trim();
+            if self.bearer && scheme.eq_ignore_ascii_case("bearer") {
+                return self.verifier.verify(Credentials::Bearer(rest)).map(Some);
+            }
+            if self.basic && scheme.eq_ignore_ascii_case("basic") {
+                let decoded = base64::engine::general_purpose::STANDARD
+                    .decode(rest)
+                    .ok()
+                    .and_then(|d| String::from_utf8(d).ok())
+                    .ok_or(AuthError::InvalidCredentials)?;
+                let (username, password) = decoded.split_once(':').ok_or(AuthError::InvalidCredentials)?;
+                return self.verifier.verify(Credentials::Basic { username, password }).map(Some);
+            }
+        }
+        if let Some(name) = &self.api_key_header
+            && let Some(key) = headers.get(name)
+        {
+            let key = key.to_str().map_err(|_| AuthError::InvalidCredentials)?;
+            return self.verifier.verify(Credentials::ApiKey(key)).map(Some);
+        }
+        Ok(None)
+    }
+
+    /// `401 Unauthorized` with a `WWW-Authenticate` challenge per enabled scheme.
+    pub fn reject(&self, error: AuthError) -> ExumRejection {
+        let mut rejection = ExumRejection::new(StatusCode::UNAUTHORIZED, error.code(), error.to_string());
+        let realm = self.realm.replace('"', "");
+        let mut challenges = Vec::new();
+        if self.bearer {
+            let mut challenge = format!("Bearer realm=\"{}\"", realm);
+            if matches!(error, AuthError::InvalidToken(_)) {
+                challenge.push_str(", error=\"invalid_token\"");
+            }
+            challenges.push(challenge);
+        }
+        if self.basic {
+            challenges.push(format!("Basic realm=\"{}\", charset=\"UTF-8\"", realm));
+        }
+        if let Some(name) = &self.api_key_header {
+            challenges.push(format!("ApiKey realm=\"{}\", header=\"{}\"", realm, name));
+        }
+        for challenge in challenges {
+            if let Ok(value) = HeaderValue::from_str(&challenge) {
+                rejection = rejection.with_header(header::WWW_AUTHENTICATE, value);
+            }
+        }
+        rejection
+    }
+
+    fn claims<T: DeserializeOwned>(&self, claims: Value) -> Result<T, ExumRejection> {
+        serde_json::from_value(claims).map_err(|e| self.reject(AuthError::InvalidClaims(e.to_string())))
+    }
+}
+
+fn init_auth_service() -> StateFuture {
+    Box::pin(async {
+        Arc::new(AuthService::from_config(&AuthConfig::load())) as Arc<dyn Any + Send + Sync>
+    })
+}
+
+// prewarmed so that an invalid [auth] section stops the app at startup, not on the first request
+fn auth_state_def() -> StateDef {
+    StateDef {
+        type_id: TypeId::of::<AuthService>(),
+        prewarm: true,
+        init_fn: init_auth_service,
+    }
+}
+
+inventory::submit! {
+    StateDefFn(auth_state_def)
+}
+
+/// The authenticated principal, generated for `#[current_user] user: Claims`.
+pub struct CurrentUser<T>(pub T);
+
+impl<S, T> FromRequestParts<S> for CurrentUser<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned,
+{
+    type Rejection = ExumRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let service = global_container().get::<AuthService>().await;
+        match service.authenticate(&parts.headers) {
+            Ok(Some(claims)) => service.claims(claims).map(Self),
+            Ok(None) => Err(service.reject(AuthError::MissingCredentials)),
+            Err(e) => Err(service.reject(e)),
+        }
+    }
+}
+
+/// Generated for `#[current_user] user: Option<Claims>`: `None` without credentials,
+/// but invalid credentials are still rejected.
+pub struct MaybeUser<T>(pub Option<T>);
+
+impl<S, T> FromRequestParts<S> for MaybeUser<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned,
+{
+    type Rejection = ExumRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let service = global_container().get::<AuthService>().await;
+        match service.authenticate(&parts.headers) {
+            Ok(Some(claims)) => service.claims(claims).map(|c| Self(Some(c))),
+            Ok(None) => Ok(Self(None)),
+            Err(e) => Err(service.reject(e)),
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use axum::{
+    extract::{FromRequest, FromRequestParts, Request},
+    http::{header, request::Parts, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Form, Json,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::rejection::ExumRejection;
+
+/// Body encodings understood by `#[b(auto)]` and [`Accept::respond`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyFormat {
+    Json,
+    Form,
+    #[cfg(feature = "msgpack")]
+    MsgPack,
+    #[cfg(feature = "cbor")]
+    Cbor,
+    #[cfg(feature = "xml")]
+    Xml,
+}
+
+impl BodyFormat {
+    /// Maps a media type (parameters are ignored) to an enabled format.
+    pub fn from_mime(mime: &str) -> Option<Self> {
+        let mime = essence(mime);
+        match mime.as_str() {
+            "application/json" => Some(Self::Json),
+            "application/x-www-form-urlencoded" => Some(Self::Form),
+            #[cfg(feature = "msgpack")]
+            "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => Some(Self::MsgPack),
+            #[cfg(feature = "cbor")]
+            "application/cbor" => Some(Self::Cbor),
+            #[cfg(feature = "xml")]
+            "application/xml" | "text/xml" => Some(Self::Xml),
+            m if m.ends_with("+json") => Some(Self::Json),
+            #[cfg(feature = "xml")]
+            m if m.ends_with("+xml") => Some(Self::Xml),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Form => "application/x-www-form-urlencoded",
+            #[cfg(feature = "msgpack")]
+            Self::MsgPack => "application/msgpack",
+            #[cfg(feature = "cbor")]
+            Self::Cbor => "application/cbor",
+            #[cfg(feature = "xml")]
+            Self::Xml => "application/xml",
+        }
+    }
+
+    /// Serializes `value` into a response with the matching `Content-Type`.
+    pub fn respond<T: Serialize>(self, value: &T) -> Response {
+        match self {
+            Self::Json => Json(value).into_response(),
+            Self::Form => Form(value).into_response(),
+            #[cfg(any(feature = "msgpack", feature = "cbor", feature = "xml"))]
+            format => match encode(format, value) {
+                Ok(bytes) => ([(header::CONTENT_TYPE, format.content_type())], bytes).into_response(),
+                Err(e) => {
+                    tracing::error!("Failed to serialize response body: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR.into_response()
+                }
+            },
+        }
+    }
+
+    #[cfg(any(feature = "msgpack", feature = "cbor", feature = "xml"))]
+    fn name(self) -> &'static str {
+        match self {
+            Self::Json => "JSON",
+            Self::Form => "form",
+            #[cfg(feature = "msgpack")]
+            Self::MsgPack => "MessagePack",
+            #[cfg(feature = "cbor")]
+            Self::Cbor => "CBOR",
+            #[cfg(feature = "xml")]
+            Self::Xml => "XML",
+        }
+    }
+}
+
+fn essence(mime: &str) -> String {
+    mime.split(';').next().unwrap_or("").trim().to_ascii_lowercase()
+}
+
+fn content_type(headers: &HeaderMap) -> Option<&str> {
+    headers.get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok())
+}
+
+fn unsupported_media_type(content_type: Option<&str>) -> ExumRejection {
+    let message = match content_type {
+        Some(ct) => format!("Unsupported content type `{}`", ct),
+        None => "Missing `Content-Type` header".to_string(),
+    };
+    ExumRejection::new(StatusCode::UNSUPPORTED_MEDIA_TYPE, "unsupported_media_type", message)
+}
+
+#[cfg(any(feature = "msgpack", feature = "cbor", feature = "xml"))]
+fn decode<T: DeserializeOwned>(format: BodyFormat, bytes: &[u8]) -> Result<T, ExumRejection> {
+    let result: Result<T, String> = match format {
+        BodyFormat::Json | BodyFormat::Form => unreachable!("decoded through axum extractors"),
+        #[cfg(feature = "msgpack")]
+        BodyFormat::MsgPack => rmp_serde::from_slice(bytes).map_err(|e| e.to_string()),
+        #[cfg(feature = "cbor")]
+        BodyFormat::Cbor => ciborium::from_reader(bytes).map_err(|e| e.to_string()),
+        #[cfg(feature = "xml")]
+        BodyFormat::Xml => std::str::from_utf8(bytes)
+            .map_err(|e| e.to_string())
+            .and_then(|s| quick_xml::de::from_str(s).map_err(|e| e.to_string())),
+    };
+    result.map_err(|e| {
+        ExumRejection::new(
+            StatusCode::BAD_REQUEST,
+            "invalid_body",
+            format!("Failed to deserialize the {} body: {}", format.name(), e),
+        )
+    })
+}
+
+#[cfg(any(feature = "msgpack", feature = "cbor", feature = "xml"))]
+fn encode<T: Serialize>(format: BodyFormat, value: &T) -> Result<Vec<u8>, String> {
+    match format {
+        BodyFormat::Json | BodyFormat::Form => unreachable!("encoded through axum responses"),
+        #[cfg(feature = "msgpack")]
+        BodyFormat::MsgPack => rmp_serde::to_vec_named(value).map_err(|e| e.to_string()),
+        #[cfg(feature = "cbor")]
+        BodyFormat::Cbor => {
+            let mut buf = Vec::new();
+            ciborium::into_writer(value, &mut buf).map_err(|e| e.to_string())?;
+            Ok(buf)
+        }
+        #[cfg(feature = "xml")]
+        BodyFormat::Xml => quick_xml::se::to_string(value)
+            .map(String::into_bytes)
+            .map_err(|e| e.to_string()),
+    }
+}
+
+/// Defines a body extractor / response type for a feature-gated format.
+#[cfg(any(feature = "msgpack", feature = "cbor", feature = "xml"))]
+macro_rules! format_body {
+    ($(#[$meta:meta])* $name:ident, $format:expr) => {
+        $(#[$meta])*
+        pub struct $name<T>(pub T);
+
+        impl<S, T> FromRequest<S> for $name<T>
+        where
+            S: Send + Sync,
+            T: DeserializeOwned,
+        {
+            type Rejection = ExumRejection;
+
+            async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+                let ct = content_type(req.headers());
+                if ct.and_then(BodyFormat::from_mime) != Some($format) {
+                    return Err(unsupported_media_type(ct));
+                }
+                let bytes = axum::body::Bytes::from_request(req, state).await?;
+                decode(($format), &bytes).map(Self)
+            }
+        }
+
+        impl<T: Serialize> IntoResponse for $name<T> {
+            fn into_response(self) -> Response {
+                ($format).respond(&self.0)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "msgpack")]
+format_body!(
+    /// MessagePack body, used by `#[b(msgpack)]`.
+    MsgPack,
+    BodyFormat::MsgPack
+);
+
+#[cfg(feature = "cbor")]
+format_body!(
+    /// CBOR body, used by `#[b(cbor)]`.
+    Cbor,
+    BodyFormat::Cbor
+);
+
+#[cfg(feature = "xml")]
+format_body!(
+    /// XML body, used by `#[b(xml)]`.
+    Xml,
+    BodyFormat::Xml
+);
+
+/// Body extractor used by `#[b(auto)]`, picking the decoder from the request `Content-Type`.
+///
+/// Unknown or missing content types are rejected with `415 Unsupported Media Type`.
+pub struct Auto<T>(pub T);
+
+impl<S, T> FromRequest<S> for Auto<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned,
+{
+    type Rejection = ExumRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let ct = content_type(req.headers()).map(|s| s.to_string());
+        match ct.as_deref().and_then(BodyFormat::from_mime) {
+            Some(BodyFormat::Json) => Ok(Self(Json::<T>::from_request(req, state).await?.0)),
+            Some(BodyFormat::Form) => Ok(Self(Form::<T>::from_request(req, state).await?.0)),
+            #[cfg(any(feature = "msgpack", feature = "cbor", feature = "xml"))]
+            Some(format) => {
+                let bytes = axum::body::Bytes::from_request(req, state).await?;
+                decode(format, &bytes).map(Self)
+            }
+            None => Err(unsupported_media_type(ct.as_deref())),
+        }
+    }
+}
+
+/// The response format preferred by the client's `Accept` header.
+///
+/// ```rust,ignore
+/// #[get("/users/{id}")]
+/// async fn get_user(id: u64, accept: Accept) -> Response {
+///     accept.respond(&User { id })
+/// }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Accept(pub Option<BodyFormat>);
+
+impl Accept {
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let Some(accept) = headers.get(header::ACCEPT).and_then(|v| v.to_str().ok()) else {
+            return Self(Some(BodyFormat::Json));
+        };
+        if accept.trim().is_empty() {
+            return Self(Some(BodyFormat::Json));
+        }
+        let mut ranges: Vec<(f32, &str)> = accept
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';');
+                let mime = parts.next()?.trim();
+                let q = parts
+                    .filter_map(|p| p.trim().strip_prefix("q="))
+                    .find_map(|q| q.trim().parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                (q > 0.0).then_some((q, mime))
+            })
+            .collect();
+        // stable sort keeps header order between equal weights
+        ranges.sort_by(|a, b| b.0.total_cmp(&a.0));
+        let format = ranges.into_iter().find_map(|(_, mime)| match essence(mime).as_str() {
+            "*/*" | "application/*" => Some(BodyFormat::Json),
+            m => BodyFormat::from_mime(m),
+        });
+        Self(format)
+    }
+
+    pub fn format(&self) -> Option<BodyFormat> {
+        self.0
+    }
+
+    /// Serializes `value` in the negotiated format, or returns `406 Not Acceptable`.
+    pub fn respond<T: Serialize>(&self, value: &T) -> Response {
+        match self.0 {
+            Some(format) => format.respond(value),
+            None => ExumRejection::new(
+                StatusCode::NOT_ACCEPTABLE,
+                "not_acceptable",
+                "None of the requested media types can be produced",
+            )
+            .into_response(),
+        }
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Accept {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self::from_headers(&parts.headers))
+    }
+}
@@ -56,7 +56,7 @@ fn merge_toml(base: &mut Value, other: &Value) {
 
 static _EXUM_CONFIG: OnceLock<Value> = OnceLock::new();
 pub fn get_config() -> &'static Value {
-    _EXUM_CONFIG.get_or_init(|| load_config())
+    _EXUM_CONFIG.get_or_init(load_config)
 }
 
 pub fn load_config() -> Value {
@@ -65,10 +65,10 @@ pub fn load_config() -> Value {
 
     let env = detect_env();
     let env_file = format!("config.{env}.toml");
-    if let Ok(env_str) = fs::read_to_string(&env_file) {
-        if let Ok(env_val) = toml::from_str::<Value>(&env_str) {
-            merge_toml(&mut base, &env_val);
-        }
+    if let Ok(env_str) = fs::read_to_string(&env_file)
+        && let Ok(env_val) = toml::from_str::<Value>(&env_str)
+    {
+        merge_toml(&mut base, &env_val);
     }
 
     inject_env_vars(&mut base);
@@ -1,5 +1,137 @@
+use std::any::TypeId;
+
+use axum::Router;
+use serde::Deserialize;
+
+use crate::config::{get_config, get_value};
+
 pub struct ControllerDef {
   pub router: fn() -> ::axum::Router,
+  pub type_id: fn() -> TypeId,
+  pub prefix: &'static str,
+  /// `module_path!()` of the controller, its first segment is the declaring crate.
+  pub module_path: &'static str,
+  /// Controllers mounted under this one; they are not merged into the top-level router themselves.
+  pub children: &'static [fn() -> TypeId],
+  /// Guards declared on the controller, which its children run too.
+  pub guards: &'static [&'static str],
+  /// `group = ".."` of the controller, see [`Application::mount_group`](crate::Application::mount_group).
+  pub group: Option<&'static str>,
+  /// Environments the controller is mounted in, see [`profile_active`](crate::profile_active).
+  pub profile: Option<&'static str>,
+}
+
+inventory::collect!(ControllerDef);
+
+/// Selects the crates whose controllers are mounted, loaded from the `[controllers]` config table:
+///
+/// ```toml
+/// [controllers]
+/// include = ["my_app", "users_api"]
+/// exclude = ["admin_api"]
+/// ```
+///
+/// Crate names are compared with `-` normalized to `_`. Nested controllers follow their parent.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ControllerFilter {
+    /// Only these crates when set, otherwise every crate.
+    pub include: Option<Vec<String>>,
+    pub exclude: Vec<String>,
+}
+
+impl ControllerFilter {
+    pub fn load() -> Self {
+        get_value(get_config(), "controllers").unwrap_or_default()
+    }
+
+    pub fn include_crate(mut self, name: &str) -> Self {
+        self.include.get_or_insert_default().push(name.to_string());
+        self
+    }
+
+    pub fn exclude_crate(mut self, name: &str) -> Self {
+        self.exclude.push(name.to_string());
+        self
+    }
+
+    pub fn allows(&self, def: &ControllerDef) -> bool {
+        let krate = def.module_path.split("::").next().unwrap_or_default();
+        let matches = |name: &String| name.replace('-', "_") == krate;
+        self.include.as_ref().is_none_or(|include| include.iter().any(matches)) && !self.exclude.iter().any(matches)
+    }
+}
+
+/// Implemented by `#[derive(Controller)]`; controllers are built once through the DI container.
+pub trait Controller: Send + Sync + 'static {}
+
+/// Implemented by `#[controller]` so parent controllers can mount their children.
+pub trait ControllerRoutes {
+    const PREFIX: &'static str;
+
+    fn routes() -> Router;
+}
+
+/// The shared controller instance used by `&self` handlers.
+pub async fn instance<T: Controller>() -> std::sync::Arc<T> {
+    crate::global_container().get::<T>().await
+}
+
+/// Nests a child controller's routes under the parent prefix.
+pub fn mount(prefix: &str, router: Router) -> Router {
+    let prefix = prefix.trim_end_matches('/');
+    if prefix.is_empty() {
+        router
+    } else {
+        Router::new().nest(prefix, router)
+    }
+}
+
+fn parent_of(type_id: TypeId) -> Option<&'static ControllerDef> {
+    inventory::iter::<ControllerDef>
+        .into_iter()
+        .find(|def| def.children.iter().any(|child| child() == type_id))
 }
 
-inventory::collect!(ControllerDef);
\ No newline at end of file
+/// The top-level controller `type_id` is mounted through, `None` when it or one of its parents
+/// is not active in the current environment.
+pub(crate) fn mounted_root(type_id: TypeId) -> Option<&'static ControllerDef> {
+    let mut def = inventory::iter::<ControllerDef>.into_iter().find(|def| (def.type_id)() == type_id)?;
+    loop {
+        if def.profile.is_some_and(|profile| !crate::profile_active(profile)) {
+            return None;
+        }
+        match parent_of((def.type_id)()) {
+            Some(parent) => def = parent,
+            None => return Some(def),
+        }
+    }
+}
+
+/// Whether the controller is mounted by a parent controller.
+pub(crate) fn is_nested(def: &ControllerDef) -> bool {
+    parent_of((def.type_id)()).is_some()
+}
+
+/// Prefixes of the parent controllers, outermost first.
+pub(crate) fn parent_prefix(type_id: TypeId) -> String {
+    let mut prefixes = Vec::new();
+    let mut current = type_id;
+    while let Some(parent) = parent_of(current) {
+        prefixes.push(parent.prefix.trim_end_matches('/'));
+        current = (parent.type_id)();
+    }
+    prefixes.reverse();
+    prefixes.concat()
+}
+
+/// Guards of the parent controllers, outermost first.
+pub(crate) fn parent_guards(type_id: TypeId) -> Vec<&'static str> {
+    let mut guards = Vec::new();
+    let mut current = type_id;
+    while let Some(parent) = parent_of(current) {
+        guards.push(parent.guards);
+        current = (parent.type_id)();
+    }
+    guards.into_iter().rev().flatten().copied().collect()
+}
@@ -1,5 +1,4 @@
 use std::{any::{Any, TypeId}, collections::HashMap, pin::Pin, sync::Arc};
-use inventory;
 
 use tokio::sync::{OnceCell};
 
@@ -24,7 +23,7 @@ pub fn global_container() -> Arc<LazyDependencyContainer> {
 pub struct StateDef {
     pub type_id: TypeId,
     pub prewarm: bool,
-    pub init_fn: fn() -> Pin<Box<dyn Future<Output = Arc<dyn Any + Send + Sync>> + Send>>,
+    pub init_fn: fn() -> StateFuture,
 }
 pub struct StateDefFn(pub fn() -> StateDef);
 
@@ -36,7 +35,7 @@ pub fn collect_states() -> Vec<StateDef> {
         .collect()
 }
 
-type StateFuture =
+pub(crate) type StateFuture =
     Pin<Box<dyn Future<Output = Arc<dyn Any + Send + Sync>> + Send>>;
 pub struct LazyDependencyContainer {
   registry: HashMap<TypeId, fn() -> StateFuture>,
@@ -78,16 +77,25 @@ impl LazyDependencyContainer {
     let type_id = TypeId::of::<T>();
     let init_fn = self.registry.get(&type_id).expect("No init function found for type");
     let cell = self.instances.get(&type_id).expect("No instance cell found for type");
-    let instance = cell.get_or_init(|| init_fn()).await.clone();
+    let instance = cell.get_or_init(init_fn).await.clone();
     instance.clone().downcast_arc::<T>().unwrap()
   }
 
+  /// Supplies the instance of a registered type before it is first resolved, e.g. to swap in a
+  /// fake in tests. Returns `false` if the type is unknown or already initialized.
+  pub fn provide<T: 'static + Send + Sync>(&self, value: T) -> bool {
+    match self.instances.get(&TypeId::of::<T>()) {
+        Some(cell) => cell.set(Arc::new(value) as Arc<dyn Any + Send + Sync>).is_ok(),
+        None => false,
+    }
+  }
+
   pub async fn prewarm_all(&self) {
       for (type_id, prewarm) in &self.prewarm_flags {
           if *prewarm {
               let init_fn = self.registry.get(type_id).unwrap();
               let cell = self.instances.get(type_id).unwrap();
-              let _ = cell.get_or_init(|| init_fn()).await;
+              let _ = cell.get_or_init(init_fn).await;
           }
       }
   }
@@ -2,12 +2,10 @@ use toml::Value;
 
 pub fn inject_env_vars(val: &mut Value) {
     match val {
-        Value::String(s) => {
-            if s.starts_with("${") && s.ends_with("}") {
-                let key = &s[2..s.len() - 1];
-                if let Ok(env_val) = std::env::var(key) {
-                    *s = env_val;
-                }
+        Value::String(s) if s.starts_with("${") && s.ends_with("}") => {
+            let key = &s[2..s.len() - 1];
+            if let Ok(env_val) = std::env::var(key) {
+                *s = env_val;
             }
         }
         Value::Table(map) => {
@@ -34,4 +32,10 @@ pub fn detect_env() -> String {
     } else {
         "prod".to_string()
     }
-}
\ No newline at end of file
+}
+
+/// Whether the current environment is one of `profiles`, a comma separated list such as `"dev,test"`.
+pub fn profile_active(profiles: &str) -> bool {
+    let env = detect_env();
+    profiles.split(',').any(|p| p.trim() == env)
+}
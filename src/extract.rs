@@ -0,0 +1,136 @@
+use std::{fmt::Display, str::FromStr};
+
+use axum::{
+    http::{header::COOKIE, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+use crate::rejection::ExumRejection;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamSource {
+    Header,
+    Cookie,
+}
+
+impl Display for ParamSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParamSource::Header => write!(f, "header"),
+            ParamSource::Cookie => write!(f, "cookie"),
+        }
+    }
+}
+
+/// Rejection produced by the `#[h]` and `#[cookie]` parameter extractors.
+#[derive(Debug, Clone)]
+pub enum ParamRejection {
+    Missing { source: ParamSource, name: String },
+    Invalid { source: ParamSource, name: String, reason: String },
+}
+
+impl Display for ParamRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParamRejection::Missing { source, name } => {
+                write!(f, "Missing required {} `{}`", source, name)
+            }
+            ParamRejection::Invalid { source, name, reason } => {
+                write!(f, "Invalid {} `{}`: {}", source, name, reason)
+            }
+        }
+    }
+}
+
+impl From<ParamRejection> for ExumRejection {
+    fn from(e: ParamRejection) -> Self {
+        let (code, name) = match &e {
+            ParamRejection::Missing { name, .. } => ("missing_parameter", name.clone()),
+            ParamRejection::Invalid { name, .. } => ("invalid_parameter", name.clone()),
+        };
+        ExumRejection::new(StatusCode::BAD_REQUEST, code, e.to_string()).with_param(name)
+    }
+}
+
+impl IntoResponse for ParamRejection {
+    fn into_response(self) -> Response {
+        ExumRejection::from(self).into_response()
+    }
+}
+
+fn parse_value<T>(source: ParamSource, name: &str, raw: &str) -> Result<T, ParamRejection>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    raw.parse::<T>().map_err(|e| ParamRejection::Invalid {
+        source,
+        name: name.to_string(),
+        reason: e.to_string(),
+    })
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Result<Option<&'a str>, ParamRejection> {
+    match headers.get(name) {
+        Some(value) => value.to_str().map(Some).map_err(|_| ParamRejection::Invalid {
+            source: ParamSource::Header,
+            name: name.to_string(),
+            reason: "value is not valid visible ASCII".to_string(),
+        }),
+        None => Ok(None),
+    }
+}
+
+pub fn header_opt<T>(headers: &HeaderMap, name: &str) -> Result<Option<T>, ParamRejection>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    header_str(headers, name)?
+        .map(|raw| parse_value(ParamSource::Header, name, raw))
+        .transpose()
+}
+
+pub fn header<T>(headers: &HeaderMap, name: &str) -> Result<T, ParamRejection>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    header_opt(headers, name)?.ok_or_else(|| ParamRejection::Missing {
+        source: ParamSource::Header,
+        name: name.to_string(),
+    })
+}
+
+/// Looks up a cookie across every `Cookie` header of the request.
+pub fn cookie_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(k, _)| k.trim() == name)
+        .map(|(_, v)| v.trim().trim_matches('"'))
+}
+
+pub fn cookie_opt<T>(headers: &HeaderMap, name: &str) -> Result<Option<T>, ParamRejection>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    cookie_value(headers, name)
+        .map(|raw| parse_value(ParamSource::Cookie, name, raw))
+        .transpose()
+}
+
+pub fn cookie<T>(headers: &HeaderMap, name: &str) -> Result<T, ParamRejection>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    cookie_opt(headers, name)?.ok_or_else(|| ParamRejection::Missing {
+        source: ParamSource::Cookie,
+        name: name.to_string(),
+    })
+}
@@ -13,6 +13,12 @@ pub fn response_bad_request() -> Response<Body> {
         .unwrap()
 }
 
+pub fn response_forbidden() -> Response<Body> {
+    Response::builder().status(StatusCode::FORBIDDEN)
+        .body(Body::from("403 Forbidden"))
+        .unwrap()
+}
+
 pub fn response_ok<T: Into<Body>>(body: T) -> Response<Body> {
     Response::builder().status(StatusCode::OK)
         .body(body.into())
@@ -31,6 +37,17 @@ pub fn response_method_not_allowed() -> Response<Body> {
         .unwrap()
 }
 
+/// The plain text response of `status`, falling back to `500` for statuses without one.
+pub fn response_for_status(status: StatusCode) -> Response<Body> {
+    match status {
+        StatusCode::NOT_FOUND => reponse_not_found(),
+        StatusCode::FORBIDDEN => response_forbidden(),
+        StatusCode::BAD_REQUEST => response_bad_request(),
+        StatusCode::METHOD_NOT_ALLOWED => response_method_not_allowed(),
+        _ => internal_server_error(),
+    }
+}
+
 pub fn cors_any() -> CorsLayer {
     CorsLayer::new()
         .allow_origin(Any)
@@ -0,0 +1,348 @@
+//! In-memory cache of metadata and small files for
+//! [`StaticFileService`](super::static_layer::StaticFileService), see
+//! [`StaticFileServiceBuilder::cache`](super::static_layer::StaticFileServiceBuilder::cache).
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Cursor,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use axum::body::Bytes;
+use tokio::io::AsyncReadExt;
+
+use super::vfs::{VfsFuture, VfsMetadata, VfsReader, VirtualFs};
+
+/// When cached entries are checked against the file system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Revalidate {
+    /// Compare size and mtime on every request: one `metadata` call, no `open`.
+    Mtime,
+    /// Trust entries for a while without touching the file system.
+    Ttl(Duration),
+    /// Trust entries until [`StaticCache::invalidate`] or [`StaticCache::clear`]. No file watcher
+    /// is included: call them from your own, e.g. one built with the `notify` crate.
+    Manual,
+}
+
+/// Counters of a [`StaticCache`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Metadata lookups answered from the cache.
+    pub hits: u64,
+    pub misses: u64,
+    /// File reads answered from the cache.
+    pub content_hits: u64,
+    pub content_misses: u64,
+    pub evictions: u64,
+    pub entries: usize,
+    /// Bytes of cached file content.
+    pub bytes: u64,
+}
+
+impl CacheStats {
+    /// Share of metadata lookups and file reads answered from the cache, `0.0` before any request.
+    pub fn hit_rate(&self) -> f64 {
+        let hits = self.hits + self.content_hits;
+        let total = hits + self.misses + self.content_misses;
+        if total == 0 { 0.0 } else { hits as f64 / total as f64 }
+    }
+}
+
+/// Mount index and path inside the mount.
+type Key = (usize, String);
+
+#[derive(Debug)]
+struct Entry {
+    /// Path relative to the service, for [`StaticCache::invalidate`].
+    rel: String,
+    metadata: VfsMetadata,
+    content: Option<Bytes>,
+    checked: Instant,
+    tick: u64,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    entries: HashMap<Key, Entry>,
+    /// Least recently used first.
+    order: BTreeMap<u64, Key>,
+    tick: u64,
+    bytes: u64,
+}
+
+impl State {
+    fn touch(&mut self, key: &Key) {
+        self.tick += 1;
+        let tick = self.tick;
+        if let Some(entry) = self.entries.get_mut(key) {
+            self.order.remove(&entry.tick);
+            entry.tick = tick;
+            self.order.insert(tick, key.clone());
+        }
+    }
+
+    fn remove(&mut self, key: &Key) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.order.remove(&entry.tick);
+            self.bytes -= entry.content.map_or(0, |c| c.len() as u64);
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    max_bytes: u64,
+    max_file_size: u64,
+    max_entries: usize,
+    revalidate: Revalidate,
+    state: Mutex<State>,
+    content_types: Mutex<HashMap<String, Arc<str>>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    content_hits: AtomicU64,
+    content_misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+/// A least-recently-used cache of file metadata and the content of small files, shared by
+/// the clones of a service. Keep a clone to read [`stats`](Self::stats) or invalidate entries.
+#[derive(Debug, Clone)]
+pub struct StaticCache(Arc<Inner>);
+
+impl StaticCache {
+    /// Caches file content up to `max_bytes` in total; files over 1 MiB are only cached as metadata.
+    pub fn new(max_bytes: u64) -> Self {
+        Self(Arc::new(Inner {
+            max_bytes,
+            max_file_size: 1024 * 1024,
+            max_entries: 10_000,
+            revalidate: Revalidate::Mtime,
+            state: Mutex::default(),
+            content_types: Mutex::default(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            content_hits: AtomicU64::new(0),
+            content_misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }))
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        Arc::get_mut(&mut self.0).expect("configure the cache before cloning it")
+    }
+
+    /// Files larger than this are served from the file system, 1 MiB by default.
+    pub fn max_file_size(mut self, max: u64) -> Self {
+        self.inner_mut().max_file_size = max;
+        self
+    }
+
+    /// The number of cached paths, 10 000 by default.
+    pub fn max_entries(mut self, max: usize) -> Self {
+        self.inner_mut().max_entries = max;
+        self
+    }
+
+    /// [`Revalidate::Mtime`] by default.
+    pub fn revalidate(mut self, revalidate: Revalidate) -> Self {
+        self.inner_mut().revalidate = revalidate;
+        self
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let state = self.0.state.lock().unwrap();
+        CacheStats {
+            hits: self.0.hits.load(Ordering::Relaxed),
+            misses: self.0.misses.load(Ordering::Relaxed),
+            content_hits: self.0.content_hits.load(Ordering::Relaxed),
+            content_misses: self.0.content_misses.load(Ordering::Relaxed),
+            evictions: self.0.evictions.load(Ordering::Relaxed),
+            entries: state.entries.len(),
+            bytes: state.bytes,
+        }
+    }
+
+    /// Drops the entries of `rel`, a path relative to the service such as `assets/app.js`.
+    pub fn invalidate(&self, rel: &str) {
+        let rel = rel.trim_matches('/');
+        let mut state = self.0.state.lock().unwrap();
+        let keys: Vec<Key> = state
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.rel == rel)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in keys {
+            state.remove(&key);
+        }
+    }
+
+    pub fn clear(&self) {
+        *self.0.state.lock().unwrap() = State::default();
+    }
+
+    /// `content_type(rel)` memoized per extension.
+    pub(crate) fn content_type(&self, rel: &str, content_type: impl FnOnce() -> String) -> Arc<str> {
+        let ext = rel.rsplit('/').next().and_then(|name| name.rsplit_once('.')).map_or("", |(_, ext)| ext);
+        let mut types = self.0.content_types.lock().unwrap();
+        types.entry(ext.to_string()).or_insert_with(|| content_type().into()).clone()
+    }
+
+    fn fresh(&self, entry: &Entry) -> bool {
+        match self.0.revalidate {
+            Revalidate::Mtime => false,
+            Revalidate::Ttl(ttl) => entry.checked.elapsed() < ttl,
+            Revalidate::Manual => true,
+        }
+    }
+
+    fn insert(&self, key: Key, rel: String, metadata: VfsMetadata) {
+        let mut state = self.0.state.lock().unwrap();
+        state.remove(&key);
+        while state.entries.len() >= self.0.max_entries.max(1) {
+            let Some((_, oldest)) = state.order.pop_first() else { break };
+            state.remove(&oldest);
+            self.0.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+        state.tick += 1;
+        let tick = state.tick;
+        state.order.insert(tick, key.clone());
+        state.entries.insert(key, Entry { rel, metadata, content: None, checked: Instant::now(), tick });
+    }
+
+    /// Stores `content` for an entry, evicting least recently used content over the size cap.
+    fn store_content(&self, key: &Key, content: Bytes) {
+        let len = content.len() as u64;
+        if len > self.0.max_bytes {
+            return;
+        }
+        let mut state = self.0.state.lock().unwrap();
+        while state.bytes + len > self.0.max_bytes {
+            let Some((_, oldest)) = state.order.pop_first() else { break };
+            state.remove(&oldest);
+            self.0.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+        if !state.entries.contains_key(key) {
+            return;
+        }
+        state.bytes += len;
+        state.touch(key);
+        if let Some(entry) = state.entries.get_mut(key) {
+            entry.content = Some(content);
+        }
+    }
+}
+
+/// A mount served through a [`StaticCache`].
+pub(crate) struct CachedFs {
+    inner: Arc<dyn VirtualFs>,
+    cache: StaticCache,
+    mount: usize,
+    prefix: String,
+}
+
+impl CachedFs {
+    pub(crate) fn new(inner: Arc<dyn VirtualFs>, cache: StaticCache, mount: usize, prefix: String) -> Self {
+        Self { inner, cache, mount, prefix }
+    }
+
+    fn key(&self, path: &str) -> Key {
+        (self.mount, path.to_string())
+    }
+
+    fn rel(&self, path: &str) -> String {
+        match (self.prefix.is_empty(), path.is_empty()) {
+            (true, _) => path.to_string(),
+            (false, true) => self.prefix.clone(),
+            (false, false) => format!("{}/{}", self.prefix, path),
+        }
+    }
+
+    fn cached(&self, key: &Key) -> Option<(VfsMetadata, bool)> {
+        let mut state = self.cache.0.state.lock().unwrap();
+        let entry = state.entries.get(key)?;
+        let result = (entry.metadata, self.cache.fresh(entry));
+        state.touch(key);
+        Some(result)
+    }
+}
+
+impl VirtualFs for CachedFs {
+    fn metadata<'a>(&'a self, path: &'a str) -> VfsFuture<'a, VfsMetadata> {
+        Box::pin(async move {
+            let key = self.key(path);
+            let cached = self.cached(&key);
+            if let Some((metadata, true)) = cached {
+                self.cache.0.hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(metadata);
+            }
+            let metadata = match self.inner.metadata(path).await {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    self.cache.0.state.lock().unwrap().remove(&key);
+                    return Err(e);
+                }
+            };
+            match cached {
+                // unchanged on disk: keep the cached content
+                Some((old, _)) if old == metadata => {
+                    self.cache.0.hits.fetch_add(1, Ordering::Relaxed);
+                    if let Some(entry) = self.cache.0.state.lock().unwrap().entries.get_mut(&key) {
+                        entry.checked = Instant::now();
+                    }
+                }
+                _ => {
+                    self.cache.0.misses.fetch_add(1, Ordering::Relaxed);
+                    self.cache.insert(key, self.rel(path), metadata);
+                }
+            }
+            Ok(metadata)
+        })
+    }
+
+    fn open<'a>(&'a self, path: &'a str, offset: u64) -> VfsFuture<'a, VfsReader> {
+        Box::pin(async move {
+            let key = self.key(path);
+            let (content, len) = {
+                let state = self.cache.0.state.lock().unwrap();
+                match state.entries.get(&key) {
+                    Some(entry) => (entry.content.clone(), Some(entry.metadata.len)),
+                    None => (None, None),
+                }
+            };
+            let reader = |bytes: Bytes| {
+                let start = (offset as usize).min(bytes.len());
+                Box::new(Cursor::new(bytes.slice(start..))) as VfsReader
+            };
+            if let Some(content) = content {
+                self.cache.0.content_hits.fetch_add(1, Ordering::Relaxed);
+                self.cache.0.state.lock().unwrap().touch(&key);
+                return Ok(reader(content));
+            }
+            match len {
+                Some(len) if len <= self.cache.0.max_file_size => {
+                    self.cache.0.content_misses.fetch_add(1, Ordering::Relaxed);
+                    let mut bytes = Vec::with_capacity(len as usize);
+                    self.inner.open(path, 0).await?.read_to_end(&mut bytes).await?;
+                    let bytes = Bytes::from(bytes);
+                    self.cache.store_content(&key, bytes.clone());
+                    Ok(reader(bytes))
+                }
+                _ => self.inner.open(path, offset).await,
+            }
+        })
+    }
+
+    fn read_dir<'a>(&'a self, path: &'a str) -> VfsFuture<'a, Vec<(String, VfsMetadata)>> {
+        self.inner.read_dir(path)
+    }
+
+    fn etag(&self, path: &str) -> Option<String> {
+        self.inner.etag(path)
+    }
+}
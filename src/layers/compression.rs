@@ -0,0 +1,116 @@
+//! Content negotiation over `Accept-Encoding` for static files: precompressed siblings
+//! (`app.js.br`, `app.js.gz`, `app.js.zst`) and, with the `compression` feature, on-the-fly compression.
+
+#[cfg(feature = "compression")]
+use mime_guess::mime::{self, Mime};
+
+/// A content coding static files can be served with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    Brotli,
+    Gzip,
+    Zstd,
+}
+
+impl Encoding {
+    /// The `Content-Encoding` token.
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gzip",
+            Encoding::Zstd => "zstd",
+        }
+    }
+
+    /// The extension of precompressed files.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gz",
+            Encoding::Zstd => "zst",
+        }
+    }
+
+    /// The precompressed sibling of `path`, e.g. `app.js.br`.
+    pub(crate) fn sibling(self, path: &str) -> String {
+        format!("{}.{}", path, self.extension())
+    }
+}
+
+/// The encodings of `supported` the client accepts, best first: higher `q` wins, ties keep the
+/// order of `supported`.
+pub(crate) fn negotiate(accept_encoding: Option<&str>, supported: &[Encoding]) -> Vec<Encoding> {
+    let Some(accept_encoding) = accept_encoding else {
+        return Vec::new();
+    };
+    let entries: Vec<(&str, f32)> = accept_encoding
+        .split(',')
+        .filter_map(|entry| {
+            let mut params = entry.split(';');
+            let coding = params.next()?.trim();
+            let q = params
+                .filter_map(|p| p.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            (!coding.is_empty()).then_some((coding, q))
+        })
+        .collect();
+    let quality = |encoding: Encoding| {
+        let find = |name: &str| entries.iter().find(|(c, _)| c.eq_ignore_ascii_case(name)).map(|(_, q)| *q);
+        // `x-gzip` is an alias kept for old clients
+        let alias = (encoding == Encoding::Gzip).then(|| find("x-gzip")).flatten();
+        find(encoding.name()).or(alias).or_else(|| find("*")).unwrap_or(0.0)
+    };
+    let mut accepted: Vec<(Encoding, f32)> = supported
+        .iter()
+        .map(|e| (*e, quality(*e)))
+        .filter(|(_, q)| *q > 0.0)
+        .collect();
+    accepted.sort_by(|a, b| b.1.total_cmp(&a.1));
+    accepted.into_iter().map(|(e, _)| e).collect()
+}
+
+/// Distinguishes the tag of a compressed representation from the one of the file, keeping
+/// the weak prefix: `W/"1f-6650"` becomes `W/"1f-6650-br"`.
+pub(crate) fn encoded_etag(etag: String, encoding: Encoding) -> String {
+    match etag.strip_suffix('"') {
+        Some(tag) => format!("{}-{}\"", tag, encoding.extension()),
+        None => etag,
+    }
+}
+
+/// Text-like types worth compressing on the fly.
+#[cfg(feature = "compression")]
+pub(crate) fn is_compressible(mime: &Mime) -> bool {
+    mime.type_() == mime::TEXT
+        || mime.suffix().is_some_and(|s| s == mime::JSON || s == mime::XML)
+        || (mime.type_() == mime::APPLICATION
+            && matches!(mime.subtype().as_str(), "javascript" | "json" | "xml" | "wasm"))
+}
+
+/// On-the-fly compression settings, see
+/// [`StaticFileServiceBuilder::compress_on_the_fly`](super::static_layer::StaticFileServiceBuilder::compress_on_the_fly).
+#[cfg(feature = "compression")]
+#[derive(Debug, Clone)]
+pub(crate) struct DynamicCompression {
+    pub encodings: Vec<Encoding>,
+    pub min_size: u64,
+}
+
+#[cfg(feature = "compression")]
+pub(crate) async fn compressed_body(
+    fs: &dyn super::vfs::VirtualFs,
+    path: &str,
+    encoding: Encoding,
+) -> std::io::Result<axum::body::Body> {
+    use async_compression::tokio::bufread::{BrotliEncoder, GzipEncoder, ZstdEncoder};
+    use tokio::io::{AsyncRead, BufReader};
+
+    let file = BufReader::new(fs.open(path, 0).await?);
+    let reader: Box<dyn AsyncRead + Send + Unpin> = match encoding {
+        Encoding::Brotli => Box::new(BrotliEncoder::new(file)),
+        Encoding::Gzip => Box::new(GzipEncoder::new(file)),
+        Encoding::Zstd => Box::new(ZstdEncoder::new(file)),
+    };
+    Ok(axum::body::Body::from_stream(tokio_util::io::ReaderStream::new(reader)))
+}
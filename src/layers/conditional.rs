@@ -0,0 +1,150 @@
+//! ETags and conditional requests (`If-Match`, `If-None-Match`, `If-Modified-Since`,
+//! `If-Unmodified-Since`) for static files, evaluated in the order of RFC 9110 §13.2.2.
+
+use std::{
+    collections::HashMap,
+    hash::Hasher,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use axum::http::{header, request::Parts, Method, StatusCode};
+use tokio::io::AsyncReadExt;
+
+use super::vfs::VirtualFs;
+
+/// How [`StaticFileService`](super::static_layer::StaticFileService) computes the `ETag` of a file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ETagStrategy {
+    /// No `ETag` header; conditional requests rely on `Last-Modified`.
+    Disabled,
+    /// `W/"{size}-{mtime}"`, cheap and good enough for caching.
+    #[default]
+    Weak,
+    /// `"{size}-{mtime}"`, also usable with `If-Match` and `If-Range`. The mtime has the
+    /// resolution of the file system, nanoseconds on most; prefer
+    /// [`ContentHash`](Self::ContentHash) where timestamps are coarser.
+    Strong,
+    /// A strong tag hashed from the file content, recomputed when the size or mtime changes.
+    ContentHash,
+}
+
+/// The size and mtime a hash was computed for, and the resulting tag.
+type HashEntry = (u64, Option<SystemTime>, String);
+
+/// Content hashes keyed by mount and path, valid while size and mtime are unchanged.
+#[derive(Debug, Default)]
+pub(crate) struct HashCache(Mutex<HashMap<(usize, String), HashEntry>>);
+
+fn secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+/// Nanoseconds, so a file replaced within the same second with the same size gets a new tag.
+fn nanos(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or_default()
+}
+
+pub(crate) async fn etag(
+    strategy: ETagStrategy,
+    cache: &HashCache,
+    fs: &dyn VirtualFs,
+    path: &str,
+    len: u64,
+    modified: Option<SystemTime>,
+) -> std::io::Result<Option<String>> {
+    if strategy != ETagStrategy::Disabled
+        && let Some(tag) = fs.etag(path)
+    {
+        return Ok(Some(tag));
+    }
+    let tag = format!("{:x}-{:x}", len, modified.map(nanos).unwrap_or_default());
+    Ok(match strategy {
+        ETagStrategy::Disabled => None,
+        ETagStrategy::Weak => Some(format!("W/\"{}\"", tag)),
+        ETagStrategy::Strong => Some(format!("\"{}\"", tag)),
+        ETagStrategy::ContentHash => {
+            let key = (fs as *const dyn VirtualFs as *const () as usize, path.to_string());
+            if let Some((_, _, tag)) = cache
+                .0
+                .lock()
+                .unwrap()
+                .get(&key)
+                .filter(|(l, m, _)| *l == len && *m == modified)
+            {
+                return Ok(Some(tag.clone()));
+            }
+            let tag = format!("\"{:016x}\"", hash_file(fs, path).await?);
+            cache.0.lock().unwrap().insert(key, (len, modified, tag.clone()));
+            Some(tag)
+        }
+    })
+}
+
+pub(crate) async fn hash_file(fs: &dyn VirtualFs, path: &str) -> std::io::Result<u64> {
+    let mut file = fs.open(path, 0).await?;
+    let mut hasher = std::hash::DefaultHasher::new();
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.write(&buf[..n]);
+    }
+    Ok(hasher.finish())
+}
+
+fn opaque(tag: &str) -> &str {
+    tag.trim().trim_start_matches("W/")
+}
+
+/// Whether an `If-Match`/`If-None-Match` list matches `etag`, comparing strongly or weakly.
+fn list_matches(list: &str, etag: Option<&str>, strong: bool) -> bool {
+    list.split(',').map(str::trim).any(|candidate| {
+        candidate == "*"
+            || etag.is_some_and(|etag| {
+                if strong {
+                    !candidate.starts_with("W/") && !etag.starts_with("W/") && candidate == etag
+                } else {
+                    opaque(candidate) == opaque(etag)
+                }
+            })
+    })
+}
+
+fn header_date(req: &Parts, name: header::HeaderName) -> Option<SystemTime> {
+    req.headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| httpdate::parse_http_date(v).ok())
+}
+
+/// Evaluates the preconditions of `req`, returning `304` or `412` when the file must not be sent.
+pub(crate) fn evaluate(req: &Parts, etag: Option<&str>, modified: Option<SystemTime>) -> Option<StatusCode> {
+    let header_str = |name| req.headers.get(name).and_then(|v: &header::HeaderValue| v.to_str().ok());
+    let not_modified_since = |date: SystemTime| modified.is_some_and(|m| secs(m) <= secs(date));
+
+    if let Some(if_match) = header_str(header::IF_MATCH) {
+        if !list_matches(if_match, etag, true) {
+            return Some(StatusCode::PRECONDITION_FAILED);
+        }
+    } else if let Some(date) = header_date(req, header::IF_UNMODIFIED_SINCE)
+        && !not_modified_since(date)
+    {
+        return Some(StatusCode::PRECONDITION_FAILED);
+    }
+
+    let safe = req.method == Method::GET || req.method == Method::HEAD;
+    if let Some(if_none_match) = header_str(header::IF_NONE_MATCH) {
+        if list_matches(if_none_match, etag, false) {
+            return Some(if safe { StatusCode::NOT_MODIFIED } else { StatusCode::PRECONDITION_FAILED });
+        }
+    } else if safe
+        && let Some(date) = header_date(req, header::IF_MODIFIED_SINCE)
+        && not_modified_since(date)
+    {
+        return Some(StatusCode::NOT_MODIFIED);
+    }
+    None
+}
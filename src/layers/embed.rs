@@ -0,0 +1,304 @@
+//! Static files baked into the binary with [`embed!`](crate::embed), served like
+//! [`StaticFileService`] serves a directory.
+//!
+//! ```ignore
+//! let router = EmbeddedFileService::builder(embed!("./dist"))
+//!     .with_spa_fallback(true)
+//!     .build_router("/");
+//! ```
+//!
+//! Debug builds read the embedded directory from disk instead, so edits show up without
+//! recompiling; see [`EmbeddedFileServiceBuilder::live_reload`].
+//!
+//! Cargo only tracks the files `include_bytes!` embedded, so adding a file to the directory
+//! does not rebuild the binary. Declare the directory in the `build.rs` of the crate:
+//!
+//! ```ignore
+//! fn main() {
+//!     println!("cargo:rerun-if-changed=dist");
+//! }
+//! ```
+
+use std::{
+    convert::Infallible,
+    io::{Cursor, ErrorKind},
+    task::{Context, Poll},
+    time::{Duration, UNIX_EPOCH},
+};
+
+use axum::{
+    body::Body,
+    http::{Request, Response, StatusCode},
+    Router,
+};
+use tower::Service;
+use tower_http::cors::CorsLayer;
+
+use crate::{fast_builder, UrlEncodedMethodExt};
+use super::{
+    compression::{self, Encoding},
+    fallback::SpaFallback,
+    security::DotFiles,
+    static_layer::{StaticFileService, StaticFileServiceBuilder},
+    vfs::{self, VfsFuture, VfsMetadata, VfsReader, VirtualFs},
+};
+
+/// A file generated by [`embed!`](crate::embed).
+#[derive(Debug)]
+pub struct EmbeddedFile {
+    /// Path relative to the embedded directory, separated by `/`.
+    pub path: &'static str,
+    pub bytes: &'static [u8],
+    pub content_type: &'static str,
+    /// Strong tag hashed from the content at compile time.
+    pub etag: &'static str,
+    /// Modification time of the source file when it was embedded, in seconds since the Unix
+    /// epoch; `0` when unknown.
+    pub modified: u64,
+    /// Precompressed siblings found next to the file.
+    pub encoded: &'static [(Encoding, &'static [u8])],
+}
+
+impl EmbeddedFile {
+    fn metadata(&self, len: usize) -> VfsMetadata {
+        let modified = (self.modified > 0).then(|| UNIX_EPOCH + Duration::from_secs(self.modified));
+        VfsMetadata::file(len as u64, modified)
+    }
+}
+
+/// The files of a directory embedded with [`embed!`](crate::embed), sorted by path.
+#[derive(Debug, Clone, Copy)]
+pub struct EmbeddedAssets {
+    root: &'static str,
+    files: &'static [EmbeddedFile],
+    live: bool,
+}
+
+impl EmbeddedAssets {
+    /// Used by [`embed!`](crate::embed); `files` must be sorted by path.
+    pub const fn new(root: &'static str, files: &'static [EmbeddedFile], live: bool) -> Self {
+        Self { root, files, live }
+    }
+
+    /// The directory the files were embedded from.
+    pub fn root(&self) -> &'static str {
+        self.root
+    }
+
+    pub fn get(&self, path: &str) -> Option<&'static EmbeddedFile> {
+        let files = self.files;
+        files.binary_search_by(|file| file.path.cmp(path)).ok().map(|i| &files[i])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &'static EmbeddedFile> + use<> {
+        self.files.iter()
+    }
+
+    /// The file at `path`, or the precompressed variant `path` names as a sibling, e.g.
+    /// `app.js.br`, so [`StaticFileService`] negotiates them like siblings on disk.
+    fn variant(&self, path: &str) -> Option<(&'static EmbeddedFile, Option<Encoding>, &'static [u8])> {
+        if let Some(file) = self.get(path) {
+            return Some((file, None, file.bytes));
+        }
+        let (base, ext) = path.rsplit_once('.')?;
+        let file = self.get(base)?;
+        file.encoded
+            .iter()
+            .find(|(encoding, _)| encoding.extension() == ext)
+            .map(|(encoding, bytes)| (file, Some(*encoding), *bytes))
+    }
+}
+
+fn not_found() -> std::io::Error {
+    std::io::Error::new(ErrorKind::NotFound, "no such embedded file")
+}
+
+/// Serves the assets for [`EmbeddedFileService`]; they can also be mounted into a
+/// [`StaticFileService`] directly, e.g. as defaults under a directory on disk.
+impl VirtualFs for EmbeddedAssets {
+    fn metadata<'a>(&'a self, path: &'a str) -> VfsFuture<'a, VfsMetadata> {
+        Box::pin(async move {
+            if let Some((file, _, bytes)) = self.variant(path) {
+                return Ok(file.metadata(bytes.len()));
+            }
+            vfs::implied_entries(self.files.iter().map(|file| file.path), path)
+                .map(|_| VfsMetadata::dir())
+                .ok_or_else(not_found)
+        })
+    }
+
+    fn open<'a>(&'a self, path: &'a str, offset: u64) -> VfsFuture<'a, VfsReader> {
+        Box::pin(async move {
+            let (_, _, bytes) = self.variant(path).ok_or_else(not_found)?;
+            let start = (offset as usize).min(bytes.len());
+            Ok(Box::new(Cursor::new(&bytes[start..])) as VfsReader)
+        })
+    }
+
+    fn read_dir<'a>(&'a self, path: &'a str) -> VfsFuture<'a, Vec<(String, VfsMetadata)>> {
+        Box::pin(async move {
+            let entries = vfs::implied_entries(self.files.iter().map(|file| file.path), path)
+                .ok_or_else(|| std::io::Error::new(ErrorKind::NotFound, "no such embedded directory"))?;
+            Ok(entries
+                .into_iter()
+                .map(|(name, is_dir)| {
+                    let full = if path.is_empty() { name.to_string() } else { format!("{}/{}", path, name) };
+                    let metadata = match self.get(&full) {
+                        Some(file) if !is_dir => file.metadata(file.bytes.len()),
+                        _ => VfsMetadata::dir(),
+                    };
+                    (name.to_string(), metadata)
+                })
+                .collect())
+        })
+    }
+
+    /// The tag hashed at compile time, marked with the encoding for precompressed variants.
+    fn etag(&self, path: &str) -> Option<String> {
+        let (file, encoding, _) = self.variant(path)?;
+        let etag = file.etag.to_string();
+        Some(match encoding {
+            Some(encoding) => compression::encoded_etag(etag, encoding),
+            None => etag,
+        })
+    }
+}
+
+/// Serves [`EmbeddedAssets`]: a [`StaticFileService`] with the assets mounted in place of the
+/// directory they were embedded from, or that directory itself with
+/// [`live_reload`](EmbeddedFileServiceBuilder::live_reload).
+#[derive(Clone)]
+pub struct EmbeddedFileService {
+    inner: StaticFileService,
+}
+
+impl EmbeddedFileService {
+    pub fn builder(assets: EmbeddedAssets) -> EmbeddedFileServiceBuilder {
+        EmbeddedFileServiceBuilder::new(assets)
+    }
+}
+
+impl Service<Request<Body>> for EmbeddedFileService {
+    type Response = Response<Body>;
+    type Error = Infallible;
+    type Future = <StaticFileService as Service<Request<Body>>>::Future;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        self.inner.call(req)
+    }
+}
+
+/// Configures an [`EmbeddedFileService`] like a [`StaticFileServiceBuilder`].
+pub struct EmbeddedFileServiceBuilder {
+    assets: EmbeddedAssets,
+    live: bool,
+    inner: StaticFileServiceBuilder,
+}
+
+impl EmbeddedFileServiceBuilder {
+    pub fn new(assets: EmbeddedAssets) -> Self {
+        Self {
+            assets,
+            live: assets.live,
+            inner: StaticFileServiceBuilder::new(assets.root)
+                .precompressed(&[Encoding::Brotli, Encoding::Zstd, Encoding::Gzip]),
+        }
+    }
+
+    /// Serves the embedded directory from disk instead of the binary, so edits show up without
+    /// recompiling. Defaults to `cfg!(debug_assertions)` of the crate calling `embed!`.
+    pub fn live_reload(mut self, enable: bool) -> Self {
+        self.live = enable;
+        self
+    }
+
+    /// Which embedded precompressed variants are negotiated, all of them by default.
+    pub fn precompressed(mut self, encodings: &[Encoding]) -> Self {
+        self.inner = self.inner.precompressed(encodings);
+        self
+    }
+
+    /// See [`StaticFileServiceBuilder::cache_control`].
+    pub fn cache_control(mut self, pattern: &str, value: &str) -> Self {
+        self.inner = self.inner.cache_control(pattern, value);
+        self
+    }
+
+    /// See [`StaticFileServiceBuilder::dotfiles`].
+    pub fn dotfiles(mut self, policy: DotFiles) -> Self {
+        self.inner = self.inner.dotfiles(policy);
+        self
+    }
+
+    /// See [`StaticFileServiceBuilder::allow_extensions`].
+    pub fn allow_extensions(mut self, extensions: &[&str]) -> Self {
+        self.inner = self.inner.allow_extensions(extensions);
+        self
+    }
+
+    /// See [`StaticFileServiceBuilder::deny_extensions`].
+    pub fn deny_extensions(mut self, extensions: &[&str]) -> Self {
+        self.inner = self.inner.deny_extensions(extensions);
+        self
+    }
+
+    /// See [`StaticFileServiceBuilder::nosniff`].
+    pub fn nosniff(mut self, enable: bool) -> Self {
+        self.inner = self.inner.nosniff(enable);
+        self
+    }
+
+    /// See [`StaticFileServiceBuilder::attachment`].
+    pub fn attachment(mut self, pattern: &str) -> Self {
+        self.inner = self.inner.attachment(pattern);
+        self
+    }
+
+    pub fn with_spa_fallback(mut self, enable: bool) -> Self {
+        self.inner = self.inner.with_spa_fallback(enable);
+        self
+    }
+
+    /// See [`StaticFileServiceBuilder::spa_fallback`]; the fallback file is looked up in the assets.
+    pub fn spa_fallback(mut self, fallback: SpaFallback) -> Self {
+        self.inner = self.inner.spa_fallback(fallback);
+        self
+    }
+
+    /// See [`StaticFileServiceBuilder::error_page`]; the page is looked up in the assets.
+    pub fn error_page(mut self, status: StatusCode, file: &str) -> Self {
+        self.inner = self.inner.error_page(status, file);
+        self
+    }
+
+    pub fn with_cors(mut self, cors_layer: CorsLayer) -> Self {
+        self.inner = self.inner.with_cors(cors_layer);
+        self
+    }
+
+    pub fn cors_any(self) -> Self {
+        self.with_cors(fast_builder::cors_any())
+    }
+
+    pub fn build(self) -> EmbeddedFileService {
+        let inner = if self.live { self.inner } else { self.inner.replace_root(self.assets) };
+        EmbeddedFileService { inner: inner.build() }
+    }
+
+    pub fn build_router(mut self, path: &str) -> Router {
+        let cors_layer = self.inner.cors_layer.take();
+        let service = self.build();
+        if let Some(cors_layer) = cors_layer {
+            Router::new()
+                .nest_service_(path, service)
+                .layer(cors_layer)
+        } else {
+            Router::new()
+                .nest_service_(path, service)
+        }
+    }
+}
@@ -0,0 +1,98 @@
+//! SPA fallback rules and custom error pages for static files.
+
+use std::io::ErrorKind;
+
+use axum::http::{header, request::Parts, Method, StatusCode};
+
+/// Serves a fallback file for misses below a path prefix, see
+/// [`StaticFileServiceBuilder::spa_fallback`](super::static_layer::StaticFileServiceBuilder::spa_fallback).
+///
+/// By default only navigations fall back: `GET`/`HEAD` requests accepting `text/html` or whose
+/// last segment has no extension. `/assets/missing.js` stays a `404`.
+#[derive(Debug, Clone)]
+pub struct SpaFallback {
+    prefix: String,
+    file: String,
+    status: StatusCode,
+    html_only: bool,
+}
+
+impl SpaFallback {
+    /// Falls back to `file`, relative to the root, for every prefix.
+    pub fn new(file: &str) -> Self {
+        Self {
+            prefix: "/".to_string(),
+            file: file.trim_start_matches('/').to_string(),
+            status: StatusCode::OK,
+            html_only: true,
+        }
+    }
+
+    /// Only applies to request paths below `prefix`, e.g. `"/admin"`; the longest matching
+    /// prefix wins when several fallbacks are configured.
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.prefix = format!("/{}", prefix.trim_matches('/'));
+        self
+    }
+
+    /// The status the fallback file is served with, `200 OK` by default.
+    pub fn status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Whether only navigations fall back, enabled by default.
+    pub fn html_only(mut self, enable: bool) -> Self {
+        self.html_only = enable;
+        self
+    }
+
+    pub(crate) fn file(&self) -> &str {
+        &self.file
+    }
+
+    pub(crate) fn status_code(&self) -> StatusCode {
+        self.status
+    }
+
+    fn matches_prefix(&self, path: &str) -> bool {
+        self.prefix == "/"
+            || path
+                .strip_prefix(&self.prefix)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    }
+}
+
+/// Whether `req` looks like a page navigation rather than an asset request.
+fn is_navigation(req: &Parts) -> bool {
+    let accepts_html = req
+        .headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/html"));
+    let last = req.uri.path().rsplit('/').next().unwrap_or_default();
+    accepts_html || !last.contains('.')
+}
+
+/// The fallback with the longest prefix matching `req`, if it applies.
+pub(crate) fn find<'a>(fallbacks: &'a [SpaFallback], req: &Parts) -> Option<&'a SpaFallback> {
+    if req.method != Method::GET && req.method != Method::HEAD {
+        return None;
+    }
+    fallbacks
+        .iter()
+        .filter(|fallback| fallback.matches_prefix(req.uri.path()))
+        .max_by_key(|fallback| fallback.prefix.len())
+        .filter(|fallback| !fallback.html_only || is_navigation(req))
+}
+
+/// The status answering a failed lookup.
+pub(crate) fn error_status(error: &std::io::Error) -> StatusCode {
+    match error.kind() {
+        ErrorKind::PermissionDenied => StatusCode::FORBIDDEN,
+        ErrorKind::NotFound | ErrorKind::NotADirectory | ErrorKind::IsADirectory | ErrorKind::InvalidFilename => {
+            StatusCode::NOT_FOUND
+        }
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
@@ -0,0 +1,60 @@
+//! Glob patterns over paths relative to the static root.
+//!
+//! `*` matches within a segment, `**` across segments, `?` a single character. Patterns without
+//! a `/` match the file name at any depth, like `.gitignore` entries: `*.html` matches `docs/a.html`.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glob {
+    pattern: String,
+    anchored: bool,
+}
+
+impl Glob {
+    pub fn new(pattern: &str) -> Self {
+        let pattern = pattern.trim_start_matches("./");
+        Self {
+            anchored: pattern.contains('/'),
+            pattern: pattern.trim_start_matches('/').to_string(),
+        }
+    }
+
+    /// Matches `path`, relative to the root and separated by `/`.
+    pub fn matches(&self, path: &str) -> bool {
+        let path = path.trim_start_matches('/');
+        let target = if self.anchored { path } else { path.rsplit('/').next().unwrap_or(path) };
+        matches(self.pattern.as_bytes(), target.as_bytes())
+    }
+}
+
+impl From<&str> for Glob {
+    fn from(pattern: &str) -> Self {
+        Glob::new(pattern)
+    }
+}
+
+fn matches(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        [b'*', b'*', rest @ ..] => {
+            if rest.is_empty() {
+                return true;
+            }
+            // `**/` also matches no directory at all
+            let rest = rest.strip_prefix(b"/").unwrap_or(rest);
+            (0..=text.len()).any(|i| (i == 0 || text[i - 1] == b'/') && matches(rest, &text[i..]))
+                || matches(rest, text)
+        }
+        [b'*', rest @ ..] => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != b'/')
+            .any(|i| matches(rest, &text[i..])),
+        [b'?', rest @ ..] => match text {
+            [c, tail @ ..] if *c != b'/' => {
+                // skip the remaining bytes of a multi-byte character
+                let skip = tail.iter().take_while(|b| (**b & 0xC0) == 0x80).count();
+                matches(rest, &tail[skip..])
+            }
+            _ => false,
+        },
+        [p, rest @ ..] => matches!(text, [c, tail @ ..] if c == p && matches(rest, tail)),
+    }
+}
@@ -0,0 +1,235 @@
+//! Directory listings for [`StaticFileService`](super::static_layer::StaticFileService),
+//! rendered as HTML or JSON.
+
+use std::{cmp::Ordering, time::UNIX_EPOCH};
+
+use axum::{
+    body::Body,
+    http::{header, request::Parts, Response},
+    response::IntoResponse,
+    Json,
+};
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use serde::Serialize;
+
+use super::vfs::VfsMetadata;
+
+/// Escapes file names used as relative links.
+pub(super) const HREF_ENCODE_SET: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}')
+    .add(b'/')
+    .add(b'\\');
+
+/// The format of a directory listing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ListingFormat {
+    Html,
+    Json,
+    /// JSON when the `Accept` header prefers `application/json`, HTML otherwise.
+    #[default]
+    Auto,
+}
+
+/// How listing entries are ordered; directories always come first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ListingSort {
+    #[default]
+    Name,
+    Size,
+    Modified,
+}
+
+impl ListingSort {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "name" => Some(ListingSort::Name),
+            "size" => Some(ListingSort::Size),
+            "modified" | "mtime" => Some(ListingSort::Modified),
+            _ => None,
+        }
+    }
+}
+
+/// Directory listing settings, see
+/// [`StaticFileServiceBuilder::directory_listing`](super::static_layer::StaticFileServiceBuilder::directory_listing).
+///
+/// The order can be overridden per request with `?sort=name|size|modified&order=asc|desc`.
+#[derive(Debug, Clone, Default)]
+pub struct DirectoryListing {
+    format: ListingFormat,
+    sort: ListingSort,
+    descending: bool,
+    show_hidden: bool,
+}
+
+impl DirectoryListing {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn format(mut self, format: ListingFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn sort(mut self, sort: ListingSort, descending: bool) -> Self {
+        self.sort = sort;
+        self.descending = descending;
+        self
+    }
+
+    /// Lists files and directories whose name starts with `.`, hidden by default.
+    pub fn show_hidden(mut self, show: bool) -> Self {
+        self.show_hidden = show;
+        self
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Entry {
+    name: String,
+    is_dir: bool,
+    /// `None` for directories.
+    size: Option<u64>,
+    /// Seconds since the Unix epoch.
+    modified: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct Listing<'a> {
+    path: &'a str,
+    entries: Vec<Entry>,
+}
+
+fn to_entries(entries: Vec<(String, VfsMetadata)>, show_hidden: bool) -> Vec<Entry> {
+    entries
+        .into_iter()
+        .filter(|(name, _)| show_hidden || !name.starts_with('.'))
+        .map(|(name, metadata)| Entry {
+            name,
+            is_dir: metadata.is_dir,
+            size: (!metadata.is_dir).then_some(metadata.len),
+            modified: metadata
+                .modified
+                .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs()),
+        })
+        .collect()
+}
+
+fn sort_entries(entries: &mut [Entry], sort: ListingSort, descending: bool) {
+    entries.sort_by(|a, b| {
+        let order = match sort {
+            ListingSort::Name => Ordering::Equal,
+            ListingSort::Size => a.size.cmp(&b.size),
+            ListingSort::Modified => a.modified.cmp(&b.modified),
+        }
+        .then_with(|| a.name.cmp(&b.name));
+        let order = if descending { order.reverse() } else { order };
+        b.is_dir.cmp(&a.is_dir).then(order)
+    });
+}
+
+fn query_param<'a>(req: &'a Parts, name: &str) -> Option<&'a str> {
+    req.uri
+        .query()?
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find_map(|(key, value)| (key == name).then_some(value))
+}
+
+fn wants_json(req: &Parts) -> bool {
+    let accept = req.headers.get(header::ACCEPT).and_then(|v| v.to_str().ok()).unwrap_or_default();
+    let json = accept.find("application/json");
+    json.is_some_and(|json| accept.find("text/html").is_none_or(|html| json < html))
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn render_html(listing: &Listing) -> String {
+    let title = escape_html(listing.path);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Index of {title}</title></head>\n<body>\n\
+         <h1>Index of {title}</h1>\n<table>\n<tr><th>Name</th><th>Size</th><th>Last modified</th></tr>\n"
+    );
+    if listing.path != "/" {
+        html.push_str("<tr><td><a href=\"../\">../</a></td><td></td><td></td></tr>\n");
+    }
+    for entry in &listing.entries {
+        let suffix = if entry.is_dir { "/" } else { "" };
+        let href = utf8_percent_encode(&entry.name, HREF_ENCODE_SET).to_string();
+        let size = entry.size.map(|s| s.to_string()).unwrap_or_else(|| "-".to_string());
+        let modified = entry
+            .modified
+            .map(|secs| httpdate::fmt_http_date(UNIX_EPOCH + std::time::Duration::from_secs(secs)))
+            .unwrap_or_default();
+        html.push_str(&format!(
+            "<tr><td><a href=\"./{}{suffix}\">{}{suffix}</a></td><td>{}</td><td>{}</td></tr>\n",
+            escape_html(&href),
+            escape_html(&entry.name),
+            size,
+            modified,
+        ));
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+    html
+}
+
+/// Lists the `entries` of a directory requested as `uri_path`.
+pub(crate) fn render(
+    entries: Vec<(String, VfsMetadata)>,
+    uri_path: &str,
+    req: &Parts,
+    settings: &DirectoryListing,
+) -> Response<Body> {
+    let mut entries = to_entries(entries, settings.show_hidden);
+    let sort = query_param(req, "sort").and_then(ListingSort::parse).unwrap_or(settings.sort);
+    let descending = match query_param(req, "order") {
+        Some("asc") => false,
+        Some("desc") => true,
+        _ => settings.descending,
+    };
+    sort_entries(&mut entries, sort, descending);
+    let listing = Listing { path: uri_path, entries };
+
+    let json = match settings.format {
+        ListingFormat::Html => false,
+        ListingFormat::Json => true,
+        ListingFormat::Auto => wants_json(req),
+    };
+    let mut response = if json {
+        Json(listing).into_response()
+    } else {
+        (
+            [(header::CONTENT_TYPE, "text/html; charset=utf-8")],
+            render_html(&listing),
+        )
+            .into_response()
+    };
+    if settings.format == ListingFormat::Auto {
+        response.headers_mut().insert(header::VARY, header::HeaderValue::from_static("accept"));
+    }
+    response
+}
@@ -1,4 +1,22 @@
 #![cfg(feature = "layers")]
 pub mod static_layer;
+pub mod embed;
+pub mod vfs;
+mod range;
+mod glob;
+mod conditional;
+pub use conditional::ETagStrategy;
+mod compression;
+mod listing;
+mod security;
+pub use security::{DotFiles, SymlinkPolicy};
+mod fallback;
+pub use fallback::SpaFallback;
+pub use listing::{DirectoryListing, ListingFormat, ListingSort};
+pub use compression::Encoding;
+mod cache;
+pub use cache::{CacheStats, Revalidate, StaticCache};
+mod upload;
+pub use upload::{Overwrite, StoredFile, UploadPolicy};
 mod ext;
 pub use ext::*;
\ No newline at end of file
@@ -0,0 +1,155 @@
+//! `Range` requests for static files: single ranges answer with `206 Partial Content`,
+//! several ranges with a `multipart/byteranges` body.
+
+use std::{
+    io::Cursor,
+    ops::RangeInclusive,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use axum::body::Body;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio_util::io::ReaderStream;
+
+use super::vfs::VirtualFs;
+
+/// More ranges than this are answered with the whole file.
+const MAX_RANGES: usize = 32;
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum RangeRequest {
+    /// No usable `Range` header: serve the whole file.
+    Full,
+    /// Satisfiable ranges, sorted and with overlapping ones merged.
+    Partial(Vec<RangeInclusive<u64>>),
+    /// None of the ranges overlaps the file.
+    Unsatisfiable,
+}
+
+/// Parses a `Range` header against a file of `len` bytes. Malformed headers and units other
+/// than `bytes` are ignored, as RFC 9110 allows.
+pub(crate) fn parse_range(header: &str, len: u64) -> RangeRequest {
+    let Some((unit, specs)) = header.split_once('=') else {
+        return RangeRequest::Full;
+    };
+    if !unit.trim().eq_ignore_ascii_case("bytes") {
+        return RangeRequest::Full;
+    }
+    let mut ranges = Vec::new();
+    for spec in specs.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let Some((start, end)) = spec.split_once('-') else {
+            return RangeRequest::Full;
+        };
+        let range = match (start.trim(), end.trim()) {
+            ("", "") => return RangeRequest::Full,
+            ("", suffix) => match suffix.parse::<u64>() {
+                Ok(0) => None,
+                Ok(n) if len > 0 => Some(len.saturating_sub(n)..=len - 1),
+                Ok(_) => None,
+                Err(_) => return RangeRequest::Full,
+            },
+            (start, end) => {
+                let Ok(start) = start.parse::<u64>() else {
+                    return RangeRequest::Full;
+                };
+                let end = match end {
+                    "" => u64::MAX,
+                    end => match end.parse::<u64>() {
+                        Ok(end) if end >= start => end,
+                        _ => return RangeRequest::Full,
+                    },
+                };
+                (start < len).then(|| start..=end.min(len - 1))
+            }
+        };
+        ranges.extend(range);
+    }
+    if ranges.is_empty() {
+        return RangeRequest::Unsatisfiable;
+    }
+    if ranges.len() > MAX_RANGES {
+        return RangeRequest::Full;
+    }
+    ranges.sort_by_key(|r| *r.start());
+    let mut merged: Vec<RangeInclusive<u64>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if *range.start() <= last.end().saturating_add(1) => {
+                *last = *last.start()..=(*last.end()).max(*range.end());
+            }
+            _ => merged.push(range),
+        }
+    }
+    RangeRequest::Partial(merged)
+}
+
+/// Whether an `If-Range` validator still matches the file, so the `Range` header applies.
+/// Entity tags are compared strongly; dates must equal `Last-Modified`.
+pub(crate) fn if_range_matches(if_range: &str, last_modified: Option<SystemTime>, etag: Option<&str>) -> bool {
+    let if_range = if_range.trim();
+    if if_range.starts_with('"') || if_range.starts_with("W/") {
+        return etag.is_some_and(|etag| !etag.starts_with("W/") && etag == if_range);
+    }
+    match (httpdate::parse_http_date(if_range), last_modified) {
+        (Ok(date), Some(modified)) => httpdate::fmt_http_date(modified) == httpdate::fmt_http_date(date),
+        _ => false,
+    }
+}
+
+pub(crate) fn content_range(range: &RangeInclusive<u64>, len: u64) -> String {
+    format!("bytes {}-{}/{}", range.start(), range.end(), len)
+}
+
+fn range_len(range: &RangeInclusive<u64>) -> u64 {
+    range.end() - range.start() + 1
+}
+
+async fn open_range(
+    fs: &dyn VirtualFs,
+    path: &str,
+    range: &RangeInclusive<u64>,
+) -> std::io::Result<impl AsyncRead + Send + Unpin + use<>> {
+    Ok(fs.open(path, *range.start()).await?.take(range_len(range)))
+}
+
+/// The body of a single range.
+pub(crate) async fn range_body(fs: &dyn VirtualFs, path: &str, range: &RangeInclusive<u64>) -> std::io::Result<(u64, Body)> {
+    let reader = open_range(fs, path, range).await?;
+    Ok((range_len(range), Body::from_stream(ReaderStream::new(reader))))
+}
+
+fn boundary() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or_default();
+    format!("exum-{:016x}{:04x}", nanos, COUNTER.fetch_add(1, Ordering::Relaxed) & 0xffff)
+}
+
+/// A `multipart/byteranges` body, returned with its boundary and exact length.
+pub(crate) async fn multipart_body(
+    fs: &dyn VirtualFs,
+    path: &str,
+    ranges: &[RangeInclusive<u64>],
+    content_type: &str,
+    len: u64,
+) -> std::io::Result<(String, u64, Body)> {
+    let boundary = boundary();
+    let mut total = 0;
+    let mut reader: Box<dyn AsyncRead + Send + Unpin> = Box::new(Cursor::new(Vec::new()));
+    for (i, range) in ranges.iter().enumerate() {
+        let head = format!(
+            "{}--{}\r\nContent-Type: {}\r\nContent-Range: {}\r\n\r\n",
+            if i == 0 { "" } else { "\r\n" },
+            boundary,
+            content_type,
+            content_range(range, len),
+        );
+        total += head.len() as u64 + range_len(range);
+        let part = open_range(fs, path, range).await?;
+        reader = Box::new(reader.chain(Cursor::new(head.into_bytes())).chain(part));
+    }
+    let tail = format!("\r\n--{}--\r\n", boundary);
+    total += tail.len() as u64;
+    reader = Box::new(reader.chain(Cursor::new(tail.into_bytes())));
+    Ok((boundary, total, Body::from_stream(ReaderStream::new(reader))))
+}
@@ -0,0 +1,52 @@
+//! Access rules for static files: dotfiles, symlinks and file extensions.
+
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+
+/// How files and directories whose name starts with `.` (`.env`, `.git/config`) are handled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DotFiles {
+    /// Answer `403 Forbidden`.
+    Deny,
+    /// Answer as if the file did not exist, so SPA fallback still applies.
+    #[default]
+    Ignore,
+    /// Serve them like any other file.
+    Allow,
+}
+
+/// Which symlinks below the root are followed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// Follow symlinks whose target stays inside the canonical root.
+    #[default]
+    WithinRoot,
+    /// Never serve a path going through a symlink.
+    Never,
+    /// Follow every symlink, even out of the root.
+    Always,
+}
+
+pub(crate) fn is_dotfile(rel: &str) -> bool {
+    rel.split('/').any(|segment| segment.starts_with('.'))
+}
+
+pub(crate) fn extension(rel: &str) -> Option<String> {
+    let name = rel.rsplit('/').next().unwrap_or(rel);
+    name.rsplit_once('.')
+        .filter(|(stem, _)| !stem.is_empty())
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+}
+
+/// `attachment` with the file name, ASCII-only in `filename` and exact in `filename*`.
+pub(crate) fn attachment(rel: &str) -> String {
+    let name = rel.rsplit('/').next().unwrap_or(rel);
+    let ascii: String = name
+        .chars()
+        .map(|c| if (c.is_ascii_graphic() && c != '"' && c != '\\') || c == ' ' { c } else { '_' })
+        .collect();
+    format!(
+        "attachment; filename=\"{}\"; filename*=UTF-8''{}",
+        ascii,
+        utf8_percent_encode(name, NON_ALPHANUMERIC)
+    )
+}
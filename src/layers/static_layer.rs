@@ -1,42 +1,230 @@
 #![cfg(feature = "layers")]
 use std::{path::{Component, Path, PathBuf}, pin::Pin, sync::Arc, task::{Context, Poll}};
-use tokio::{fs::File};
+use tokio::io::AsyncReadExt;
 use mime_guess;
-use axum::{body::Body, http::{Method, Request, Response, StatusCode}, Router};
+use axum::{body::Body, extract::OriginalUri, http::{header, request::Parts, HeaderValue, Method, Request, Response, StatusCode}, response::{IntoResponse, Redirect}, Router};
 use percent_encoding::percent_decode_str;
 use tokio_util::io::ReaderStream;
 use tower::Service;
 use tower_http::cors::CorsLayer;
 
 use crate::{fast_builder, UrlEncodedMethodExt};
+use super::{
+    cache::{CachedFs, StaticCache},
+    compression::{self, Encoding},
+    conditional::{self, ETagStrategy, HashCache},
+    fallback::{self, SpaFallback},
+    glob::Glob,
+    listing::{self, DirectoryListing},
+    range::{self, RangeRequest},
+    security::{self, DotFiles, SymlinkPolicy},
+    upload::{self, UploadPolicy},
+    vfs::{DiskFs, VfsMetadata, VirtualFs},
+};
+
+/// Settings shared by the clones of a [`StaticFileService`].
+#[derive(Debug)]
+pub(super) struct Settings {
+    pub(super) etag: ETagStrategy,
+    /// `Cache-Control` values, the first matching pattern wins.
+    cache_control: Vec<(Glob, HeaderValue)>,
+    pub(super) hashes: HashCache,
+    /// Precompressed siblings looked up next to each file, in order of preference.
+    precompressed: Vec<Encoding>,
+    #[cfg(feature = "compression")]
+    dynamic: Option<compression::DynamicCompression>,
+    /// Files served for a directory, the first existing one wins.
+    index_files: Vec<String>,
+    /// Redirects `/docs` to `/docs/` when it is a directory.
+    redirect_trailing_slash: bool,
+    listing: Option<DirectoryListing>,
+    pub(super) dotfiles: DotFiles,
+    symlinks: SymlinkPolicy,
+    /// Lowercase extensions; `None` allows every extension not denied.
+    allow_extensions: Option<Vec<String>>,
+    deny_extensions: Vec<String>,
+    nosniff: bool,
+    /// Files sent with `Content-Disposition: attachment`.
+    attachments: Vec<Glob>,
+    fallbacks: Vec<SpaFallback>,
+    /// Pages served for error statuses, relative to the root.
+    error_pages: Vec<(StatusCode, String)>,
+    pub(super) cache: Option<StaticCache>,
+    uploads: Option<UploadPolicy>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            etag: ETagStrategy::default(),
+            cache_control: Vec::new(),
+            hashes: HashCache::default(),
+            precompressed: Vec::new(),
+            #[cfg(feature = "compression")]
+            dynamic: None,
+            index_files: vec!["index.html".to_string()],
+            redirect_trailing_slash: true,
+            listing: None,
+            dotfiles: DotFiles::default(),
+            symlinks: SymlinkPolicy::default(),
+            allow_extensions: None,
+            deny_extensions: Vec::new(),
+            nosniff: true,
+            attachments: Vec::new(),
+            fallbacks: Vec::new(),
+            error_pages: Vec::new(),
+            cache: None,
+            uploads: None,
+        }
+    }
+}
+
+impl Settings {
+    fn cache_control(&self, rel: &str) -> Option<&HeaderValue> {
+        self.cache_control.iter().find(|(glob, _)| glob.matches(rel)).map(|(_, value)| value)
+    }
+
+    /// The `Content-Type` of `rel`, memoized per extension when a cache is configured.
+    fn content_type(&self, rel: &str, mime: &mime_guess::Mime) -> Arc<str> {
+        match &self.cache {
+            Some(cache) => cache.content_type(rel, || content_type(mime)),
+            None => content_type(mime).into(),
+        }
+    }
+
+    /// Whether responses depend on `Accept-Encoding`.
+    fn negotiates(&self) -> bool {
+        #[cfg(feature = "compression")]
+        if self.dynamic.is_some() {
+            return true;
+        }
+        !self.precompressed.is_empty()
+    }
+
+    /// The SPA fallback applying to a missed request, if any.
+    fn fallback(&self, req: &Parts) -> Option<&SpaFallback> {
+        fallback::find(&self.fallbacks, req)
+    }
+
+    fn error_page(&self, status: StatusCode) -> Option<&str> {
+        self.error_pages.iter().find(|(s, _)| *s == status).map(|(_, page)| page.as_str())
+    }
+
+    /// Applies the dotfile policy to `rel`; `PermissionDenied` is answered with `403`.
+    fn check_dotfiles(&self, rel: &str) -> std::io::Result<()> {
+        if !security::is_dotfile(rel) {
+            return Ok(());
+        }
+        match self.dotfiles {
+            DotFiles::Allow => Ok(()),
+            DotFiles::Deny => Err(std::io::Error::new(std::io::ErrorKind::PermissionDenied, "dotfile")),
+            DotFiles::Ignore => Err(std::io::Error::new(std::io::ErrorKind::NotFound, "dotfile")),
+        }
+    }
+
+    /// Hides files whose extension is denied or not allowed.
+    pub(super) fn check_extension(&self, rel: &str) -> std::io::Result<()> {
+        let ext = security::extension(rel);
+        let denied = ext.as_ref().is_some_and(|ext| self.deny_extensions.contains(ext));
+        let allowed = self
+            .allow_extensions
+            .as_ref()
+            .is_none_or(|allowed| ext.as_ref().is_some_and(|ext| allowed.contains(ext)));
+        if denied || !allowed {
+            return Err(std::io::Error::new(std::io::ErrorKind::NotFound, "extension not served"));
+        }
+        Ok(())
+    }
+
+    /// Headers every file response gets: `nosniff` and `Content-Disposition`.
+    fn file_headers(&self, rel: &str, mut builder: axum::http::response::Builder) -> axum::http::response::Builder {
+        if self.nosniff {
+            builder = builder.header(header::X_CONTENT_TYPE_OPTIONS, "nosniff");
+        }
+        if self.attachments.iter().any(|glob| glob.matches(rel)) {
+            builder = builder.header(header::CONTENT_DISPOSITION, security::attachment(rel));
+        }
+        builder
+    }
+
+    /// Redirects a directory requested without its trailing slash, when enabled.
+    fn trailing_slash_redirect(&self, req: &Parts) -> Option<Response<Body>> {
+        let path = original_path(req);
+        if !self.redirect_trailing_slash || path.ends_with('/') {
+            return None;
+        }
+        let location = match req.uri.query() {
+            Some(query) => format!("{}/?{}", path, query),
+            None => format!("{}/", path),
+        };
+        Some(Redirect::permanent(&location).into_response())
+    }
+
+    /// The encoding to compress a file of `len` bytes with on the fly, if any.
+    #[cfg(feature = "compression")]
+    fn dynamic_encoding(&self, accept_encoding: Option<&str>, len: u64, mime: &mime_guess::Mime) -> Option<Encoding> {
+        let dynamic = self.dynamic.as_ref()?;
+        if len < dynamic.min_size || !compression::is_compressible(mime) {
+            return None;
+        }
+        compression::negotiate(accept_encoding, &dynamic.encodings).into_iter().next()
+    }
+
+    #[cfg(not(feature = "compression"))]
+    fn dynamic_encoding(&self, _: Option<&str>, _: u64, _: &mime_guess::Mime) -> Option<Encoding> {
+        None
+    }
+}
+
+/// A file source mounted below a path prefix.
+pub(super) struct Mount {
+    /// Without leading or trailing `/`, `""` for the whole service.
+    pub(super) prefix: String,
+    pub(super) fs: Arc<dyn VirtualFs>,
+    /// The directory behind `fs` for disk mounts, where uploads are written.
+    pub(super) disk: Option<Arc<DiskFs>>,
+}
+
+impl Mount {
+    /// The path of `rel` inside this mount, if it lies below the prefix.
+    pub(super) fn strip<'a>(&self, rel: &'a str) -> Option<&'a str> {
+        if self.prefix.is_empty() {
+            return Some(rel);
+        }
+        match rel.strip_prefix(self.prefix.as_str())? {
+            "" => Some(""),
+            rest => rest.strip_prefix('/'),
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct StaticFileService {
   pub root: Arc<PathBuf>,
-  spa_fallback: bool,
+  mounts: Arc<[Mount]>,
+  settings: Arc<Settings>,
 }
 impl StaticFileService {
   pub fn new<P: Into<PathBuf>>(root: P, spa_fallback: bool) -> Self {
-    let p = root.into();
-    let abs = p.canonicalize().unwrap_or(p);
-    Self { root: Arc::new(abs), spa_fallback }
+    StaticFileServiceBuilder::new(root).with_spa_fallback(spa_fallback).build()
   }
 
   pub fn builder(root: impl Into<PathBuf>) -> StaticFileServiceBuilder {
       StaticFileServiceBuilder::new(root)
   }
+}
 
-  fn resolve_path(&self, uri_path: &str) -> PathBuf {
-    let mut path = self.root.as_ref().clone();
-    let decoded = percent_decode_str(uri_path)
-            .decode_utf8_lossy();
-    let rel = decoded.trim_start_matches('/');
-    let safe_rel = Path::new(rel).components()
-        .filter(|c| matches!(c, Component::Normal(_)))
-        .collect::<PathBuf>();
-    path.push(safe_rel);
-    path
-  }
+/// The path a request URI points to, relative to the mounts and without `.` and `..` segments.
+fn resolve_path(uri_path: &str) -> String {
+    let decoded = percent_decode_str(uri_path).decode_utf8_lossy();
+    Path::new(decoded.trim_start_matches('/'))
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(name) => Some(name.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
 }
 
 impl Service<Request<Body>> for StaticFileService {
@@ -51,71 +239,475 @@ impl Service<Request<Body>> for StaticFileService {
   fn call(&mut self, req: Request<Body>) -> Self::Future {
 
     // clone to avoid lifetime issue
-    let root = self.root.clone();
-    let spa_fallback = self.spa_fallback;
+    let mounts = self.mounts.clone();
+    let settings = self.settings.clone();
 
-      let path = self.resolve_path(req.uri().path());
+      let path = resolve_path(req.uri().path());
+      if let Some(policy) = &settings.uploads && upload::is_upload(req.method()) {
+        let policy = policy.clone();
+        return Box::pin(async move { Ok(upload::handle(req, path, &mounts, &settings, &policy).await) });
+      }
+      let (req, _) = req.into_parts();
       Box::pin(async move {
-        match serve_file(&path, &req.method()).await {
-          Ok(resp) => Ok(resp),
-          Err(_) if spa_fallback => {
-            let index_path = root.join("index.html");
-              Ok(serve_file(&index_path, &req.method())
-                  .await
-                  .unwrap_or_else(|e| {
-                    tracing::warn!("Failed to serve file {:?}: {}", path, e);
-                    fast_builder::reponse_not_found()
-                  }))
+        let error = match serve(&mounts, &path, &req, &settings).await {
+          Ok(resp) => return Ok(resp),
+          Err(e) => e,
+        };
+        let status = fallback::error_status(&error);
+        if status == StatusCode::NOT_FOUND && let Some(fallback) = settings.fallback(&req) {
+          match serve_overlay_file(&mounts, fallback.file(), &req, &settings).await {
+            Ok(mut resp) => {
+              if resp.status() == StatusCode::OK {
+                *resp.status_mut() = fallback.status_code();
+              }
+              return Ok(resp);
+            }
+            Err(e) => tracing::warn!("Failed to serve fallback {:?}: {}", fallback.file(), e),
           }
-          Err(e) => {
-            tracing::warn!("Failed to serve file {:?}: {}", path, e);
-            Ok(fast_builder::reponse_not_found())
-          },
         }
+        tracing::warn!("Failed to serve file {:?}: {}", path, error);
+        Ok(error_response(&mounts, &settings, status).await)
       })
   }
 }
 
-async fn serve_file(path: &PathBuf, method: &Method) -> Result<Response<Body>, std::io::Error> {
-    let mime = mime_guess::from_path(path).first_or_octet_stream();
-    let content_type = if mime.type_() == mime_guess::mime::TEXT {
+/// Runs `serve` on each mount containing `rel`, in order, until one has the file.
+async fn overlay<'a, F>(mounts: &'a [Mount], rel: &'a str, serve: F) -> std::io::Result<Response<Body>>
+where
+    F: Fn(&'a dyn VirtualFs, &'a str) -> Pin<Box<dyn Future<Output = std::io::Result<Response<Body>>> + Send + 'a>>,
+{
+    let mut error = std::io::Error::new(std::io::ErrorKind::NotFound, "no mount for path");
+    for mount in mounts {
+        let Some(path) = mount.strip(rel) else {
+            continue;
+        };
+        match serve(mount.fs.as_ref(), path).await {
+            Err(e) if fallback::error_status(&e) == StatusCode::NOT_FOUND => error = e,
+            result => return result,
+        }
+    }
+    Err(error)
+}
+
+async fn serve_overlay_file(mounts: &[Mount], rel: &str, req: &Parts, settings: &Settings) -> std::io::Result<Response<Body>> {
+    overlay(mounts, rel, |fs, path| Box::pin(serve_file(fs, path, rel, req, settings))).await
+}
+
+/// `status` with the configured error page, or a plain text body.
+async fn error_response(mounts: &[Mount], settings: &Settings, status: StatusCode) -> Response<Body> {
+    if let Some(page) = settings.error_page(status) {
+        let body = overlay(mounts, page, |fs, path| {
+            Box::pin(async move {
+                let mut bytes = Vec::new();
+                fs.open(path, 0).await?.read_to_end(&mut bytes).await?;
+                Ok(Response::new(Body::from(bytes)))
+            })
+        });
+        match body.await {
+            Ok(mut resp) => {
+                *resp.status_mut() = status;
+                let content_type = content_type(&mime_guess::from_path(page).first_or_octet_stream());
+                resp.headers_mut().insert(header::CONTENT_TYPE, HeaderValue::from_str(&content_type).unwrap());
+                if settings.nosniff {
+                    resp.headers_mut().insert(header::X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+                }
+                return resp;
+            }
+            Err(e) => tracing::warn!("Failed to read error page {:?}: {}", page, e),
+        }
+    }
+    fast_builder::response_for_status(status)
+}
+
+/// The `Content-Type` of `mime`, with `charset=utf-8` for text.
+pub(super) fn content_type(mime: &mime_guess::Mime) -> String {
+    if mime.type_() == mime_guess::mime::TEXT {
         format!("{}; charset=utf-8", mime)
     } else {
         mime.to_string()
-    };
-    let metadata = tokio::fs::metadata(path).await?;
+    }
+}
+
+fn join(dir: &str, name: &str) -> String {
+    if dir.is_empty() { name.to_string() } else { format!("{}/{}", dir, name) }
+}
+
+/// The path before `nest_service` stripped the prefix, relative links resolve against it.
+pub(super) fn original_path(req: &Parts) -> &str {
+    req.extensions.get::<OriginalUri>().map_or(req.uri.path(), |uri| uri.path())
+}
+
+/// Serves `rel` from the first mount having it; directories without an index file in any
+/// mount are listed with the entries of all of them.
+async fn serve(mounts: &[Mount], rel: &str, req: &Parts, settings: &Settings) -> std::io::Result<Response<Body>> {
+    settings.check_dotfiles(rel)?;
+    let result = overlay(mounts, rel, |fs, path| Box::pin(serve_entry(fs, path, rel, req, settings))).await;
+    match (&settings.listing, result) {
+        (Some(listing), Err(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            let entries = list_dir(mounts, rel).await?;
+            Ok(listing::render(entries, original_path(req), req, listing))
+        }
+        (_, result) => result,
+    }
+}
+
+/// Serves a file, or the index file of a directory. `rel` is the path relative to the
+/// service, which patterns and rules match against.
+async fn serve_entry(fs: &dyn VirtualFs, path: &str, rel: &str, req: &Parts, settings: &Settings) -> std::io::Result<Response<Body>> {
+    if !fs.metadata(path).await?.is_dir {
+        return serve_file(fs, path, rel, req, settings).await;
+    }
+    if let Some(redirect) = settings.trailing_slash_redirect(req) {
+        return Ok(redirect);
+    }
+    for index in &settings.index_files {
+        let index_path = join(path, index);
+        if fs.metadata(&index_path).await.is_ok_and(|m| !m.is_dir) {
+            return serve_file(fs, &index_path, &join(rel, index), req, settings).await;
+        }
+    }
+    Err(std::io::Error::new(std::io::ErrorKind::NotFound, "directory without index file"))
+}
+
+/// The entries of the directory `rel` in every mount, earlier mounts shadowing later ones.
+async fn list_dir(mounts: &[Mount], rel: &str) -> std::io::Result<Vec<(String, VfsMetadata)>> {
+    let mut entries: Vec<(String, VfsMetadata)> = Vec::new();
+    let mut found = false;
+    for mount in mounts {
+        // a mount below `rel` shows up as a directory
+        let below = match rel {
+            "" => Some(mount.prefix.as_str()).filter(|prefix| !prefix.is_empty()),
+            rel => mount.prefix.strip_prefix(rel).and_then(|rest| rest.strip_prefix('/')),
+        };
+        if let Some(below) = below {
+            let name = below.split('/').next().unwrap_or(below);
+            if !entries.iter().any(|(n, _)| n == name) {
+                entries.push((name.to_string(), VfsMetadata::dir()));
+            }
+            found = true;
+            continue;
+        }
+        let Some(path) = mount.strip(rel) else {
+            continue;
+        };
+        if !fs_is_dir(mount.fs.as_ref(), path).await {
+            continue;
+        }
+        found = true;
+        for entry in mount.fs.read_dir(path).await? {
+            if !entries.iter().any(|(name, _)| *name == entry.0) {
+                entries.push(entry);
+            }
+        }
+    }
+    if !found {
+        return Err(std::io::Error::new(std::io::ErrorKind::NotFound, "no such directory"));
+    }
+    Ok(entries)
+}
+
+async fn fs_is_dir(fs: &dyn VirtualFs, path: &str) -> bool {
+    fs.metadata(path).await.is_ok_and(|m| m.is_dir)
+}
+
+async fn serve_file(fs: &dyn VirtualFs, path: &str, rel: &str, req: &Parts, settings: &Settings) -> std::io::Result<Response<Body>> {
+    settings.check_extension(rel)?;
+    let mime = mime_guess::from_path(rel).first_or_octet_stream();
+    let content_type = settings.content_type(rel, &mime);
+    let content_type = &*content_type;
+    let mut metadata = fs.metadata(path).await?;
+    if metadata.is_dir {
+        return Err(std::io::Error::new(std::io::ErrorKind::NotFound, "not a file"));
+    }
+
+    // a precompressed sibling wins over compressing on the fly
+    let accept_encoding = req.headers.get(header::ACCEPT_ENCODING).and_then(|v| v.to_str().ok());
+    let mut file_path = path.to_string();
+    let mut encoding = None;
+    for candidate in compression::negotiate(accept_encoding, &settings.precompressed) {
+        let sibling = candidate.sibling(path);
+        if let Ok(sibling_metadata) = fs.metadata(&sibling).await
+            && !sibling_metadata.is_dir
+        {
+            file_path = sibling;
+            metadata = sibling_metadata;
+            encoding = Some(candidate);
+            break;
+        }
+    }
+    let dynamic = encoding
+        .is_none()
+        .then(|| settings.dynamic_encoding(accept_encoding, metadata.len, &mime))
+        .flatten();
+    let path = file_path.as_str();
+
+    let len = metadata.len;
+    let modified = metadata.modified;
+    let mut etag = conditional::etag(settings.etag, &settings.hashes, fs, path, len, modified).await?;
+    if let Some(encoding) = dynamic {
+        etag = etag.map(|etag| compression::encoded_etag(etag, encoding));
+    }
     let mut builder = Response::builder()
         .status(StatusCode::OK)
-        .header("Content-Type", content_type);
-    if let Ok(time) = metadata.modified() {
+        .header(header::ACCEPT_RANGES, if dynamic.is_some() { "none" } else { "bytes" });
+    if let Some(encoding) = encoding.or(dynamic) {
+        builder = builder.header(header::CONTENT_ENCODING, encoding.name());
+    }
+    if settings.negotiates() {
+        builder = builder.header(header::VARY, "accept-encoding");
+    }
+    if let Some(time) = modified {
         let datetime = httpdate::fmt_http_date(time);
-        builder = builder.header("Last-Modified", datetime);
+        builder = builder.header(header::LAST_MODIFIED, datetime);
+    }
+    if let Some(etag) = &etag {
+        builder = builder.header(header::ETAG, etag);
     }
-    if method == Method::HEAD {
-        return Ok(builder.body(Body::empty()).unwrap());
+    if let Some(cache_control) = settings.cache_control(rel) {
+        builder = builder.header(header::CACHE_CONTROL, cache_control);
+    }
+    if let Some(status) = conditional::evaluate(req, etag.as_deref(), modified) {
+        return Ok(builder.status(status).body(Body::empty()).unwrap());
+    }
+    builder = settings.file_headers(rel, builder);
+    #[cfg(feature = "compression")]
+    if let Some(encoding) = dynamic {
+        // the compressed length is unknown up front, and ranges would apply to the compressed bytes
+        let body = if req.method == Method::HEAD {
+            Body::empty()
+        } else {
+            compression::compressed_body(fs, path, encoding).await?
+        };
+        return Ok(builder.header(header::CONTENT_TYPE, content_type).body(body).unwrap());
+    }
+    if req.method == Method::HEAD {
+        return Ok(builder
+            .header(header::CONTENT_TYPE, content_type)
+            .header(header::CONTENT_LENGTH, len)
+            .body(Body::empty())
+            .unwrap());
+    }
+
+    let range = req
+        .headers
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .filter(|_| req.method == Method::GET)
+        .filter(|_| {
+            req.headers
+                .get(header::IF_RANGE)
+                .and_then(|v| v.to_str().ok())
+                .is_none_or(|if_range| range::if_range_matches(if_range, modified, etag.as_deref()))
+        })
+        .map_or(RangeRequest::Full, |header| range::parse_range(header, len));
+    match range {
+        RangeRequest::Full => {
+            let stream = ReaderStream::new(fs.open(path, 0).await?);
+            Ok(builder
+                .header(header::CONTENT_TYPE, content_type)
+                .header(header::CONTENT_LENGTH, len)
+                .body(Body::from_stream(stream))
+                .unwrap())
+        }
+        RangeRequest::Unsatisfiable => Ok(builder
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(header::CONTENT_RANGE, format!("bytes */{}", len))
+            .body(Body::empty())
+            .unwrap()),
+        RangeRequest::Partial(ranges) if ranges.len() == 1 => {
+            let (part_len, body) = range::range_body(fs, path, &ranges[0]).await?;
+            Ok(builder
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(header::CONTENT_TYPE, content_type)
+                .header(header::CONTENT_RANGE, range::content_range(&ranges[0], len))
+                .header(header::CONTENT_LENGTH, part_len)
+                .body(body)
+                .unwrap())
+        }
+        RangeRequest::Partial(ranges) => {
+            let (boundary, total, body) = range::multipart_body(fs, path, &ranges, content_type, len).await?;
+            Ok(builder
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(header::CONTENT_TYPE, format!("multipart/byteranges; boundary={}", boundary))
+                .header(header::CONTENT_LENGTH, total)
+                .body(body)
+                .unwrap())
+        }
     }
-    let file = File::open(path).await?;
-    let stream = ReaderStream::new(file);
-    Ok(builder.body(Body::from_stream(stream)).unwrap())
+}
+
+/// A file source added to the builder; disk roots are opened at build time, once the
+/// symlink policy is known.
+enum MountSource {
+    Disk(PathBuf),
+    Virtual(Arc<dyn VirtualFs>),
 }
 
 pub struct StaticFileServiceBuilder {
     root: PathBuf,
-    spa_fallback: bool,
-    cors_layer: Option<CorsLayer>,
+    mounts: Vec<(String, MountSource)>,
+    pub(super) cors_layer: Option<CorsLayer>,
+    settings: Settings,
 }
 
 impl StaticFileServiceBuilder {
     pub fn new(root: impl Into<PathBuf>) -> Self {
+        let root = root.into();
         Self {
-            root: root.into(),
-            spa_fallback: false,
+            mounts: vec![(String::new(), MountSource::Disk(root.clone()))],
+            root,
             cors_layer: None,
+            settings: Settings::default(),
         }
     }
 
+    /// Adds a root searched after the previous ones, e.g. a base directory under a theme:
+    /// `StaticFileServiceBuilder::new("./theme").root("./base")`.
+    pub fn root(self, root: impl Into<PathBuf>) -> Self {
+        self.mount("/", root)
+    }
+
+    /// Serves `root` below `prefix`, e.g. `.mount("/uploads", "./data/uploads")`. Mounts are
+    /// searched in the order they were added, the first one having the file wins.
+    pub fn mount(mut self, prefix: &str, root: impl Into<PathBuf>) -> Self {
+        self.mounts.push((prefix.trim_matches('/').to_string(), MountSource::Disk(root.into())));
+        self
+    }
+
+    /// Serves a [`VirtualFs`](super::vfs::VirtualFs) below `prefix`, like [`mount`](Self::mount).
+    pub fn mount_fs(mut self, prefix: &str, fs: impl VirtualFs) -> Self {
+        self.mounts.push((prefix.trim_matches('/').to_string(), MountSource::Virtual(Arc::new(fs))));
+        self
+    }
+
+    /// Serves `fs` in place of the root directory given to [`new`](Self::new).
+    pub(super) fn replace_root(mut self, fs: impl VirtualFs) -> Self {
+        self.mounts[0].1 = MountSource::Virtual(Arc::new(fs));
+        self
+    }
+
+    /// How the `ETag` of each file is computed, [`ETagStrategy::Weak`] by default.
+    pub fn etag(mut self, strategy: ETagStrategy) -> Self {
+        self.settings.etag = strategy;
+        self
+    }
+
+    /// Sends `value` as `Cache-Control` for the files matching `pattern`, e.g.
+    /// `.cache_control("assets/**", "public, max-age=31536000, immutable")` and
+    /// `.cache_control("index.html", "no-cache")`. The first matching pattern wins.
+    pub fn cache_control(mut self, pattern: &str, value: &str) -> Self {
+        let value = HeaderValue::from_str(value)
+            .unwrap_or_else(|_| panic!("invalid Cache-Control value for `{}`: {:?}", pattern, value));
+        self.settings.cache_control.push((Glob::new(pattern), value));
+        self
+    }
+
+    /// Serves precompressed siblings such as `app.js.br`, `app.js.gz` or `app.js.zst` when the
+    /// client accepts them, preferring the encodings in the given order on equal `q`.
+    pub fn precompressed(mut self, encodings: &[Encoding]) -> Self {
+        self.settings.precompressed = encodings.to_vec();
+        self
+    }
+
+    /// Compresses text-like files of at least `min_size` bytes on the fly when no precompressed
+    /// sibling matches. Compressed responses have no `Content-Length` and ignore `Range`.
+    #[cfg(feature = "compression")]
+    pub fn compress_on_the_fly(mut self, encodings: &[Encoding], min_size: u64) -> Self {
+        self.settings.dynamic = Some(compression::DynamicCompression { encodings: encodings.to_vec(), min_size });
+        self
+    }
+
+    /// Files served when a directory is requested, `["index.html"]` by default; an empty list
+    /// disables index files.
+    pub fn index_files(mut self, files: &[&str]) -> Self {
+        self.settings.index_files = files.iter().map(|f| f.to_string()).collect();
+        self
+    }
+
+    /// Whether a directory requested without a trailing slash is redirected (`308`) to the
+    /// path with one, so relative links inside its index resolve correctly. Enabled by default.
+    pub fn redirect_trailing_slash(mut self, enable: bool) -> Self {
+        self.settings.redirect_trailing_slash = enable;
+        self
+    }
+
+    /// Lists directories without an index file instead of answering `404`.
+    pub fn directory_listing(mut self, listing: DirectoryListing) -> Self {
+        self.settings.listing = Some(listing);
+        self
+    }
+
+    /// How dotfiles such as `.env` or `.git/config` are handled, [`DotFiles::Ignore`] by default.
+    pub fn dotfiles(mut self, policy: DotFiles) -> Self {
+        self.settings.dotfiles = policy;
+        self
+    }
+
+    /// Which symlinks are followed, [`SymlinkPolicy::WithinRoot`] by default.
+    pub fn follow_symlinks(mut self, policy: SymlinkPolicy) -> Self {
+        self.settings.symlinks = policy;
+        self
+    }
+
+    /// Only serves files with these extensions (case-insensitive, without the dot).
+    pub fn allow_extensions(mut self, extensions: &[&str]) -> Self {
+        self.settings.allow_extensions = Some(extensions.iter().map(|e| e.to_ascii_lowercase()).collect());
+        self
+    }
+
+    /// Never serves files with these extensions (case-insensitive, without the dot), e.g. `["map", "bak"]`.
+    pub fn deny_extensions(mut self, extensions: &[&str]) -> Self {
+        self.settings.deny_extensions = extensions.iter().map(|e| e.to_ascii_lowercase()).collect();
+        self
+    }
+
+    /// Whether file responses carry `X-Content-Type-Options: nosniff`, enabled by default.
+    pub fn nosniff(mut self, enable: bool) -> Self {
+        self.settings.nosniff = enable;
+        self
+    }
+
+    /// Sends the files matching `pattern` with `Content-Disposition: attachment`, so browsers
+    /// download them instead of rendering them.
+    pub fn attachment(mut self, pattern: &str) -> Self {
+        self.settings.attachments.push(Glob::new(pattern));
+        self
+    }
+
+    /// Falls back to `index.html` for missed navigations, see [`SpaFallback`] for the rules.
     pub fn with_spa_fallback(mut self, enable: bool) -> Self {
-        self.spa_fallback = enable;
+        if !enable {
+            self.settings.fallbacks.clear();
+        } else if self.settings.fallbacks.is_empty() {
+            self.settings.fallbacks.push(SpaFallback::new("index.html"));
+        }
+        self
+    }
+
+    /// Adds a fallback, e.g. `SpaFallback::new("admin/index.html").prefix("/admin")` for one of
+    /// several SPAs under the same root.
+    pub fn spa_fallback(mut self, fallback: SpaFallback) -> Self {
+        self.settings.fallbacks.push(fallback);
+        self
+    }
+
+    /// Serves `file`, relative to the root, as the body of `status` responses (`404`, `403` or `500`).
+    pub fn error_page(mut self, status: StatusCode, file: &str) -> Self {
+        self.settings.error_pages.push((status, file.trim_start_matches('/').to_string()));
+        self
+    }
+
+    /// Keeps metadata and small files in memory, see [`StaticCache`]; keep a clone of `cache`
+    /// to read its [`stats`](StaticCache::stats) or invalidate entries.
+    pub fn cache(mut self, cache: StaticCache) -> Self {
+        self.settings.cache = Some(cache);
+        self
+    }
+
+    /// Accepts `PUT`, multipart `POST` and `DELETE` requests as described by `policy`; files
+    /// are written to the first disk mount covering the path.
+    pub fn with_uploads(mut self, policy: UploadPolicy) -> Self {
+        self.settings.uploads = Some(policy);
         self
     }
 
@@ -129,9 +721,31 @@ impl StaticFileServiceBuilder {
     }
 
     pub fn build(self) -> StaticFileService {
+        let symlinks = self.settings.symlinks;
+        let cache = self.settings.cache.clone();
+        let mounts = self
+            .mounts
+            .into_iter()
+            .enumerate()
+            .map(|(i, (prefix, source))| {
+                let (fs, disk): (Arc<dyn VirtualFs>, _) = match source {
+                    MountSource::Disk(root) => {
+                        let disk = Arc::new(DiskFs::new(root, symlinks));
+                        (disk.clone(), Some(disk))
+                    }
+                    MountSource::Virtual(fs) => (fs, None),
+                };
+                let fs = match &cache {
+                    Some(cache) => Arc::new(CachedFs::new(fs, cache.clone(), i, prefix.clone())),
+                    None => fs,
+                };
+                Mount { prefix, fs, disk }
+            })
+            .collect();
         StaticFileService {
-            root: Arc::new(self.root),
-            spa_fallback: self.spa_fallback,
+            root: Arc::new(self.root.canonicalize().unwrap_or(self.root)),
+            mounts,
+            settings: Arc::new(self.settings),
         }
     }
 
@@ -0,0 +1,636 @@
+//! Uploads for [`StaticFileService`](super::static_layer::StaticFileService): `PUT` a file,
+//! `POST` a multipart form to a directory or `DELETE` a file, see
+//! [`StaticFileServiceBuilder::with_uploads`](super::static_layer::StaticFileServiceBuilder::with_uploads).
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use axum::{
+    body::{Body, HttpBody},
+    http::{header, request::Parts, HeaderValue, Method, Request, Response, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use percent_encoding::utf8_percent_encode;
+use serde::Serialize;
+use tokio::io::AsyncWriteExt;
+
+use crate::rejection::ExumRejection;
+use super::{
+    conditional,
+    listing::HREF_ENCODE_SET,
+    security::{self, DotFiles},
+    static_layer::{content_type, original_path, Mount, Settings},
+    vfs::{DiskFs, VirtualFs},
+};
+
+/// Types browsers execute scripts in, see [`UploadPolicy::allow_active_content`].
+const ACTIVE_TYPES: &[&str] = &[
+    "text/html",
+    "application/xhtml+xml",
+    "image/svg+xml",
+    "text/xml",
+    "application/xml",
+    "text/javascript",
+    "application/javascript",
+    "application/ecmascript",
+];
+
+type Authorize = dyn Fn(&Parts) -> Result<(), ExumRejection> + Send + Sync;
+
+/// What happens when an upload targets an existing file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Overwrite {
+    /// Answer `409 Conflict`.
+    #[default]
+    Reject,
+    /// Replace the file atomically.
+    Replace,
+    /// Store the upload next to it as `name-1.ext`, `name-2.ext`, ...
+    Rename,
+}
+
+/// Upload settings. Every upload request is authorized first; files are written to a temporary
+/// file next to the target and renamed into place once complete.
+#[derive(Clone)]
+pub struct UploadPolicy {
+    authorize: Arc<Authorize>,
+    max_size: u64,
+    max_files: usize,
+    /// `image/*` style wildcards are supported. Empty allows all.
+    allowed_types: Vec<String>,
+    overwrite: Overwrite,
+    delete: bool,
+    active_content: bool,
+}
+
+impl fmt::Debug for UploadPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UploadPolicy")
+            .field("max_size", &self.max_size)
+            .field("max_files", &self.max_files)
+            .field("allowed_types", &self.allowed_types)
+            .field("overwrite", &self.overwrite)
+            .field("delete", &self.delete)
+            .field("active_content", &self.active_content)
+            .finish_non_exhaustive()
+    }
+}
+
+impl UploadPolicy {
+    /// Accepts the requests for which `authorize` returns `true`, others get `401 Unauthorized`.
+    pub fn new(authorize: impl Fn(&Parts) -> bool + Send + Sync + 'static) -> Self {
+        Self::with_authorizer(move |req| {
+            if authorize(req) {
+                Ok(())
+            } else {
+                Err(ExumRejection::new(StatusCode::UNAUTHORIZED, "unauthorized", "Uploads require authentication"))
+            }
+        })
+    }
+
+    /// Accepts the requests carrying credentials `auth` verifies, answering others with its
+    /// `WWW-Authenticate` challenges.
+    #[cfg(feature = "auth")]
+    pub fn with_auth(auth: Arc<crate::auth::AuthService>) -> Self {
+        Self::with_authorizer(move |req| match auth.authenticate(&req.headers) {
+            Ok(Some(_)) => Ok(()),
+            Ok(None) => Err(auth.reject(crate::auth::AuthError::MissingCredentials)),
+            Err(e) => Err(auth.reject(e)),
+        })
+    }
+
+    /// Like [`new`](Self::new), `authorize` choosing the rejection itself.
+    pub fn with_authorizer(authorize: impl Fn(&Parts) -> Result<(), ExumRejection> + Send + Sync + 'static) -> Self {
+        Self {
+            authorize: Arc::new(authorize),
+            max_size: 10 * 1024 * 1024,
+            max_files: 16,
+            allowed_types: Vec::new(),
+            overwrite: Overwrite::default(),
+            delete: true,
+            active_content: false,
+        }
+    }
+
+    /// The size limit of each file, 10 MiB by default.
+    pub fn max_size(mut self, max: u64) -> Self {
+        self.max_size = max;
+        self
+    }
+
+    /// The number of files a multipart request may carry, 16 by default.
+    pub fn max_files(mut self, max: usize) -> Self {
+        self.max_files = max;
+        self
+    }
+
+    /// Only accepts these content types, e.g. `["image/*", "application/pdf"]`. The type checked
+    /// is the one the file will be served as, guessed from its extension; a different declared
+    /// type is rejected.
+    pub fn allowed_types(mut self, types: &[&str]) -> Self {
+        self.allowed_types = types.iter().map(|t| t.to_ascii_lowercase()).collect();
+        self
+    }
+
+    /// [`Overwrite::Reject`] by default.
+    pub fn overwrite(mut self, overwrite: Overwrite) -> Self {
+        self.overwrite = overwrite;
+        self
+    }
+
+    /// Whether `DELETE` removes files, enabled by default.
+    pub fn allow_delete(mut self, enable: bool) -> Self {
+        self.delete = enable;
+        self
+    }
+
+    /// Accepts HTML, SVG, XML and JavaScript files, rejected by default: served from the app's
+    /// origin, they run scripts with its cookies.
+    pub fn allow_active_content(mut self, enable: bool) -> Self {
+        self.active_content = enable;
+        self
+    }
+
+    fn type_allowed(&self, essence: &str) -> bool {
+        if self.allowed_types.is_empty() {
+            return true;
+        }
+        self.allowed_types.iter().any(|allowed| match allowed.strip_suffix("/*") {
+            Some(major) => essence.split('/').next() == Some(major),
+            None => allowed == "*/*" || allowed == essence,
+        })
+    }
+
+    /// The `Content-Type` `name` will be served with, once checked against the policy and the
+    /// type the client declared.
+    fn content_type(&self, declared: Option<&str>, name: &str) -> Result<String, ExumRejection> {
+        let mime = mime_guess::from_path(name).first_or_octet_stream();
+        let essence = mime.essence_str();
+        let declared = declared.map(|d| d.split(';').next().unwrap_or_default().trim().to_ascii_lowercase());
+        // `application/octet-stream` is what clients send when they do not know better
+        if let Some(declared) = declared.filter(|d| !d.is_empty() && d != "application/octet-stream")
+            && declared != essence
+        {
+            return Err(rejection(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "content_type_mismatch",
+                format!("Declared type `{}` does not match `{}`, the type of `{}`", declared, essence, name),
+            ));
+        }
+        if (!self.active_content && ACTIVE_TYPES.contains(&essence)) || !self.type_allowed(essence) {
+            return Err(rejection(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "unsupported_content_type",
+                format!("Content type `{}` is not allowed", essence),
+            ));
+        }
+        Ok(content_type(&mime))
+    }
+}
+
+/// The JSON body describing a stored file.
+#[derive(Debug, Clone, Serialize)]
+pub struct StoredFile {
+    /// The URL path the file is served at.
+    pub path: String,
+    pub size: u64,
+    pub content_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    /// `false` when an existing file was replaced.
+    pub created: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct StoredFiles {
+    files: Vec<StoredFile>,
+}
+
+pub(super) fn is_upload(method: &Method) -> bool {
+    matches!(*method, Method::PUT | Method::POST | Method::DELETE)
+}
+
+fn rejection(status: StatusCode, code: &'static str, message: impl Into<String>) -> ExumRejection {
+    ExumRejection::new(status, code, message)
+}
+
+fn io_error(e: std::io::Error) -> ExumRejection {
+    use std::io::ErrorKind;
+    match e.kind() {
+        ErrorKind::NotFound => rejection(StatusCode::NOT_FOUND, "not_found", "No such file or directory"),
+        ErrorKind::PermissionDenied => rejection(StatusCode::FORBIDDEN, "forbidden_path", "Path is not writable"),
+        ErrorKind::NotADirectory | ErrorKind::IsADirectory | ErrorKind::AlreadyExists | ErrorKind::DirectoryNotEmpty => {
+            rejection(StatusCode::CONFLICT, "path_conflict", "Path conflicts with an existing file or directory")
+        }
+        _ => {
+            tracing::warn!("Failed to store upload: {}", e);
+            rejection(StatusCode::INTERNAL_SERVER_ERROR, "upload_failed", "Failed to store uploaded file")
+        }
+    }
+}
+
+fn too_large(max: u64) -> ExumRejection {
+    rejection(StatusCode::PAYLOAD_TOO_LARGE, "payload_too_large", format!("File exceeds the size limit of {} bytes", max))
+}
+
+/// The disk mount uploads to `rel` go to, and the path inside it: the one with the longest
+/// prefix, the first added on ties. Virtual mounts are read-only.
+fn disk_mount<'a>(mounts: &'a [Mount], rel: &'a str) -> Result<(&'a Mount, &'a DiskFs, &'a str), ExumRejection> {
+    mounts
+        .iter()
+        .filter_map(|mount| Some((mount, mount.disk.as_deref()?, mount.strip(rel)?)))
+        .rev()
+        .max_by_key(|(mount, _, _)| mount.prefix.len())
+        .ok_or_else(|| {
+            rejection(StatusCode::METHOD_NOT_ALLOWED, "read_only", "Path is not writable")
+                .with_header(header::ALLOW, HeaderValue::from_static("GET, HEAD"))
+        })
+}
+
+/// Applies the dotfile and extension rules of the service to a path being written or deleted.
+fn check_path(settings: &Settings, rel: &str) -> Result<(), ExumRejection> {
+    let dotfile = security::is_dotfile(rel) && settings.dotfiles != DotFiles::Allow;
+    if dotfile || settings.check_extension(rel).is_err() {
+        return Err(rejection(StatusCode::FORBIDDEN, "forbidden_path", format!("Uploads to `{}` are not allowed", rel)));
+    }
+    Ok(())
+}
+
+/// Creates the directories of `dir` one by one, checking each against the symlink policy before
+/// anything is created inside it.
+async fn create_dirs(disk: &DiskFs, dir: &str) -> std::io::Result<PathBuf> {
+    let mut current = String::new();
+    let mut abs = disk.resolve("").await?;
+    for segment in dir.split('/').filter(|s| !s.is_empty()) {
+        current = if current.is_empty() { segment.to_string() } else { format!("{}/{}", current, segment) };
+        match tokio::fs::create_dir(disk.root().join(&current)).await {
+            Err(e) if e.kind() != std::io::ErrorKind::AlreadyExists => return Err(e),
+            _ => {}
+        }
+        abs = disk.resolve(&current).await?;
+        if !tokio::fs::metadata(&abs).await?.is_dir() {
+            return Err(std::io::Error::new(std::io::ErrorKind::NotADirectory, "not a directory"));
+        }
+    }
+    Ok(abs)
+}
+
+/// The deepest existing directory of `dir`, checked against the symlink policy like
+/// [`create_dirs`], and whether it is `dir` itself.
+async fn existing_dir(disk: &DiskFs, dir: &str) -> std::io::Result<(PathBuf, bool)> {
+    let mut current = String::new();
+    let mut abs = disk.resolve("").await?;
+    for segment in dir.split('/').filter(|s| !s.is_empty()) {
+        current = if current.is_empty() { segment.to_string() } else { format!("{}/{}", current, segment) };
+        match tokio::fs::symlink_metadata(disk.root().join(&current)).await {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((abs, false)),
+            Err(e) => return Err(e),
+            Ok(_) => {}
+        }
+        abs = disk.resolve(&current).await?;
+        if !tokio::fs::metadata(&abs).await?.is_dir() {
+            return Err(std::io::Error::new(std::io::ErrorKind::NotADirectory, "not a directory"));
+        }
+    }
+    Ok((abs, true))
+}
+
+/// A temporary file in the upload target's directory, or its deepest existing parent, removed
+/// unless persisted.
+struct TempUpload {
+    file: tokio::fs::File,
+    path: PathBuf,
+    size: u64,
+    persisted: bool,
+}
+
+impl TempUpload {
+    async fn create(dir: &Path, name: &str) -> std::io::Result<Self> {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.subsec_nanos()).unwrap_or_default();
+        let unique = format!("{}-{}-{}", std::process::id(), COUNTER.fetch_add(1, Ordering::Relaxed), nanos);
+        let path = dir.join(format!(".{}.{}.upload", name, unique));
+        let file = tokio::fs::OpenOptions::new().write(true).create_new(true).open(&path).await?;
+        Ok(Self { file, path, size: 0, persisted: false })
+    }
+
+    async fn write(&mut self, chunk: &[u8], max: u64) -> Result<(), ExumRejection> {
+        self.size += chunk.len() as u64;
+        if self.size > max {
+            return Err(too_large(max));
+        }
+        self.file.write_all(chunk).await.map_err(io_error)
+    }
+
+    /// Moves the file to `dir/name` following `overwrite`; returns the final name and whether
+    /// it did not exist before.
+    async fn persist(mut self, dir: &Path, name: &str, overwrite: Overwrite) -> std::io::Result<(String, bool)> {
+        self.file.flush().await?;
+        self.file.sync_all().await?;
+        let result = match overwrite {
+            Overwrite::Replace => {
+                let target = dir.join(name);
+                let existed = tokio::fs::symlink_metadata(&target).await.is_ok();
+                tokio::fs::rename(&self.path, &target).await.map(|_| (name.to_string(), !existed))
+            }
+            // linking fails when the target exists, unlike renaming
+            Overwrite::Reject => tokio::fs::hard_link(&self.path, dir.join(name)).await.map(|_| (name.to_string(), true)),
+            Overwrite::Rename => {
+                let (stem, ext) = match name.rsplit_once('.') {
+                    Some((stem, ext)) if !stem.is_empty() => (stem, Some(ext)),
+                    _ => (name, None),
+                };
+                let mut result = Err(std::io::Error::new(std::io::ErrorKind::AlreadyExists, "no free name"));
+                for i in 0..1000 {
+                    let candidate = match (i, ext) {
+                        (0, _) => name.to_string(),
+                        (i, Some(ext)) => format!("{}-{}.{}", stem, i, ext),
+                        (i, None) => format!("{}-{}", stem, i),
+                    };
+                    match tokio::fs::hard_link(&self.path, dir.join(&candidate)).await {
+                        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                        r => {
+                            result = r.map(|_| (candidate, true));
+                            break;
+                        }
+                    }
+                }
+                result
+            }
+        };
+        if overwrite == Overwrite::Replace && result.is_ok() {
+            self.persisted = true;
+        }
+        result
+    }
+}
+
+impl Drop for TempUpload {
+    fn drop(&mut self) {
+        if !self.persisted {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Where a file is being stored.
+struct Target<'a> {
+    mount: &'a Mount,
+    disk: &'a DiskFs,
+    /// Directory inside the mount.
+    dir: String,
+    /// The deepest existing directory of `dir`, where uploads are staged.
+    staging: PathBuf,
+    /// Whether `staging` is `dir` itself.
+    exists: bool,
+}
+
+impl<'a> Target<'a> {
+    /// Checks the directory `rel` (relative to the service) without creating anything, so a
+    /// rejected upload leaves no empty directories behind.
+    async fn new(mounts: &'a [Mount], rel: &'a str) -> Result<Self, ExumRejection> {
+        let (mount, disk, dir) = disk_mount(mounts, rel)?;
+        let (staging, exists) = existing_dir(disk, dir).await.map_err(io_error)?;
+        Ok(Self { mount, disk, dir: dir.to_string(), staging, exists })
+    }
+
+    /// The absolute path of `name` when the directory already exists.
+    fn existing(&self, name: &str) -> Option<PathBuf> {
+        self.exists.then(|| self.staging.join(name))
+    }
+
+    /// Creates the missing directories, once the upload is complete.
+    async fn create_dir(&self) -> Result<PathBuf, ExumRejection> {
+        if self.exists {
+            return Ok(self.staging.clone());
+        }
+        create_dirs(self.disk, &self.dir).await.map_err(io_error)
+    }
+
+    /// The path of `name` relative to the service.
+    fn rel(&self, name: &str) -> String {
+        [self.mount.prefix.as_str(), self.dir.as_str(), name].iter().filter(|s| !s.is_empty()).copied().collect::<Vec<_>>().join("/")
+    }
+
+    async fn stored(&self, name: &str, url: String, content_type: String, created: bool, settings: &Settings) -> Result<StoredFile, ExumRejection> {
+        let path = if self.dir.is_empty() { name.to_string() } else { format!("{}/{}", self.dir, name) };
+        let fs: &dyn VirtualFs = self.mount.fs.as_ref();
+        if let Some(cache) = &settings.cache {
+            cache.invalidate(&self.rel(name));
+        }
+        let metadata = fs.metadata(&path).await.map_err(io_error)?;
+        let etag = conditional::etag(settings.etag, &settings.hashes, fs, &path, metadata.len, metadata.modified)
+            .await
+            .map_err(io_error)?;
+        Ok(StoredFile { path: url, size: metadata.len, content_type, etag, created })
+    }
+}
+
+fn split_name(rel: &str) -> (&str, &str) {
+    rel.rsplit_once('/').unwrap_or(("", rel))
+}
+
+/// The path the service is nested at, without trailing `/`.
+fn service_base(parts: &Parts) -> String {
+    let base = original_path(parts).strip_suffix(parts.uri.path()).unwrap_or_default();
+    base.trim_end_matches('/').to_string()
+}
+
+/// The URL path of `rel`, relative to the service.
+fn url_for(base: &str, rel: &str) -> String {
+    let mut url = base.to_string();
+    for segment in rel.split('/') {
+        url.push('/');
+        url.extend(utf8_percent_encode(segment, HREF_ENCODE_SET));
+    }
+    url
+}
+
+/// Answers an upload request: authorization, path checks, then `PUT`, `POST` or `DELETE`.
+pub(super) async fn handle(
+    req: Request<Body>,
+    rel: String,
+    mounts: &[Mount],
+    settings: &Settings,
+    policy: &UploadPolicy,
+) -> Response<Body> {
+    let (parts, body) = req.into_parts();
+    let request_id = crate::rejection::request_id(&parts.headers);
+    let result = match (policy.authorize)(&parts) {
+        Err(e) => Err(e),
+        Ok(()) => match parts.method {
+            Method::PUT => put(&parts, body, &rel, mounts, settings, policy).await,
+            Method::DELETE => delete(&parts, &rel, mounts, settings, policy).await,
+            _ => post(parts, body, &rel, mounts, settings, policy).await,
+        },
+    };
+    result.unwrap_or_else(|mut e| {
+        e.request_id = e.request_id.or(request_id);
+        e.into_response()
+    })
+}
+
+async fn put(parts: &Parts, mut body: Body, rel: &str, mounts: &[Mount], settings: &Settings, policy: &UploadPolicy) -> Result<Response<Body>, ExumRejection> {
+    if rel.is_empty() || original_path(parts).ends_with('/') {
+        return Err(rejection(StatusCode::BAD_REQUEST, "invalid_path", "PUT needs a file path"));
+    }
+    check_path(settings, rel)?;
+    let declared = parts.headers.get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok());
+    let content_type = policy.content_type(declared, rel)?;
+    let length = parts.headers.get(header::CONTENT_LENGTH).and_then(|v| v.to_str().ok()?.parse::<u64>().ok());
+    if length.is_some_and(|len| len > policy.max_size) {
+        return Err(too_large(policy.max_size));
+    }
+
+    let (dir, name) = split_name(rel);
+    let target = Target::new(mounts, dir).await?;
+    if let Some(path) = target.existing(name)
+        && tokio::fs::metadata(path).await.is_ok_and(|m| m.is_dir())
+    {
+        return Err(io_error(std::io::Error::from(std::io::ErrorKind::IsADirectory)));
+    }
+    let mut temp = TempUpload::create(&target.staging, name).await.map_err(io_error)?;
+    while let Some(frame) = std::future::poll_fn(|cx| Pin::new(&mut body).poll_frame(cx)).await {
+        let frame = frame.map_err(|e| rejection(StatusCode::BAD_REQUEST, "invalid_body", e.to_string()))?;
+        if let Ok(data) = frame.into_data() {
+            temp.write(&data, policy.max_size).await?;
+        }
+    }
+    let abs_dir = target.create_dir().await?;
+    let (stored_name, created) = temp.persist(&abs_dir, name, policy.overwrite).await.map_err(|e| exists(e, rel))?;
+    let url = url_for(&service_base(parts), &target.rel(&stored_name));
+    let stored = target.stored(&stored_name, url, content_type, created, settings).await?;
+    Ok(created_response(vec![stored], false))
+}
+
+#[cfg(feature = "multipart")]
+async fn post(parts: Parts, body: Body, rel: &str, mounts: &[Mount], settings: &Settings, policy: &UploadPolicy) -> Result<Response<Body>, ExumRejection> {
+    use axum::extract::{DefaultBodyLimit, FromRequest, Multipart};
+    use tower::{Layer, ServiceExt};
+
+    check_path(settings, rel)?;
+    let base = service_base(&parts);
+    // each file is limited while streaming, see `TempUpload::write`
+    let identity = tower::service_fn(|req: Request<Body>| async move { Ok::<_, std::convert::Infallible>(req) });
+    let Ok(req) = DefaultBodyLimit::disable().layer(identity).oneshot(Request::from_parts(parts, body)).await;
+    let mut multipart = Multipart::from_request(req, &()).await?;
+
+    // every part is received and checked before the first one is stored, so a rejected form
+    // leaves nothing behind
+    let target = Target::new(mounts, rel).await?;
+    let mut staged = Vec::new();
+    while let Some(mut field) = multipart.next_field().await? {
+        // text fields are ignored
+        let Some(file_name) = field.file_name() else { continue };
+        let name = file_name.rsplit(['/', '\\']).next().unwrap_or_default().to_string();
+        if name.is_empty() || name == "." || name == ".." {
+            return Err(rejection(StatusCode::BAD_REQUEST, "invalid_file_name", format!("Invalid file name `{}`", file_name)));
+        }
+        if staged.len() >= policy.max_files {
+            return Err(rejection(StatusCode::PAYLOAD_TOO_LARGE, "too_many_files", format!("At most {} files per request", policy.max_files)));
+        }
+        check_path(settings, &target.rel(&name))?;
+        let content_type = policy.content_type(field.content_type(), &name)?;
+        if policy.overwrite == Overwrite::Reject
+            && (staged.iter().any(|(staged, _, _)| *staged == name)
+                || match target.existing(&name) {
+                    Some(path) => tokio::fs::symlink_metadata(path).await.is_ok(),
+                    None => false,
+                })
+        {
+            return Err(exists(std::io::Error::from(std::io::ErrorKind::AlreadyExists), &name));
+        }
+        let mut temp = TempUpload::create(&target.staging, &name).await.map_err(io_error)?;
+        while let Some(chunk) = field.chunk().await? {
+            temp.write(&chunk, policy.max_size).await?;
+        }
+        staged.push((name, content_type, temp));
+    }
+    if staged.is_empty() {
+        return Err(rejection(StatusCode::BAD_REQUEST, "missing_file", "The form has no file part"));
+    }
+
+    let abs_dir = target.create_dir().await?;
+    let mut persisted: Vec<(String, String, bool)> = Vec::new();
+    for (name, content_type, temp) in staged {
+        match temp.persist(&abs_dir, &name, policy.overwrite).await {
+            Ok((stored_name, created)) => persisted.push((stored_name, content_type, created)),
+            Err(e) => {
+                // a file created concurrently; files replaced so far keep their new content
+                for (stored_name, _, created) in &persisted {
+                    if *created {
+                        let _ = tokio::fs::remove_file(abs_dir.join(stored_name)).await;
+                    }
+                }
+                return Err(exists(e, &name));
+            }
+        }
+    }
+    let mut stored = Vec::new();
+    for (stored_name, content_type, created) in persisted {
+        let url = url_for(&base, &target.rel(&stored_name));
+        stored.push(target.stored(&stored_name, url, content_type, created, settings).await?);
+    }
+    Ok(created_response(stored, true))
+}
+
+#[cfg(not(feature = "multipart"))]
+async fn post(_: Parts, _: Body, _: &str, _: &[Mount], _: &Settings, _: &UploadPolicy) -> Result<Response<Body>, ExumRejection> {
+    Err(rejection(StatusCode::METHOD_NOT_ALLOWED, "method_not_allowed", "Multipart uploads need the `multipart` feature")
+        .with_header(header::ALLOW, HeaderValue::from_static("GET, HEAD, PUT, DELETE")))
+}
+
+async fn delete(parts: &Parts, rel: &str, mounts: &[Mount], settings: &Settings, policy: &UploadPolicy) -> Result<Response<Body>, ExumRejection> {
+    if !policy.delete {
+        return Err(rejection(StatusCode::METHOD_NOT_ALLOWED, "method_not_allowed", "Deleting files is disabled")
+            .with_header(header::ALLOW, HeaderValue::from_static("GET, HEAD, PUT, POST")));
+    }
+    if rel.is_empty() || original_path(parts).ends_with('/') {
+        return Err(rejection(StatusCode::BAD_REQUEST, "invalid_path", "DELETE needs a file path"));
+    }
+    check_path(settings, rel)?;
+    let (_, disk, path) = disk_mount(mounts, rel)?;
+    let abs = disk.resolve(path).await.map_err(io_error)?;
+    if tokio::fs::metadata(&abs).await.map_err(io_error)?.is_dir() {
+        return Err(io_error(std::io::Error::from(std::io::ErrorKind::IsADirectory)));
+    }
+    tokio::fs::remove_file(&abs).await.map_err(io_error)?;
+    if let Some(cache) = &settings.cache {
+        cache.invalidate(rel);
+    }
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+fn exists(e: std::io::Error, name: &str) -> ExumRejection {
+    if e.kind() == std::io::ErrorKind::AlreadyExists {
+        return rejection(StatusCode::CONFLICT, "file_exists", format!("`{}` already exists", name)).with_param(name);
+    }
+    io_error(e)
+}
+
+/// `201 Created` (or `200 OK` when every file replaced an existing one) with the stored files.
+fn created_response(mut stored: Vec<StoredFile>, many: bool) -> Response<Body> {
+    let status = if stored.iter().any(|file| file.created) { StatusCode::CREATED } else { StatusCode::OK };
+    let mut response = if many {
+        (status, Json(StoredFiles { files: stored })).into_response()
+    } else {
+        let file = stored.remove(0);
+        let location = HeaderValue::from_str(&file.path).ok();
+        let mut response = (status, Json(file)).into_response();
+        if let Some(location) = location {
+            response.headers_mut().insert(header::LOCATION, location);
+        }
+        response
+    };
+    response.headers_mut().insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    response
+}
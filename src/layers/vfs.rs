@@ -0,0 +1,239 @@
+//! File sources for [`StaticFileService`](super::static_layer::StaticFileService): disk roots
+//! and anything implementing [`VirtualFs`], mounted with
+//! [`StaticFileServiceBuilder::mount_fs`](super::static_layer::StaticFileServiceBuilder::mount_fs).
+//!
+//! Paths are relative to the mount and separated by `/`, `""` being the mount itself. They only
+//! contain normal segments: `.` and `..` are removed before a file system sees them.
+
+use std::{
+    collections::BTreeMap,
+    io::{Cursor, ErrorKind, SeekFrom},
+    path::PathBuf,
+    pin::Pin,
+    time::SystemTime,
+};
+
+use axum::body::Bytes;
+use tokio::io::{AsyncRead, AsyncSeekExt};
+
+use super::security::SymlinkPolicy;
+
+pub type VfsFuture<'a, T> = Pin<Box<dyn Future<Output = std::io::Result<T>> + Send + 'a>>;
+pub type VfsReader = Box<dyn AsyncRead + Send + Unpin>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VfsMetadata {
+    pub is_dir: bool,
+    /// `0` for directories.
+    pub len: u64,
+    pub modified: Option<SystemTime>,
+}
+
+impl VfsMetadata {
+    pub fn file(len: u64, modified: Option<SystemTime>) -> Self {
+        Self { is_dir: false, len, modified }
+    }
+
+    pub fn dir() -> Self {
+        Self { is_dir: true, len: 0, modified: None }
+    }
+}
+
+/// A read-only file tree served with the same response logic as disk roots: ETags, ranges,
+/// precompressed siblings, index files and listings.
+pub trait VirtualFs: Send + Sync + 'static {
+    /// Fails with [`ErrorKind::NotFound`] when `path` does not exist.
+    fn metadata<'a>(&'a self, path: &'a str) -> VfsFuture<'a, VfsMetadata>;
+
+    /// Reads the file at `path` from byte `offset` to its end.
+    fn open<'a>(&'a self, path: &'a str, offset: u64) -> VfsFuture<'a, VfsReader>;
+
+    /// The entries of the directory at `path`, needed for directory listings only.
+    fn read_dir<'a>(&'a self, path: &'a str) -> VfsFuture<'a, Vec<(String, VfsMetadata)>> {
+        let _ = path;
+        Box::pin(async { Err(std::io::Error::new(ErrorKind::Unsupported, "listing not supported")) })
+    }
+
+    /// A tag computed ahead of time for the file at `path`, e.g. hashed at compile time, sent
+    /// instead of the one the [`ETagStrategy`](super::ETagStrategy) would compute unless tags
+    /// are disabled.
+    fn etag(&self, path: &str) -> Option<String> {
+        let _ = path;
+        None
+    }
+}
+
+fn not_found() -> std::io::Error {
+    std::io::Error::new(ErrorKind::NotFound, "no such file")
+}
+
+/// The entries below `dir` of a flat list of file paths, directories being implied by the
+/// paths of their files. `None` when `dir` is neither a file nor a directory.
+pub(crate) fn implied_entries<'a>(
+    paths: impl Iterator<Item = &'a str>,
+    dir: &str,
+) -> Option<BTreeMap<&'a str, bool>> {
+    let mut entries = BTreeMap::new();
+    let mut found = dir.is_empty();
+    for path in paths {
+        let rest = if dir.is_empty() {
+            path
+        } else {
+            match path.strip_prefix(dir).and_then(|rest| rest.strip_prefix('/')) {
+                Some(rest) => rest,
+                None => continue,
+            }
+        };
+        found = true;
+        match rest.split_once('/') {
+            Some((name, _)) => entries.insert(name, true),
+            None => entries.insert(rest, false),
+        };
+    }
+    found.then_some(entries)
+}
+
+/// A directory on disk.
+#[derive(Debug)]
+pub(crate) struct DiskFs {
+    /// Canonical, symlink checks compare against it.
+    root: PathBuf,
+    symlinks: SymlinkPolicy,
+}
+
+impl DiskFs {
+    pub(crate) fn new(root: PathBuf, symlinks: SymlinkPolicy) -> Self {
+        let root = root.canonicalize().unwrap_or(root);
+        Self { root, symlinks }
+    }
+
+    pub(crate) fn root(&self) -> &std::path::Path {
+        &self.root
+    }
+
+    /// The absolute path of `path`, hidden when it goes through a symlink the policy does
+    /// not follow.
+    pub(crate) async fn resolve(&self, path: &str) -> std::io::Result<PathBuf> {
+        let abs = self.root.join(path);
+        let followed = match self.symlinks {
+            SymlinkPolicy::Always => true,
+            // the root is canonical and `path` only has normal segments, so the canonical
+            // path differs exactly when a symlink is involved
+            SymlinkPolicy::Never => tokio::fs::canonicalize(&abs).await? == abs,
+            SymlinkPolicy::WithinRoot => tokio::fs::canonicalize(&abs).await?.starts_with(&self.root),
+        };
+        if !followed {
+            return Err(std::io::Error::new(ErrorKind::NotFound, "symlink not followed"));
+        }
+        Ok(abs)
+    }
+}
+
+fn disk_metadata(metadata: &std::fs::Metadata) -> VfsMetadata {
+    VfsMetadata {
+        is_dir: metadata.is_dir(),
+        len: if metadata.is_dir() { 0 } else { metadata.len() },
+        modified: metadata.modified().ok(),
+    }
+}
+
+impl VirtualFs for DiskFs {
+    fn metadata<'a>(&'a self, path: &'a str) -> VfsFuture<'a, VfsMetadata> {
+        Box::pin(async move {
+            let metadata = tokio::fs::metadata(self.resolve(path).await?).await?;
+            if !metadata.is_dir() && !metadata.is_file() {
+                return Err(not_found());
+            }
+            Ok(disk_metadata(&metadata))
+        })
+    }
+
+    fn open<'a>(&'a self, path: &'a str, offset: u64) -> VfsFuture<'a, VfsReader> {
+        Box::pin(async move {
+            let mut file = tokio::fs::File::open(self.resolve(path).await?).await?;
+            if offset > 0 {
+                file.seek(SeekFrom::Start(offset)).await?;
+            }
+            Ok(Box::new(file) as VfsReader)
+        })
+    }
+
+    fn read_dir<'a>(&'a self, path: &'a str) -> VfsFuture<'a, Vec<(String, VfsMetadata)>> {
+        Box::pin(async move {
+            let mut entries = Vec::new();
+            let mut read_dir = tokio::fs::read_dir(self.resolve(path).await?).await?;
+            while let Some(entry) = read_dir.next_entry().await? {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                let child = if path.is_empty() { name.clone() } else { format!("{}/{}", path, name) };
+                // symlinks the policy does not follow are hidden like on direct requests, broken
+                // links are skipped
+                let Ok(abs) = self.resolve(&child).await else { continue };
+                if let Ok(metadata) = tokio::fs::metadata(abs).await {
+                    entries.push((name, disk_metadata(&metadata)));
+                }
+            }
+            Ok(entries)
+        })
+    }
+}
+
+/// Files kept in memory, e.g. generated content or fixtures in tests.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryFs {
+    files: BTreeMap<String, (Bytes, SystemTime)>,
+}
+
+impl MemoryFs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds or replaces the file at `path`; its parent directories exist implicitly.
+    pub fn file(mut self, path: &str, content: impl Into<Bytes>) -> Self {
+        self.insert(path, content);
+        self
+    }
+
+    pub fn insert(&mut self, path: &str, content: impl Into<Bytes>) {
+        let path = path.trim_matches('/').to_string();
+        self.files.insert(path, (content.into(), SystemTime::now()));
+    }
+}
+
+impl VirtualFs for MemoryFs {
+    fn metadata<'a>(&'a self, path: &'a str) -> VfsFuture<'a, VfsMetadata> {
+        Box::pin(async move {
+            if let Some((bytes, modified)) = self.files.get(path) {
+                return Ok(VfsMetadata::file(bytes.len() as u64, Some(*modified)));
+            }
+            implied_entries(self.files.keys().map(String::as_str), path)
+                .map(|_| VfsMetadata::dir())
+                .ok_or_else(not_found)
+        })
+    }
+
+    fn open<'a>(&'a self, path: &'a str, offset: u64) -> VfsFuture<'a, VfsReader> {
+        Box::pin(async move {
+            let (bytes, _) = self.files.get(path).ok_or_else(not_found)?;
+            let start = (offset as usize).min(bytes.len());
+            Ok(Box::new(Cursor::new(bytes.slice(start..))) as VfsReader)
+        })
+    }
+
+    fn read_dir<'a>(&'a self, path: &'a str) -> VfsFuture<'a, Vec<(String, VfsMetadata)>> {
+        Box::pin(async move {
+            let entries = implied_entries(self.files.keys().map(String::as_str), path).ok_or_else(not_found)?;
+            Ok(entries
+                .into_iter()
+                .map(|(name, is_dir)| {
+                    let full = if path.is_empty() { name.to_string() } else { format!("{}/{}", path, name) };
+                    let metadata = match self.files.get(&full) {
+                        Some((bytes, modified)) if !is_dir => VfsMetadata::file(bytes.len() as u64, Some(*modified)),
+                        _ => VfsMetadata::dir(),
+                    };
+                    (name.to_string(), metadata)
+                })
+                .collect())
+        })
+    }
+}
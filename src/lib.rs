@@ -2,9 +2,25 @@ mod serve;
 pub use serve::*;
 
 mod env;
+pub use env::{detect_env, profile_active};
 pub mod controller;
+pub mod versioning;
+pub mod path;
+pub mod url;
 
 pub mod config;
+pub mod extract;
+pub mod body;
+pub use body::{Accept, BodyFormat};
+pub mod rejection;
+pub mod validate;
+pub use validate::Validate;
+#[cfg(feature = "multipart")]
+pub mod multipart;
+#[cfg(feature = "multipart")]
+pub use multipart::{MultipartForm, UploadedFile};
+#[cfg(feature = "auth")]
+pub mod auth;
 #[cfg(feature = "app_chain_ext")]
 mod app_chain_ext;
 #[cfg(feature = "app_chain_ext")]
@@ -0,0 +1,379 @@
+#![cfg(feature = "multipart")]
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    io,
+    path::Path,
+    str::FromStr,
+};
+
+use axum::{
+    body::Bytes,
+    extract::{
+        multipart::{MultipartError, MultipartRejection}, DefaultBodyLimit, FromRequest,
+        Multipart, Request,
+    },
+    http::StatusCode,
+};
+use tempfile::TempPath;
+use tokio::io::AsyncWriteExt;
+use tower::{Layer, ServiceExt};
+
+use crate::rejection::ExumRejection;
+
+pub const DEFAULT_MEMORY_THRESHOLD: u64 = 256 * 1024;
+
+/// Extra room for part headers and boundaries on top of `max_total_size`.
+const BODY_LIMIT_OVERHEAD: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    Text,
+    File,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FieldSpec {
+    pub kind: FieldKind,
+    pub max_size: Option<u64>,
+    /// Allowed content types for file parts, `image/*` style wildcards are supported. Empty allows all.
+    pub content_types: &'static [&'static str],
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FormLimits {
+    pub max_total_size: Option<u64>,
+    /// File parts larger than this are spilled to a temporary file.
+    pub memory_threshold: u64,
+    pub deny_unknown_fields: bool,
+}
+
+impl Default for FormLimits {
+    fn default() -> Self {
+        Self {
+            max_total_size: None,
+            memory_threshold: DEFAULT_MEMORY_THRESHOLD,
+            deny_unknown_fields: false,
+        }
+    }
+}
+
+/// A multipart form, usually implemented with `#[derive(MultipartForm)]`.
+pub trait MultipartForm: Sized {
+    fn limits() -> FormLimits {
+        FormLimits::default()
+    }
+
+    fn field_spec(name: &str) -> Option<FieldSpec>;
+
+    fn from_data(data: MultipartData) -> Result<Self, ExumRejection>;
+}
+
+#[derive(Debug)]
+pub enum FileData {
+    Memory(Bytes),
+    Temp(TempPath),
+}
+
+/// A file part received through `#[b(multipart)]`.
+#[derive(Debug)]
+pub struct UploadedFile {
+    pub field_name: String,
+    pub file_name: Option<String>,
+    pub content_type: Option<String>,
+    pub size: u64,
+    pub data: FileData,
+}
+
+impl UploadedFile {
+    pub fn is_in_memory(&self) -> bool {
+        matches!(self.data, FileData::Memory(_))
+    }
+
+    /// Path of the backing temp file, removed when the `UploadedFile` is dropped.
+    pub fn path(&self) -> Option<&Path> {
+        match &self.data {
+            FileData::Memory(_) => None,
+            FileData::Temp(p) => Some(p),
+        }
+    }
+
+    pub async fn bytes(&self) -> io::Result<Bytes> {
+        match &self.data {
+            FileData::Memory(b) => Ok(b.clone()),
+            FileData::Temp(p) => tokio::fs::read(p).await.map(Bytes::from),
+        }
+    }
+
+    pub async fn save_to(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        match &self.data {
+            FileData::Memory(b) => tokio::fs::write(path, b).await,
+            FileData::Temp(p) => tokio::fs::copy(p, path).await.map(|_| ()),
+        }
+    }
+}
+
+/// Raw parts collected from the request, consumed by `MultipartForm::from_data`.
+#[derive(Debug, Default)]
+pub struct MultipartData {
+    texts: HashMap<String, Vec<String>>,
+    files: HashMap<String, Vec<UploadedFile>>,
+}
+
+fn missing(name: &str) -> ExumRejection {
+    ExumRejection::new(
+        StatusCode::UNPROCESSABLE_ENTITY,
+        "missing_field",
+        format!("Missing multipart field `{}`", name),
+    )
+    .with_param(name)
+}
+
+fn parse_text<T>(name: &str, raw: String) -> Result<T, ExumRejection>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    raw.parse::<T>().map_err(|e| {
+        ExumRejection::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "invalid_field",
+            format!("Invalid multipart field `{}`: {}", name, e),
+        )
+        .with_param(name)
+    })
+}
+
+impl MultipartData {
+    pub fn text_opt<T>(&mut self, name: &str) -> Result<Option<T>, ExumRejection>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        match self.texts.get_mut(name).and_then(|v| (!v.is_empty()).then(|| v.remove(0))) {
+            Some(raw) => parse_text(name, raw).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn text<T>(&mut self, name: &str) -> Result<T, ExumRejection>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.text_opt(name)?.ok_or_else(|| missing(name))
+    }
+
+    pub fn texts<T>(&mut self, name: &str) -> Result<Vec<T>, ExumRejection>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.texts
+            .remove(name)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|raw| parse_text(name, raw))
+            .collect()
+    }
+
+    pub fn file_opt(&mut self, name: &str) -> Option<UploadedFile> {
+        self.files
+            .get_mut(name)
+            .and_then(|v| (!v.is_empty()).then(|| v.remove(0)))
+    }
+
+    pub fn file(&mut self, name: &str) -> Result<UploadedFile, ExumRejection> {
+        self.file_opt(name).ok_or_else(|| missing(name))
+    }
+
+    pub fn files(&mut self, name: &str) -> Vec<UploadedFile> {
+        self.files.remove(name).unwrap_or_default()
+    }
+}
+
+fn content_type_allowed(allowed: &[&str], content_type: Option<&str>) -> bool {
+    if allowed.is_empty() {
+        return true;
+    }
+    let Some(ct) = content_type else {
+        return false;
+    };
+    let ct = ct.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+    allowed.iter().any(|a| {
+        let a = a.to_ascii_lowercase();
+        match a.strip_suffix("/*") {
+            Some(major) => ct.split('/').next() == Some(major),
+            None => a == "*/*" || a == ct,
+        }
+    })
+}
+
+fn too_large(name: &str, message: String) -> ExumRejection {
+    ExumRejection::new(StatusCode::PAYLOAD_TOO_LARGE, "payload_too_large", message).with_param(name)
+}
+
+fn io_error(e: io::Error) -> ExumRejection {
+    tracing::warn!("Failed to buffer multipart upload: {}", e);
+    ExumRejection::new(StatusCode::INTERNAL_SERVER_ERROR, "upload_failed", "Failed to store uploaded file")
+}
+
+impl From<MultipartRejection> for ExumRejection {
+    fn from(e: MultipartRejection) -> Self {
+        ExumRejection::new(e.status(), "invalid_multipart", e.body_text())
+    }
+}
+
+impl From<MultipartError> for ExumRejection {
+    fn from(e: MultipartError) -> Self {
+        ExumRejection::new(e.status(), "invalid_multipart", e.body_text())
+    }
+}
+
+enum Buffer {
+    Memory(Vec<u8>),
+    Temp(tokio::fs::File, TempPath),
+}
+
+impl Buffer {
+    async fn push(&mut self, chunk: &[u8], threshold: u64) -> io::Result<()> {
+        if let Buffer::Memory(buf) = self
+            && (buf.len() + chunk.len()) as u64 > threshold
+        {
+            let (file, path) = tempfile::NamedTempFile::new()?.into_parts();
+            let mut file = tokio::fs::File::from_std(file);
+            file.write_all(buf).await?;
+            *self = Buffer::Temp(file, path);
+        }
+        match self {
+            Buffer::Memory(buf) => buf.extend_from_slice(chunk),
+            Buffer::Temp(file, _) => file.write_all(chunk).await?,
+        }
+        Ok(())
+    }
+
+    async fn finish(self) -> io::Result<FileData> {
+        match self {
+            Buffer::Memory(buf) => Ok(FileData::Memory(Bytes::from(buf))),
+            Buffer::Temp(mut file, path) => {
+                file.flush().await?;
+                Ok(FileData::Temp(path))
+            }
+        }
+    }
+}
+
+async fn collect<T: MultipartForm>(mut multipart: Multipart, limits: FormLimits) -> Result<MultipartData, ExumRejection> {
+    let mut data = MultipartData::default();
+    let mut total: u64 = 0;
+
+    while let Some(mut field) = multipart.next_field().await? {
+        let name = field.name().unwrap_or_default().to_string();
+        let Some(spec) = T::field_spec(&name) else {
+            if limits.deny_unknown_fields {
+                return Err(ExumRejection::new(
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    "unknown_field",
+                    format!("Unknown multipart field `{}`", name),
+                )
+                .with_param(name));
+            }
+            // drain the part so it still counts towards the total limit
+            while let Some(chunk) = field.chunk().await? {
+                total += chunk.len() as u64;
+                if limits.max_total_size.is_some_and(|max| total > max) {
+                    return Err(too_large(&name, "Multipart body exceeds the size limit".to_string()));
+                }
+            }
+            continue;
+        };
+
+        let content_type = field.content_type().map(|s| s.to_string());
+        if spec.kind == FieldKind::File && !content_type_allowed(spec.content_types, content_type.as_deref()) {
+            return Err(ExumRejection::new(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "unsupported_content_type",
+                format!(
+                    "Content type `{}` is not allowed for field `{}`",
+                    content_type.as_deref().unwrap_or("unknown"),
+                    name
+                ),
+            )
+            .with_param(name));
+        }
+        let file_name = field.file_name().map(|s| s.to_string());
+        let threshold = match spec.kind {
+            FieldKind::Text => u64::MAX,
+            FieldKind::File => limits.memory_threshold,
+        };
+
+        let mut size: u64 = 0;
+        let mut buffer = Buffer::Memory(Vec::new());
+        while let Some(chunk) = field.chunk().await? {
+            size += chunk.len() as u64;
+            total += chunk.len() as u64;
+            if let Some(max) = spec.max_size
+                && size > max
+            {
+                return Err(too_large(&name, format!("Field `{}` exceeds the size limit of {} bytes", name, max)));
+            }
+            if limits.max_total_size.is_some_and(|max| total > max) {
+                return Err(too_large(&name, "Multipart body exceeds the size limit".to_string()));
+            }
+            buffer.push(&chunk, threshold).await.map_err(io_error)?;
+        }
+
+        match spec.kind {
+            FieldKind::Text => {
+                let Buffer::Memory(buf) = buffer else { unreachable!() };
+                let text = String::from_utf8(buf).map_err(|_| {
+                    ExumRejection::new(
+                        StatusCode::UNPROCESSABLE_ENTITY,
+                        "invalid_field",
+                        format!("Multipart field `{}` is not valid UTF-8", name),
+                    )
+                    .with_param(name.clone())
+                })?;
+                data.texts.entry(name).or_default().push(text);
+            }
+            FieldKind::File => {
+                let file = UploadedFile {
+                    field_name: name.clone(),
+                    file_name,
+                    content_type,
+                    size,
+                    data: buffer.finish().await.map_err(io_error)?,
+                };
+                data.files.entry(name).or_default().push(file);
+            }
+        }
+    }
+    Ok(data)
+}
+
+/// Body extractor generated for `#[b(multipart)]`.
+pub struct TypedMultipart<T>(pub T);
+
+impl<S, T> FromRequest<S> for TypedMultipart<T>
+where
+    S: Send + Sync,
+    T: MultipartForm + Send,
+{
+    type Rejection = ExumRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let limits = T::limits();
+        let req = match limits.max_total_size {
+            Some(max) => {
+                // lift axum's default body limit up to our own, which `collect` enforces per part
+                let limit = usize::try_from(max).unwrap_or(usize::MAX).saturating_add(BODY_LIMIT_OVERHEAD);
+                let identity = tower::service_fn(|req: Request| async move { Ok::<_, std::convert::Infallible>(req) });
+                let Ok(req) = DefaultBodyLimit::max(limit).layer(identity).oneshot(req).await;
+                req
+            }
+            None => req,
+        };
+        let multipart = Multipart::from_request(req, state).await?;
+        T::from_data(collect::<T>(multipart, limits).await?).map(Self)
+    }
+}
@@ -0,0 +1,206 @@
+//! Constraints on path segments, declared in the route path: `/{id:u64}`, `/{slug:[a-z-]+}`, `/{day:date}`.
+//!
+//! The route macros strip the constraints from the path registered with axum and check them before
+//! any other extractor runs. A segment failing a type constraint is answered with `400 invalid_path`,
+//! a segment not matching a pattern with `404 not_found`.
+
+use std::{marker::PhantomData, sync::OnceLock};
+
+use axum::{
+    extract::{FromRequestParts, RawPathParams},
+    http::{request::Parts, StatusCode},
+};
+use regex::Regex;
+
+use crate::rejection::ExumRejection;
+
+/// The named constraints, e.g. `{id:u64}` or `{day:date}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathType {
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    Usize,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    Isize,
+    F32,
+    F64,
+    Bool,
+    /// A hyphenated UUID such as `67e55044-10b1-426f-9247-bb680e5fe0c8`.
+    Uuid,
+    /// A calendar date in `YYYY-MM-DD` form.
+    Date,
+}
+
+impl PathType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PathType::U8 => "u8",
+            PathType::U16 => "u16",
+            PathType::U32 => "u32",
+            PathType::U64 => "u64",
+            PathType::U128 => "u128",
+            PathType::Usize => "usize",
+            PathType::I8 => "i8",
+            PathType::I16 => "i16",
+            PathType::I32 => "i32",
+            PathType::I64 => "i64",
+            PathType::I128 => "i128",
+            PathType::Isize => "isize",
+            PathType::F32 => "f32",
+            PathType::F64 => "f64",
+            PathType::Bool => "bool",
+            PathType::Uuid => "uuid",
+            PathType::Date => "date",
+        }
+    }
+
+    pub fn matches(&self, value: &str) -> bool {
+        match self {
+            PathType::U8 => value.parse::<u8>().is_ok(),
+            PathType::U16 => value.parse::<u16>().is_ok(),
+            PathType::U32 => value.parse::<u32>().is_ok(),
+            PathType::U64 => value.parse::<u64>().is_ok(),
+            PathType::U128 => value.parse::<u128>().is_ok(),
+            PathType::Usize => value.parse::<usize>().is_ok(),
+            PathType::I8 => value.parse::<i8>().is_ok(),
+            PathType::I16 => value.parse::<i16>().is_ok(),
+            PathType::I32 => value.parse::<i32>().is_ok(),
+            PathType::I64 => value.parse::<i64>().is_ok(),
+            PathType::I128 => value.parse::<i128>().is_ok(),
+            PathType::Isize => value.parse::<isize>().is_ok(),
+            PathType::F32 => value.parse::<f32>().is_ok(),
+            PathType::F64 => value.parse::<f64>().is_ok(),
+            PathType::Bool => value.parse::<bool>().is_ok(),
+            PathType::Uuid => is_uuid(value),
+            PathType::Date => is_date(value),
+        }
+    }
+}
+
+fn is_uuid(value: &str) -> bool {
+    value.len() == 36
+        && value.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+fn is_date(value: &str) -> bool {
+    let mut parts = value.split('-');
+    let (Some(year), Some(month), Some(day), None) = (parts.next(), parts.next(), parts.next(), parts.next()) else {
+        return false;
+    };
+    if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+        return false;
+    }
+    let (Ok(year), Ok(month), Ok(day)) = (year.parse::<u32>(), month.parse::<u32>(), day.parse::<u32>()) else {
+        return false;
+    };
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return false,
+    };
+    (1..=days).contains(&day)
+}
+
+/// A regex matched against the whole segment, compiled on first use.
+#[derive(Debug)]
+pub struct Pattern {
+    source: &'static str,
+    regex: OnceLock<Regex>,
+}
+
+impl Pattern {
+    pub const fn new(source: &'static str) -> Self {
+        Self { source, regex: OnceLock::new() }
+    }
+
+    pub fn source(&self) -> &'static str {
+        self.source
+    }
+
+    pub fn is_match(&self, value: &str) -> bool {
+        self.regex
+            .get_or_init(|| {
+                Regex::new(&format!("^(?:{})$", self.source))
+                    .unwrap_or_else(|e| panic!("invalid path pattern `{}`: {}", self.source, e))
+            })
+            .is_match(value)
+    }
+}
+
+#[derive(Debug)]
+pub enum PathRule {
+    Type(PathType),
+    Pattern(Pattern),
+}
+
+/// The constraint of a single path parameter.
+#[derive(Debug)]
+pub struct PathConstraint {
+    pub name: &'static str,
+    pub rule: PathRule,
+}
+
+impl PathConstraint {
+    pub const fn new(name: &'static str, rule: PathRule) -> Self {
+        Self { name, rule }
+    }
+
+    pub fn check(&self, value: &str) -> Result<(), ExumRejection> {
+        match &self.rule {
+            PathRule::Type(ty) if !ty.matches(value) => Err(ExumRejection::new(
+                StatusCode::BAD_REQUEST,
+                "invalid_path",
+                format!("path parameter `{}` must be a {}, got `{}`", self.name, ty.name(), value),
+            )
+            .with_param(self.name)),
+            PathRule::Pattern(pattern) if !pattern.is_match(value) => Err(ExumRejection::new(
+                StatusCode::NOT_FOUND,
+                "not_found",
+                format!("path parameter `{}` does not match `{}`", self.name, pattern.source()),
+            )
+            .with_param(self.name)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Implemented by the marker types the route macros generate for constrained paths.
+pub trait PathConstraints {
+    fn constraints() -> &'static [PathConstraint];
+}
+
+/// Checks the path parameters of the request against `C`, without extracting them.
+pub struct PathCheck<C>(PhantomData<C>);
+
+impl<S, C> FromRequestParts<S> for PathCheck<C>
+where
+    S: Send + Sync,
+    C: PathConstraints,
+{
+    type Rejection = ExumRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let params = RawPathParams::from_request_parts(parts, state)
+            .await
+            .map_err(|e| ExumRejection::new(e.status(), "invalid_path", e.body_text()))?;
+        for constraint in C::constraints() {
+            if let Some((_, value)) = params.iter().find(|(name, _)| *name == constraint.name) {
+                constraint.check(value)?;
+            }
+        }
+        Ok(Self(PhantomData))
+    }
+}
@@ -0,0 +1,297 @@
+use std::{marker::PhantomData, sync::OnceLock};
+
+use axum::{
+    extract::{
+        rejection::{BytesRejection, FormRejection, JsonRejection, PathRejection, QueryRejection, StringRejection},
+        FromRequest, FromRequestParts, Request,
+    },
+    http::{request::Parts, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// A single failing field, addressed by its path in the request payload (e.g. `address.city`, `tags[2]`).
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub code: &'static str,
+    pub message: String,
+}
+
+/// The JSON error envelope returned by every exum extractor.
+///
+/// ```json
+/// { "status": 422, "code": "validation_failed", "message": "...", "param": "user", "fields": [...] }
+/// ```
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorBody {
+    pub status: u16,
+    pub code: &'static str,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub param: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+}
+
+impl ErrorBody {
+    pub fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(self.status).unwrap_or(StatusCode::BAD_REQUEST)
+    }
+}
+
+impl From<ExumRejection> for ErrorBody {
+    fn from(r: ExumRejection) -> Self {
+        Self {
+            status: r.status.as_u16(),
+            code: r.code,
+            message: r.message,
+            param: r.param,
+            request_id: r.request_id,
+            fields: r.fields.into_vec(),
+        }
+    }
+}
+
+impl IntoResponse for ErrorBody {
+    fn into_response(self) -> Response {
+        (self.status_code(), Json(self)).into_response()
+    }
+}
+
+/// The rejection produced by every macro-generated extractor.
+///
+/// Turning it into a response goes through the handler registered with `#[rejection_handler]`,
+/// falling back to [`ErrorBody`].
+#[derive(Debug, Clone)]
+pub struct ExumRejection {
+    pub status: StatusCode,
+    pub code: &'static str,
+    pub message: String,
+    pub param: Option<String>,
+    pub request_id: Option<String>,
+    pub fields: Box<[FieldError]>,
+    /// Extra response headers (e.g. `WWW-Authenticate`), kept even when a custom handler builds the response.
+    pub headers: Option<Box<HeaderMap>>,
+}
+
+impl ExumRejection {
+    pub fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code,
+            message: message.into(),
+            param: None,
+            request_id: None,
+            fields: Box::default(),
+            headers: None,
+        }
+    }
+
+    pub fn with_fields(mut self, fields: Vec<FieldError>) -> Self {
+        self.fields = fields.into_boxed_slice();
+        self
+    }
+
+    pub fn with_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.get_or_insert_default().append(name, value);
+        self
+    }
+
+    pub fn with_param(mut self, param: impl Into<String>) -> Self {
+        self.param = Some(param.into());
+        self
+    }
+
+    /// Sets the parameter name unless a more specific one is already known; `""` leaves it unset.
+    pub fn or_param(mut self, param: &str) -> Self {
+        if self.param.is_none() && !param.is_empty() {
+            self.param = Some(param.to_string());
+        }
+        self
+    }
+
+    pub fn with_request_id(mut self, headers: &HeaderMap) -> Self {
+        if self.request_id.is_none() {
+            self.request_id = request_id(headers);
+        }
+        self
+    }
+
+    pub fn into_default_response(self) -> Response {
+        ErrorBody::from(self).into_response()
+    }
+}
+
+impl IntoResponse for ExumRejection {
+    fn into_response(mut self) -> Response {
+        let headers = self.headers.take();
+        let mut res = (rejection_handler())(self);
+        if let Some(headers) = headers {
+            res.headers_mut().extend(*headers);
+        }
+        res
+    }
+}
+
+pub fn request_id(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string())
+}
+
+/// Extracts the serde path prefix (`page: invalid digit`) that axum puts in its error messages.
+fn leading_key(message: &str, prefix: &str) -> Option<String> {
+    let rest = message.strip_prefix(prefix)?;
+    let (key, _) = rest.split_once(": ")?;
+    if !key.is_empty() && key.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '.' || c == '[' || c == ']') {
+        Some(key.to_string())
+    } else {
+        None
+    }
+}
+
+impl From<PathRejection> for ExumRejection {
+    fn from(e: PathRejection) -> Self {
+        let param = match &e {
+            PathRejection::FailedToDeserializePathParams(inner) => match inner.kind() {
+                axum::extract::path::ErrorKind::ParseErrorAtKey { key, .. }
+                | axum::extract::path::ErrorKind::InvalidUtf8InPathParam { key } => Some(key.clone()),
+                _ => None,
+            },
+            _ => None,
+        };
+        let mut r = Self::new(e.status(), "invalid_path", e.body_text());
+        r.param = param;
+        r
+    }
+}
+
+impl From<QueryRejection> for ExumRejection {
+    fn from(e: QueryRejection) -> Self {
+        let message = e.body_text();
+        let mut r = Self::new(e.status(), "invalid_query", &message);
+        r.param = leading_key(&message, "Failed to deserialize query string: ");
+        r
+    }
+}
+
+impl From<JsonRejection> for ExumRejection {
+    fn from(e: JsonRejection) -> Self {
+        Self::new(e.status(), "invalid_body", e.body_text())
+    }
+}
+
+impl From<FormRejection> for ExumRejection {
+    fn from(e: FormRejection) -> Self {
+        Self::new(e.status(), "invalid_body", e.body_text())
+    }
+}
+
+impl From<BytesRejection> for ExumRejection {
+    fn from(e: BytesRejection) -> Self {
+        Self::new(e.status(), "invalid_body", e.body_text())
+    }
+}
+
+impl From<StringRejection> for ExumRejection {
+    fn from(e: StringRejection) -> Self {
+        Self::new(e.status(), "invalid_body", e.body_text())
+    }
+}
+
+pub struct RejectionHandlerDef {
+    pub handler: fn(ExumRejection) -> Response,
+}
+
+inventory::collect!(RejectionHandlerDef);
+
+static REJECTION_HANDLER: OnceLock<fn(ExumRejection) -> Response> = OnceLock::new();
+
+pub fn rejection_handler() -> fn(ExumRejection) -> Response {
+    *REJECTION_HANDLER.get_or_init(|| {
+        let mut handlers = inventory::iter::<RejectionHandlerDef>.into_iter();
+        match handlers.next() {
+            Some(def) => {
+                if handlers.next().is_some() {
+                    tracing::warn!("Multiple #[rejection_handler] functions registered, only one is used");
+                }
+                def.handler
+            }
+            None => ExumRejection::into_default_response,
+        }
+    })
+}
+
+/// Names the handler parameter an extractor belongs to; implemented by macro-generated marker types.
+pub trait ParamName {
+    /// Empty for extractors covering several parameters, such as a query with several `#[q]` fields.
+    const NAME: &'static str;
+
+    /// Builds the response for a rejected parameter, overridden for controllers declaring `error = T`.
+    fn respond(rejection: ExumRejection) -> Response {
+        rejection.into_response()
+    }
+}
+
+/// Converts a rejection into the error type `E`, keeping the extra headers it carries.
+pub fn respond_with<E>(mut rejection: ExumRejection) -> Response
+where
+    E: From<ExumRejection> + IntoResponse,
+{
+    let headers = rejection.headers.take();
+    let mut res = E::from(rejection).into_response();
+    if let Some(headers) = headers {
+        res.headers_mut().extend(*headers);
+    }
+    res
+}
+
+/// Wraps a macro-generated extractor so its rejection becomes an [`ExumRejection`]
+/// carrying the parameter name and request id, answered through [`ParamName::respond`].
+pub struct Param<E, N>(pub E, pub PhantomData<N>);
+
+impl<S, E, N> FromRequestParts<S> for Param<E, N>
+where
+    S: Send + Sync,
+    E: FromRequestParts<S>,
+    E::Rejection: Into<ExumRejection>,
+    N: ParamName,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match E::from_request_parts(parts, state).await {
+            Ok(value) => Ok(Self(value, PhantomData)),
+            Err(e) => Err(N::respond(e.into().or_param(N::NAME).with_request_id(&parts.headers))),
+        }
+    }
+}
+
+impl<S, E, N> FromRequest<S> for Param<E, N>
+where
+    S: Send + Sync,
+    E: FromRequest<S>,
+    E::Rejection: Into<ExumRejection>,
+    N: ParamName,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let request_id = request_id(req.headers());
+        match E::from_request(req, state).await {
+            Ok(value) => Ok(Self(value, PhantomData)),
+            Err(e) => {
+                let mut r = e.into().or_param(N::NAME);
+                r.request_id = r.request_id.or(request_id);
+                Err(N::respond(r))
+            }
+        }
+    }
+}
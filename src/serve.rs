@@ -1,28 +1,115 @@
-use std::{net::SocketAddr};
+use std::{any::TypeId, io, net::SocketAddr};
 #[cfg(feature = "deref_app")]
 use std::ops::{Deref, DerefMut};
 
 
 use axum::{Router};
+use tokio::task::JoinSet;
 
 #[derive(Debug)]
 pub struct Application {
     pub app: Router,
     pub config: ApplicationConfig,
+    /// Routers served on their own address, added by [`Application::mount_group`].
+    pub listeners: Vec<(SocketAddr, Router)>,
 }
 impl Application {
     pub fn build(config: ApplicationConfig) -> Self {
         Self {
-            app: collect_router(),
+            app: collect_router_with(&ControllerFilter::load()),
             config,
+            listeners: Vec::new(),
         }
     }
 
+    /// Builds an application serving only the routes picked by `selection`.
+    pub fn from_routes(config: ApplicationConfig, selection: &RouteSelection) -> Self {
+        Self {
+            app: collect_router_from(selection),
+            config,
+            listeners: Vec::new(),
+        }
+    }
+
+    /// Mounts the routes and controllers declared with `group = ".."`.
+    ///
+    /// ```ignore
+    /// app.mount_group("admin", "/admin"); // nested into the main router
+    /// app.mount_group("admin", 9001);     // own listener on the configured address
+    /// ```
+    pub fn mount_group(&mut self, group: &str, mount: impl Into<GroupMount>) -> &mut Self {
+        let router = collect_router_from(&RouteSelection::new().group(group));
+        match mount.into() {
+            GroupMount::Nest(prefix) => self.app = self.app.clone().merge(controller::mount(&prefix, router)),
+            GroupMount::Port(port) => self.listeners.push((SocketAddr::from((self.config.addr, port)), router)),
+            GroupMount::Listen(addr) => self.listeners.push((addr, router)),
+        }
+        self
+    }
+
+    /// Runs [`try_run`](Self::try_run), exiting the process when a server cannot start or stops.
     pub async fn run(&self) {
-        let addr = SocketAddr::from((self.config.addr, self.config.port));
-        let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-        println!("Server listening on http://{}", addr);
-        axum::serve(listener, self.app.clone()).await.unwrap();
+        if let Err(e) = self.try_run().await {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+
+    /// Serves the main router and every group listener until one of them stops.
+    ///
+    /// Every address is bound before anything is served, so a port conflict is reported
+    /// without starting the other servers.
+    pub async fn try_run(&self) -> io::Result<()> {
+        let main = SocketAddr::from((self.config.addr, self.config.port));
+        let mut servers = Vec::with_capacity(self.listeners.len() + 1);
+        for (addr, router) in std::iter::once((main, self.app.clone())).chain(self.listeners.iter().cloned()) {
+            let listener = tokio::net::TcpListener::bind(addr)
+                .await
+                .map_err(|e| io::Error::new(e.kind(), format!("Failed to bind {}: {}", addr, e)))?;
+            servers.push((addr, listener, router));
+        }
+
+        let mut tasks = JoinSet::new();
+        for (addr, listener, router) in servers {
+            println!("Server listening on http://{}", addr);
+            tasks.spawn(async move { (addr, axum::serve(listener, router).await) });
+        }
+        // the servers only return on failure; dropping the set stops the others
+        match tasks.join_next().await {
+            Some(Ok((addr, Err(e)))) => Err(io::Error::new(e.kind(), format!("Server on {} failed: {}", addr, e))),
+            Some(Ok((addr, Ok(())))) => Err(io::Error::other(format!("Server on {} stopped", addr))),
+            Some(Err(e)) => Err(io::Error::other(format!("Server task failed: {}", e))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Where [`Application::mount_group`] puts a group.
+#[derive(Debug, Clone)]
+pub enum GroupMount {
+    /// Nested under a prefix of the main router, merged when empty.
+    Nest(String),
+    /// Served on another port of the configured address.
+    Port(u16),
+    /// Served on its own address.
+    Listen(SocketAddr),
+}
+
+impl From<&str> for GroupMount {
+    fn from(prefix: &str) -> Self {
+        GroupMount::Nest(prefix.to_string())
+    }
+}
+
+impl From<u16> for GroupMount {
+    fn from(port: u16) -> Self {
+        GroupMount::Port(port)
+    }
+}
+
+impl From<SocketAddr> for GroupMount {
+    fn from(addr: SocketAddr) -> Self {
+        GroupMount::Listen(addr)
     }
 }
 #[cfg(feature = "deref_app")]
@@ -43,21 +130,219 @@ impl DerefMut for Application {
 
 pub struct RouteDef {
     pub router: fn(Router) -> Router,
+    pub handler: &'static str,
+    /// `module_path!()` of the route function.
+    pub module_path: &'static str,
+    /// `group = ".."` of the route, see [`Application::mount_group`].
+    pub group: Option<&'static str>,
+    /// Environments the route is registered in, see [`profile_active`](crate::profile_active).
+    pub profile: Option<&'static str>,
+}
+
+impl RouteDef {
+    fn active(&self) -> bool {
+        self.profile.is_none_or(crate::profile_active)
+    }
 }
 
 inventory::collect!(RouteDef);
 
+/// Describes a route registered by the route macros or a `#[controller]`.
+#[derive(Debug)]
+pub struct RouteMeta {
+    pub method: &'static str,
+    pub path: &'static str,
+    pub handler: &'static str,
+    /// `module_path!()` of the handler.
+    pub module_path: &'static str,
+    /// Guards declared on the route and its controller, controller guards first; see
+    /// [`all_guards`](Self::all_guards) for the ones inherited from parent controllers.
+    pub guards: &'static [&'static str],
+    pub tags: &'static [&'static str],
+    /// API version declared with `version = ".."`.
+    pub version: Option<&'static str>,
+    pub group: Option<&'static str>,
+    /// Environments the route is registered in, see [`profile_active`](crate::profile_active).
+    pub profile: Option<&'static str>,
+    /// The controller declaring the route.
+    pub controller: Option<fn() -> TypeId>,
+}
+
+impl RouteMeta {
+    /// The path including the prefixes of parent controllers.
+    pub fn full_path(&self) -> String {
+        match self.controller {
+            Some(type_id) => format!("{}{}", controller::parent_prefix(type_id()), self.path),
+            None => self.path.to_string(),
+        }
+    }
+
+    /// Every guard protecting the route in the order they run: those of parent controllers,
+    /// outermost first, then [`guards`](Self::guards).
+    pub fn all_guards(&self) -> Vec<&'static str> {
+        let mut guards = match self.controller {
+            Some(type_id) => controller::parent_guards(type_id()),
+            None => Vec::new(),
+        };
+        guards.extend(self.guards);
+        guards
+    }
+}
+
+inventory::collect!(RouteMeta);
+
+pub fn route_metas() -> impl Iterator<Item = &'static RouteMeta> {
+    inventory::iter::<RouteMeta>.into_iter()
+}
+
+/// Collects every route and controller linked into the binary.
+///
+/// Controllers from library crates are only found when the crate is linked, e.g. through
+/// `use users_api as _;` in the binary.
 pub fn collect_router() -> Router {
+    collect_router_with(&ControllerFilter::default())
+}
+
+/// Like [`collect_router`], mounting only the controllers allowed by `filter`.
+///
+/// Routes and controllers declared with `group = ".."` are left out, see [`Application::mount_group`].
+pub fn collect_router_with(filter: &ControllerFilter) -> Router {
+    collect(
+        |_, _, group| group.is_none(),
+        |controller| controller.group.is_none() && filter.allows(controller),
+    )
+}
+
+/// Collects only the routes and controllers picked by `selection`.
+///
+/// Panics when a handler name of the selection matches route functions of several modules.
+pub fn collect_router_from(selection: &RouteSelection) -> Router {
+    selection.check_handlers();
+    collect(
+        |module_path, handler, group| selection.has_handler(module_path, handler) || selection.has_group(group),
+        |controller| selection.controllers.contains(&(controller.type_id)()) || selection.has_group(controller.group),
+    )
+}
+
+fn collect(
+    routes: impl Fn(&str, &str, Option<&str>) -> bool,
+    controllers: impl Fn(&ControllerDef) -> bool,
+) -> Router {
+    let metas: Vec<&RouteMeta> = route_metas()
+        .filter(|meta| meta.profile.is_none_or(crate::profile_active))
+        .filter(|meta| match meta.controller {
+            None => routes(meta.module_path, meta.handler, meta.group),
+            Some(type_id) => controller::mounted_root(type_id()).is_some_and(&controllers),
+        })
+        .collect();
+    versioning::check_overlaps(versioning::config(), &metas);
     let mut router = Router::new();
     for route in inventory::iter::<RouteDef> {
-        router = (route.router)(router);
+        if route.active() && routes(route.module_path, route.handler, route.group) {
+            router = (route.router)(router);
+        }
     }
+    router = versioning::route_versions(
+        router,
+        inventory::iter::<VersionedRoute>
+            .into_iter()
+            .filter(|route| routes(route.module_path, route.handler, route.group)),
+    );
     for controller in inventory::iter::<ControllerDef> {
-        router = router.merge((controller.router)());
+        if !controller::is_nested(controller) && controllers(controller) {
+            router = router.merge((controller.router)());
+        }
     }
     router
 }
 
+/// An explicit set of routes for [`collect_router_from`] and [`Application::from_routes`].
+///
+/// ```ignore
+/// let selection = RouteSelection::new()
+///     .handler("health")
+///     .handler("admin::users::get_user")
+///     .controller::<UserController>()
+///     .group("admin");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RouteSelection {
+    handlers: Vec<String>,
+    controllers: Vec<TypeId>,
+    groups: Vec<String>,
+}
+
+impl RouteSelection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A route function by name, whatever its group.
+    ///
+    /// The name can be qualified by the end of its module path, `users::get_user`, and must be
+    /// when route functions of several modules share it.
+    pub fn handler(mut self, name: &str) -> Self {
+        self.handlers.push(name.to_string());
+        self
+    }
+
+    /// A controller together with its children, whatever its group.
+    pub fn controller<T: 'static>(mut self) -> Self {
+        self.controllers.push(TypeId::of::<T>());
+        self
+    }
+
+    /// Every route and controller declared with `group = name`.
+    pub fn group(mut self, name: &str) -> Self {
+        self.groups.push(name.to_string());
+        self
+    }
+
+    fn has_handler(&self, module_path: &str, handler: &str) -> bool {
+        self.handlers.iter().any(|name| handler_matches(name, module_path, handler))
+    }
+
+    /// Panics naming the modules when a handler name matches route functions of several of them.
+    fn check_handlers(&self) {
+        for name in &self.handlers {
+            let mut modules: Vec<&str> = route_metas()
+                .filter(|meta| meta.controller.is_none() && handler_matches(name, meta.module_path, meta.handler))
+                .map(|meta| meta.module_path)
+                .collect();
+            modules.sort_unstable();
+            modules.dedup();
+            if modules.len() > 1 {
+                let handler = name.rsplit("::").next().unwrap_or(name);
+                panic!(
+                    "Route selection `{}` matches route functions in several modules: {}. Qualify it with its module, e.g. `{}::{}`",
+                    name,
+                    modules.join(", "),
+                    modules[0],
+                    handler
+                );
+            }
+        }
+    }
+
+    fn has_group(&self, group: Option<&str>) -> bool {
+        group.is_some_and(|group| self.groups.iter().any(|g| g == group))
+    }
+}
+
+/// Whether `name`, a bare function name or one qualified by the end of its module path,
+/// names `handler` declared in `module_path`.
+fn handler_matches(name: &str, module_path: &str, handler: &str) -> bool {
+    match name.rsplit_once("::") {
+        None => name == handler,
+        Some((module, name)) => {
+            name == handler
+                && module_path
+                    .strip_suffix(module)
+                    .is_some_and(|rest| rest.is_empty() || rest.ends_with("::"))
+        }
+    }
+}
+
 pub use exum_macros::*;
 
-use crate::{config::ApplicationConfig, controller::ControllerDef};
\ No newline at end of file
+use crate::{config::ApplicationConfig, controller::{self, ControllerDef, ControllerFilter}, versioning::{self, VersionedRoute}};
\ No newline at end of file
@@ -0,0 +1,133 @@
+//! Reverse routing: building the URL of a route from its handler, see [`url_for!`](crate::url_for).
+//!
+//! Every route macro emits a hidden `__exum_url_{handler}` type implementing [`RouteUrl`]
+//! (`__exum_url_{Controller}_{method}` for controller routes). `url_for!` checks the given
+//! parameters against it at compile time and fills in the path with [`UrlBuilder`].
+
+use std::{any::TypeId, fmt::Display};
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+use crate::{controller, versioning};
+
+/// Path parameter values, escaped like the literal segments of a route path.
+const VALUE_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC.remove(b'_').remove(b'-');
+/// Wildcard values keep their `/`.
+const WILDCARD_ENCODE_SET: &AsciiSet = &VALUE_ENCODE_SET.remove(b'/');
+
+/// Where a `url_for!` argument goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamKind {
+    Path,
+    Query,
+}
+
+/// The path of a route, implemented by the types the route macros generate.
+pub trait RouteUrl {
+    /// The path registered with axum, including the controller prefix.
+    const PATH: &'static str;
+    /// API version declared with `version = ".."`.
+    const VERSION: Option<&'static str>;
+
+    /// The controller declaring the route; the prefixes of its parents are added to the URL.
+    fn controller() -> Option<TypeId> {
+        None
+    }
+}
+
+/// `names.contains(&name)`, usable in constants.
+pub const fn contains(names: &[&str], name: &str) -> bool {
+    let mut i = 0;
+    while i < names.len() {
+        if eq(names[i].as_bytes(), name.as_bytes()) {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
+
+const fn eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Fills in the path and query parameters of a route.
+#[derive(Debug)]
+pub struct UrlBuilder {
+    path: &'static str,
+    version: Option<&'static str>,
+    controller: Option<TypeId>,
+    params: Vec<(&'static str, String)>,
+    query: Vec<(String, String)>,
+}
+
+impl UrlBuilder {
+    pub fn new<R: RouteUrl>() -> Self {
+        Self {
+            path: R::PATH,
+            version: R::VERSION,
+            controller: R::controller(),
+            params: Vec::new(),
+            query: Vec::new(),
+        }
+    }
+
+    pub fn param(mut self, kind: ParamKind, name: &'static str, value: &dyn Display) -> Self {
+        match kind {
+            ParamKind::Path => self.params.push((name, value.to_string())),
+            ParamKind::Query => self.query.push((name.to_string(), value.to_string())),
+        }
+        self
+    }
+
+    pub fn build(mut self) -> String {
+        let mut path = String::new();
+        for seg in self.path.split('/').filter(|s| !s.is_empty()) {
+            path.push('/');
+            let Some(inner) = seg.strip_prefix('{').and_then(|s| s.strip_suffix('}')) else {
+                path.push_str(seg);
+                continue;
+            };
+            let name = inner.trim_start_matches('*');
+            let set = if name.len() == inner.len() { VALUE_ENCODE_SET } else { WILDCARD_ENCODE_SET };
+            match self.params.iter().find(|(n, _)| *n == name) {
+                Some((_, value)) => path.extend(utf8_percent_encode(value, set)),
+                None => path.push_str(seg),
+            }
+        }
+        if path.is_empty() {
+            path.push('/');
+        }
+
+        if let Some(version) = self.version {
+            let config = versioning::config();
+            match config.strategy {
+                versioning::VersionStrategy::Path => path = config.versioned_path(version, &path),
+                versioning::VersionStrategy::Query => self.query.push((config.query_param.clone(), version.to_string())),
+                versioning::VersionStrategy::Header => {}
+            }
+        }
+        let mut url = match self.controller {
+            Some(type_id) => format!("{}{}", controller::parent_prefix(type_id), path),
+            None => path,
+        };
+
+        for (i, (name, value)) in self.query.iter().enumerate() {
+            url.push(if i == 0 { '?' } else { '&' });
+            url.extend(utf8_percent_encode(name, VALUE_ENCODE_SET));
+            url.push('=');
+            url.extend(utf8_percent_encode(value, VALUE_ENCODE_SET));
+        }
+        url
+    }
+}
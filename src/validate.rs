@@ -0,0 +1,289 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+};
+
+use axum::{
+    extract::{FromRequest, FromRequestParts, Request},
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    Form, Json,
+};
+pub use regex::Regex;
+use serde::de::DeserializeOwned;
+
+use crate::rejection::{ExumRejection, FieldError};
+
+/// Declarative field validation, usually implemented with `#[derive(Validate)]`.
+pub trait Validate {
+    fn validate_into(&self, path: &str, errors: &mut ValidationErrors);
+
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        self.validate_into("", &mut errors);
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+}
+
+impl<T: Validate> Validate for Option<T> {
+    fn validate_into(&self, path: &str, errors: &mut ValidationErrors) {
+        if let Some(v) = self {
+            v.validate_into(path, errors);
+        }
+    }
+}
+
+impl<T: Validate> Validate for Vec<T> {
+    fn validate_into(&self, path: &str, errors: &mut ValidationErrors) {
+        for (i, v) in self.iter().enumerate() {
+            v.validate_into(&format!("{path}[{i}]"), errors);
+        }
+    }
+}
+
+impl<T: Validate> Validate for Box<T> {
+    fn validate_into(&self, path: &str, errors: &mut ValidationErrors) {
+        (**self).validate_into(path, errors);
+    }
+}
+
+impl<T: Validate> Validate for HashMap<String, T> {
+    fn validate_into(&self, path: &str, errors: &mut ValidationErrors) {
+        for (k, v) in self {
+            v.validate_into(&join_path(path, k), errors);
+        }
+    }
+}
+
+impl<T: Validate> Validate for BTreeMap<String, T> {
+    fn validate_into(&self, path: &str, errors: &mut ValidationErrors) {
+        for (k, v) in self {
+            v.validate_into(&join_path(path, k), errors);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ValidationErrors {
+    pub errors: Vec<FieldError>,
+}
+
+impl ValidationErrors {
+    pub fn add(&mut self, field: &str, code: &'static str, message: impl Into<String>) {
+        self.errors.push(FieldError {
+            field: field.to_string(),
+            code,
+            message: message.into(),
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+impl From<ValidationErrors> for ExumRejection {
+    fn from(e: ValidationErrors) -> Self {
+        ExumRejection::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "validation_failed",
+            "Request validation failed",
+        )
+        .with_fields(e.errors)
+    }
+}
+
+impl IntoResponse for ValidationErrors {
+    fn into_response(self) -> Response {
+        ExumRejection::from(self).into_response()
+    }
+}
+
+pub fn join_path(prefix: &str, field: &str) -> String {
+    if prefix.is_empty() {
+        field.to_string()
+    } else {
+        format!("{prefix}.{field}")
+    }
+}
+
+pub trait HasLength {
+    fn length(&self) -> usize;
+}
+
+impl HasLength for str {
+    fn length(&self) -> usize {
+        self.chars().count()
+    }
+}
+
+impl HasLength for String {
+    fn length(&self) -> usize {
+        self.chars().count()
+    }
+}
+
+impl<T> HasLength for Vec<T> {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T> HasLength for [T] {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<K, V> HasLength for HashMap<K, V> {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<K, V> HasLength for BTreeMap<K, V> {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+pub fn check_length<T: HasLength + ?Sized>(
+    value: &T,
+    min: Option<usize>,
+    max: Option<usize>,
+    path: &str,
+    errors: &mut ValidationErrors,
+) {
+    let len = value.length();
+    let ok = min.is_none_or(|min| len >= min) && max.is_none_or(|max| len <= max);
+    if !ok {
+        let message = match (min, max) {
+            (Some(min), Some(max)) if min == max => format!("length must be exactly {min}"),
+            (Some(min), Some(max)) => format!("length must be between {min} and {max}"),
+            (Some(min), None) => format!("length must be at least {min}"),
+            (None, Some(max)) => format!("length must be at most {max}"),
+            (None, None) => unreachable!(),
+        };
+        errors.add(path, "length", message);
+    }
+}
+
+pub fn check_range<T: PartialOrd + Display>(
+    value: &T,
+    min: Option<T>,
+    max: Option<T>,
+    path: &str,
+    errors: &mut ValidationErrors,
+) {
+    let ok = min.as_ref().is_none_or(|min| value >= min) && max.as_ref().is_none_or(|max| value <= max);
+    if !ok {
+        let message = match (min, max) {
+            (Some(min), Some(max)) => format!("must be between {min} and {max}"),
+            (Some(min), None) => format!("must be at least {min}"),
+            (None, Some(max)) => format!("must be at most {max}"),
+            (None, None) => unreachable!(),
+        };
+        errors.add(path, "range", message);
+    }
+}
+
+pub fn check_regex<T: AsRef<str> + ?Sized>(value: &T, regex: &Regex, path: &str, errors: &mut ValidationErrors) {
+    if !regex.is_match(value.as_ref()) {
+        errors.add(path, "regex", format!("must match pattern `{}`", regex.as_str()));
+    }
+}
+
+pub fn check_email<T: AsRef<str> + ?Sized>(value: &T, path: &str, errors: &mut ValidationErrors) {
+    if !is_email(value.as_ref()) {
+        errors.add(path, "email", "must be a valid email address");
+    }
+}
+
+pub fn check_custom<T: ?Sized, E: Display>(
+    value: &T,
+    f: impl FnOnce(&T) -> Result<(), E>,
+    path: &str,
+    errors: &mut ValidationErrors,
+) {
+    if let Err(e) = f(value) {
+        errors.add(path, "custom", e.to_string());
+    }
+}
+
+fn is_email(s: &str) -> bool {
+    let Some((local, domain)) = s.rsplit_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && local.len() <= 64
+        && !s.chars().any(|c| c.is_whitespace() || c.is_control())
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && domain.contains('.')
+        && !domain.contains("..")
+        && domain
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '.')
+}
+
+/// JSON body extractor used by `#[b(validate)]`.
+pub struct ValidJson<T>(pub T);
+
+impl<S, T> FromRequest<S> for ValidJson<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned + Validate,
+{
+    type Rejection = ExumRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state).await?;
+        value.validate()?;
+        Ok(Self(value))
+    }
+}
+
+/// Form body extractor used by `#[b(form, validate)]`.
+pub struct ValidForm<T>(pub T);
+
+impl<S, T> FromRequest<S> for ValidForm<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned + Validate,
+{
+    type Rejection = ExumRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Form(value) = Form::<T>::from_request(req, state).await?;
+        value.validate()?;
+        Ok(Self(value))
+    }
+}
+
+/// Query extractor used when any `#[q]` parameter carries `validate`.
+pub struct ValidQuery<T>(pub T);
+
+impl<S, T> FromRequestParts<S> for ValidQuery<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned + Validate,
+{
+    type Rejection = ExumRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let axum::extract::Query(value) = axum::extract::Query::<T>::from_request_parts(parts, state).await?;
+        value.validate().map_err(query_rejection)?;
+        Ok(Self(value))
+    }
+}
+
+/// Names the query parameter that failed, when every failing field belongs to the same one.
+fn query_rejection(errors: ValidationErrors) -> ExumRejection {
+    let param = |e: &FieldError| e.field.split(['.', '[']).next().unwrap_or_default().to_string();
+    let params: Vec<String> = errors.errors.iter().map(param).collect();
+    let rejection = ExumRejection::from(errors);
+    match params.split_first() {
+        Some((first, rest)) if rest.iter().all(|p| p == first) => rejection.with_param(first.clone()),
+        _ => rejection,
+    }
+}
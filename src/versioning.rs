@@ -0,0 +1,414 @@
+use std::{any::TypeId, cmp::Ordering, collections::HashMap, convert::Infallible, sync::{Arc, OnceLock}};
+
+use axum::{
+    extract::Request,
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    routing::MethodRouter,
+    Router,
+};
+use percent_encoding::percent_decode_str;
+use serde::Deserialize;
+use tower::ServiceExt;
+
+use crate::{
+    config::{get_config, get_value},
+    controller,
+    rejection::ExumRejection,
+    RouteMeta,
+};
+
+/// How the requested API version is read from a request.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VersionStrategy {
+    /// `/v2/users`, see [`VersioningConfig::path_prefix`].
+    #[default]
+    Path,
+    /// `Accept: application/vnd.{vendor}.v2+json`.
+    Header,
+    /// `/users?version=2`, see [`VersioningConfig::query_param`].
+    Query,
+}
+
+/// Headers sent with every response of a deprecated version.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct DeprecationConfig {
+    /// When the version was deprecated, required: `@1735689599` (seconds since the Unix epoch)
+    /// or an HTTP date. Sent as `Deprecation: @<seconds>`, the format of RFC 9745.
+    pub since: Option<String>,
+    /// Value of the `Sunset` header, an HTTP date such as `Wed, 31 Dec 2025 23:59:59 GMT`.
+    pub sunset: Option<String>,
+    /// Documentation link, sent as `Link: <..>; rel="deprecation"`.
+    pub link: Option<String>,
+}
+
+/// The `[versioning]` config table.
+///
+/// ```toml
+/// [versioning]
+/// strategy = "header"
+/// default = "1"
+/// vendor = "myapp"
+///
+/// [versioning.deprecated.1]
+/// since = "@1735689599"
+/// sunset = "Wed, 31 Dec 2025 23:59:59 GMT"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct VersioningConfig {
+    pub strategy: VersionStrategy,
+    /// Version served when the request does not ask for one; the newest version of the route when unset.
+    pub default: Option<String>,
+    /// Prefix template for the path strategy.
+    pub path_prefix: String,
+    /// Vendor matched by the header strategy, any vendor when unset.
+    pub vendor: Option<String>,
+    pub query_param: String,
+    pub deprecated: HashMap<String, DeprecationConfig>,
+}
+
+impl Default for VersioningConfig {
+    fn default() -> Self {
+        Self {
+            strategy: VersionStrategy::Path,
+            default: None,
+            path_prefix: "/v{version}".to_string(),
+            vendor: None,
+            query_param: "version".to_string(),
+            deprecated: HashMap::new(),
+        }
+    }
+}
+
+impl VersioningConfig {
+    pub fn load() -> Self {
+        get_value(get_config(), "versioning").unwrap_or_default()
+    }
+
+    /// The version asked for by the request, if any.
+    pub fn requested_version(&self, req: &Request) -> Option<String> {
+        match self.strategy {
+            VersionStrategy::Path => None,
+            VersionStrategy::Header => req
+                .headers()
+                .get_all(header::ACCEPT)
+                .iter()
+                .filter_map(|v| v.to_str().ok())
+                .flat_map(|v| v.split(','))
+                .find_map(|range| self.vendor_version(range)),
+            VersionStrategy::Query => req.uri().query()?.split('&').find_map(|pair| {
+                let (key, value) = pair.split_once('=')?;
+                (key == self.query_param).then(|| percent_decode_str(value).decode_utf8_lossy().into_owned())
+            }),
+        }
+    }
+
+    /// Parses `application/vnd.{vendor}.v{version}+json`.
+    fn vendor_version(&self, range: &str) -> Option<String> {
+        let essence = range.split(';').next()?.trim().to_ascii_lowercase();
+        let subtype = essence.strip_prefix("application/vnd.")?;
+        let subtype = subtype.split('+').next()?;
+        let (vendor, version) = subtype.rsplit_once(".v")?;
+        if version.is_empty() || self.vendor.as_ref().is_some_and(|v| !v.eq_ignore_ascii_case(vendor)) {
+            return None;
+        }
+        Some(version.to_string())
+    }
+
+    pub(crate) fn versioned_path(&self, version: &str, path: &str) -> String {
+        let prefix = self.path_prefix.replace("{version}", version);
+        let prefix = prefix.trim_end_matches('/');
+        if path == "/" {
+            prefix.to_string()
+        } else {
+            format!("{}{}", prefix, path)
+        }
+    }
+
+    /// Panics when `since` is missing or invalid, so a misconfigured version fails at startup.
+    fn deprecation_headers(&self, version: &str) -> Option<HeaderMap> {
+        let config = self.deprecated.get(version)?;
+        let mut headers = HeaderMap::new();
+        let since = match config.since.as_deref().map(deprecation_date) {
+            Some(Some(since)) => since,
+            Some(None) => panic!(
+                "Invalid `since` for deprecated API version {}: {:?}. Use `@<seconds since the Unix epoch>` or an HTTP date such as `Wed, 31 Dec 2025 23:59:59 GMT`.",
+                version,
+                config.since.as_deref().unwrap_or_default()
+            ),
+            None => panic!(
+                "Deprecated API version {} needs `since`, the date it was deprecated: `@<seconds since the Unix epoch>` or an HTTP date.",
+                version
+            ),
+        };
+        let mut insert = |name: HeaderName, value: &str| match HeaderValue::from_str(value) {
+            Ok(value) => {
+                headers.append(name, value);
+            }
+            Err(_) => tracing::warn!("Invalid {} header value for API version {}: {}", name, version, value),
+        };
+        insert(HeaderName::from_static("deprecation"), &since);
+        if let Some(sunset) = &config.sunset {
+            insert(HeaderName::from_static("sunset"), sunset);
+        }
+        if let Some(link) = &config.link {
+            insert(header::LINK, &format!("<{}>; rel=\"deprecation\"", link));
+        }
+        Some(headers)
+    }
+}
+
+/// `since` as the `@<seconds>` value of the `Deprecation` header.
+fn deprecation_date(since: &str) -> Option<String> {
+    let since = since.trim();
+    let seconds = match since.strip_prefix('@') {
+        Some(seconds) => seconds.parse::<i64>().ok()?,
+        None => httpdate::parse_http_date(since)
+            .ok()?
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()?
+            .as_secs() as i64,
+    };
+    Some(format!("@{}", seconds))
+}
+
+static CONFIG: OnceLock<VersioningConfig> = OnceLock::new();
+
+/// The versioning config loaded from the `[versioning]` table.
+pub fn config() -> &'static VersioningConfig {
+    CONFIG.get_or_init(VersioningConfig::load)
+}
+
+/// A route declared with `version = ".."`.
+pub struct VersionedRoute {
+    pub path: &'static str,
+    pub version: &'static str,
+    pub route: fn() -> MethodRouter,
+    pub handler: &'static str,
+    /// `module_path!()` of the route function.
+    pub module_path: &'static str,
+    pub group: Option<&'static str>,
+    /// Environments the route is registered in, see [`profile_active`](crate::profile_active).
+    pub profile: Option<&'static str>,
+}
+
+inventory::collect!(VersionedRoute);
+
+/// Numeric versions compare as numbers, anything else as text.
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    match (a.parse::<u64>(), b.parse::<u64>()) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+fn unsupported_version(version: &str) -> Response {
+    ExumRejection::new(
+        StatusCode::NOT_FOUND,
+        "unsupported_version",
+        format!("API version `{}` is not available for this route", version),
+    )
+    .into_response()
+}
+
+/// A path and method taken by one or more routes, `None` taking every method of the path.
+struct Claim {
+    path: String,
+    method: Option<&'static str>,
+    /// The routes behind the claim, for the error message.
+    label: String,
+    /// Why the claim covers `path`, when it is not the path the routes declared.
+    reason: Option<&'static str>,
+}
+
+/// `/users/{id}` and `/users/{name}` are the same route to axum.
+fn route_shape(path: &str) -> String {
+    path.split('/')
+        .map(|segment| match segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+            Some(name) if name.starts_with('*') => "{*}",
+            Some(_) => "{}",
+            None => segment,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Panics naming both handlers when two of `metas` would take the same path and method once
+/// versions are laid out by `config`: an unversioned route on a path served by versions
+/// through the header or query strategy, or the same path declared in several places. axum
+/// would only report an overlapping route, without saying which handlers collide.
+pub(crate) fn check_overlaps(config: &VersioningConfig, metas: &[&RouteMeta]) {
+    let mut claims = Vec::new();
+    // versioned routes by declaring controller and path
+    let mut versioned: Vec<(Option<TypeId>, &str, Vec<&RouteMeta>)> = Vec::new();
+    for meta in metas {
+        if meta.version.is_none() {
+            claims.push(Claim {
+                path: meta.full_path(),
+                method: Some(meta.method),
+                label: format!("`{}`", meta.handler),
+                reason: None,
+            });
+            continue;
+        }
+        let controller = meta.controller.map(|type_id| type_id());
+        match versioned.iter_mut().find(|(c, path, _)| *c == controller && *path == meta.path) {
+            Some((_, _, routes)) => routes.push(meta),
+            None => versioned.push((controller, meta.path, vec![meta])),
+        }
+    }
+    for (controller, path, routes) in versioned {
+        let prefix = controller.map(controller::parent_prefix).unwrap_or_default();
+        let label = |meta: &RouteMeta| format!("`{}` (version {})", meta.handler, meta.version.unwrap_or_default());
+        match config.strategy {
+            VersionStrategy::Path => {
+                let newest = routes.iter().filter_map(|m| m.version).max_by(|a, b| compare_versions(a, b));
+                let default = config
+                    .default
+                    .as_deref()
+                    .filter(|d| routes.iter().any(|m| m.version == Some(*d)))
+                    .or(newest);
+                for meta in &routes {
+                    let version = meta.version.unwrap_or_default();
+                    claims.push(Claim {
+                        path: format!("{}{}", prefix, config.versioned_path(version, path)),
+                        method: Some(meta.method),
+                        label: label(meta),
+                        reason: None,
+                    });
+                    if Some(version) == default {
+                        claims.push(Claim {
+                            path: meta.full_path(),
+                            method: Some(meta.method),
+                            label: label(meta),
+                            reason: Some("the unprefixed path serves the default version"),
+                        });
+                    }
+                }
+            }
+            VersionStrategy::Header | VersionStrategy::Query => {
+                let mut labels: Vec<String> = routes.iter().map(|meta| label(meta)).collect();
+                labels.dedup();
+                claims.push(Claim {
+                    path: format!("{}{}", prefix, path),
+                    method: None,
+                    label: labels.join(", "),
+                    reason: Some("every version of a path is served by one route taking all of its methods"),
+                });
+            }
+        }
+    }
+
+    for (i, a) in claims.iter().enumerate() {
+        for b in &claims[i + 1..] {
+            let same_method = a.method.is_none() || b.method.is_none() || a.method == b.method;
+            if !same_method || route_shape(&a.path) != route_shape(&b.path) {
+                continue;
+            }
+            let method = a.method.or(b.method).unwrap_or("any method");
+            let message = format!("Routes {} and {} overlap on {} {}", a.label, b.label, method, a.path);
+            match a.reason.or(b.reason) {
+                Some(reason) => panic!(
+                    "{}: with the {:?} versioning strategy, {}. Give the unversioned route a `version`, or move one of them to another path.",
+                    message, config.strategy, reason
+                ),
+                None => panic!("{}. Move one of them to another path or method.", message),
+            }
+        }
+    }
+}
+
+/// Adds versioned routes to `router` according to the configured [`VersionStrategy`].
+///
+/// Routes whose `profile` does not match the current environment are skipped.
+pub fn route_versions<'a>(router: Router, routes: impl IntoIterator<Item = &'a VersionedRoute>) -> Router {
+    route_versions_with(config(), router, routes)
+}
+
+pub fn route_versions_with<'a>(
+    config: &VersioningConfig,
+    mut router: Router,
+    routes: impl IntoIterator<Item = &'a VersionedRoute>,
+) -> Router {
+    let mut by_path: Vec<(&str, Vec<(&str, MethodRouter)>)> = Vec::new();
+    for route in routes {
+        if route.profile.is_some_and(|profile| !crate::profile_active(profile)) {
+            continue;
+        }
+        let index = match by_path.iter().position(|(path, _)| *path == route.path) {
+            Some(index) => index,
+            None => {
+                by_path.push((route.path, Vec::new()));
+                by_path.len() - 1
+            }
+        };
+        let versions = &mut by_path[index].1;
+        match versions.iter_mut().find(|(version, _)| *version == route.version) {
+            Some((_, method_router)) => *method_router = std::mem::take(method_router).merge((route.route)()),
+            None => versions.push((route.version, (route.route)())),
+        }
+    }
+
+    for (path, mut versions) in by_path {
+        versions.sort_by(|a, b| compare_versions(a.0, b.0));
+        let versions: Vec<(&str, MethodRouter)> = versions
+            .into_iter()
+            .map(|(version, method_router)| match config.deprecation_headers(version) {
+                Some(headers) => (
+                    version,
+                    method_router.layer(axum::middleware::map_response(move |mut res: Response| {
+                        let headers = headers.clone();
+                        async move {
+                            res.headers_mut().extend(headers);
+                            res
+                        }
+                    })),
+                ),
+                None => (version, method_router),
+            })
+            .collect();
+        let default = config
+            .default
+            .as_deref()
+            .and_then(|d| versions.iter().position(|(v, _)| *v == d))
+            .unwrap_or(versions.len() - 1);
+
+        match config.strategy {
+            VersionStrategy::Path => {
+                for (version, method_router) in &versions {
+                    router = router.route(&config.versioned_path(version, path), method_router.clone());
+                }
+                // unprefixed requests get the default version
+                router = router.route(path, versions[default].1.clone());
+            }
+            VersionStrategy::Header | VersionStrategy::Query => {
+                let config = config.clone();
+                let routers: Arc<Vec<(String, MethodRouter)>> = Arc::new(
+                    versions
+                        .into_iter()
+                        .map(|(version, method_router)| (version.to_string(), method_router))
+                        .collect(),
+                );
+                let service = tower::service_fn(move |req: Request| {
+                    let routers = routers.clone();
+                    let requested = config.requested_version(&req);
+                    async move {
+                        let router = match &requested {
+                            Some(version) => routers.iter().find(|(v, _)| v == version),
+                            None => routers.get(default),
+                        };
+                        match router {
+                            Some((_, router)) => router.clone().oneshot(req).await,
+                            None => Ok::<_, Infallible>(unsupported_version(requested.as_deref().unwrap_or_default())),
+                        }
+                    }
+                });
+                router = router.route_service(path, service);
+            }
+        }
+    }
+    router
+}
@@ -0,0 +1,17 @@
+use axum::Router;
+use exum::{config::ApplicationConfig, Application, RouteSelection};
+
+#[tokio::test]
+async fn port_conflict_is_an_error() {
+    let taken = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let taken_addr = taken.local_addr().unwrap();
+    let free_port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+
+    let config = ApplicationConfig { addr: [127, 0, 0, 1], port: free_port };
+    let mut app = Application::from_routes(config, &RouteSelection::new());
+    app.listeners.push((taken_addr, Router::new()));
+
+    let error = app.try_run().await.unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::AddrInUse);
+    assert!(error.to_string().starts_with(&format!("Failed to bind {}", taken_addr)), "{}", error);
+}
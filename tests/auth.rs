@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+
+use axum::http::{header, HeaderMap, HeaderValue};
+use exum::auth::{ApiKeyConfig, AuthConfig, AuthError, AuthService, BasicConfig, ConfigVerifier, JwtConfig};
+use jsonwebtoken::{encode, EncodingKey, Header};
+use serde_json::{json, Value};
+
+const SECRET: &str = "jwt-test-secret";
+
+fn config() -> AuthConfig {
+    AuthConfig {
+        jwt: Some(JwtConfig { secret: Some(SECRET.to_string()), ..JwtConfig::default() }),
+        basic: Some(BasicConfig { users: HashMap::from([("admin".to_string(), "s3cret".to_string())]) }),
+        api_key: Some(ApiKeyConfig {
+            keys: HashMap::from([("reporting".to_string(), "report-key".to_string())]),
+            ..ApiKeyConfig::default()
+        }),
+        ..AuthConfig::default()
+    }
+}
+
+fn headers(name: header::HeaderName, value: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(name, HeaderValue::from_str(value).unwrap());
+    headers
+}
+
+fn token(claims: Value, secret: &str) -> String {
+    encode(&Header::default(), &claims, &EncodingKey::from_secret(secret.as_bytes())).unwrap()
+}
+
+fn bearer(token: &str) -> HeaderMap {
+    headers(header::AUTHORIZATION, &format!("Bearer {}", token))
+}
+
+fn exp() -> u64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs() + 3600
+}
+
+#[test]
+fn jwt() {
+    let service = AuthService::from_config(&config());
+    let claims = service.authenticate(&bearer(&token(json!({ "sub": "alice", "exp": exp() }), SECRET))).unwrap();
+    assert_eq!(claims.unwrap()["sub"], "alice");
+
+    let forged = token(json!({ "sub": "alice", "exp": exp() }), "another-secret");
+    assert!(matches!(service.authenticate(&bearer(&forged)), Err(AuthError::InvalidToken(_))));
+    let expired = token(json!({ "sub": "alice", "exp": 1 }), SECRET);
+    assert!(matches!(service.authenticate(&bearer(&expired)), Err(AuthError::InvalidToken(_))));
+}
+
+#[test]
+fn basic() {
+    let service = AuthService::from_config(&config());
+    // admin:s3cret
+    let claims = service.authenticate(&headers(header::AUTHORIZATION, "Basic YWRtaW46czNjcmV0")).unwrap();
+    assert_eq!(claims, Some(json!({ "sub": "admin" })));
+    // admin:wrong
+    let wrong = service.authenticate(&headers(header::AUTHORIZATION, "Basic YWRtaW46d3Jvbmc="));
+    assert!(matches!(wrong, Err(AuthError::InvalidCredentials)));
+}
+
+#[test]
+fn api_key() {
+    let service = AuthService::from_config(&config());
+    let name = header::HeaderName::from_static("x-api-key");
+    let claims = service.authenticate(&headers(name.clone(), "report-key")).unwrap();
+    assert_eq!(claims, Some(json!({ "sub": "reporting" })));
+    // the subject is not a key
+    let subject = service.authenticate(&headers(name, "reporting"));
+    assert!(matches!(subject, Err(AuthError::InvalidCredentials)));
+    assert_eq!(service.authenticate(&HeaderMap::new()).unwrap(), None);
+}
+
+#[test]
+fn unresolved_placeholders_and_empty_secrets_fail() {
+    let error = |config: AuthConfig| ConfigVerifier::try_from_config(&config).err().unwrap();
+
+    let mut jwt = config();
+    jwt.jwt.as_mut().unwrap().secret = Some("${JWT_SECRET}".to_string());
+    assert_eq!(
+        error(jwt),
+        "Invalid [auth.jwt] configuration: `secret` is the unresolved placeholder `${JWT_SECRET}`, set the environment variable `JWT_SECRET`"
+    );
+
+    let mut basic = config();
+    basic.basic.as_mut().unwrap().users.insert("admin".to_string(), "${ADMIN_PASSWORD}".to_string());
+    assert!(error(basic).starts_with("Invalid [auth.basic] configuration: password of `admin` is the unresolved placeholder"));
+
+    let mut api_key = config();
+    api_key.api_key.as_mut().unwrap().keys.insert("reporting".to_string(), "${REPORTING_KEY}".to_string());
+    assert!(error(api_key).starts_with("Invalid [auth.api_key] configuration: key of `reporting` is the unresolved placeholder"));
+
+    let mut empty = config();
+    empty.basic.as_mut().unwrap().users.insert("admin".to_string(), String::new());
+    assert_eq!(error(empty), "Invalid [auth.basic] configuration: password of `admin` is empty");
+
+    assert!(ConfigVerifier::try_from_config(&config()).is_ok());
+}
+
+#[test]
+#[should_panic(expected = "unresolved placeholder `${ADMIN_PASSWORD}`")]
+fn from_config_panics_on_placeholder() {
+    let mut config = config();
+    config.basic.as_mut().unwrap().users.insert("admin".to_string(), "${ADMIN_PASSWORD}".to_string());
+    AuthService::from_config(&config);
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+    response::Response,
+};
+use common::{header_str, json, send, text};
+use exum::{body::Accept, *};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(Serialize, Deserialize)]
+struct User {
+    name: String,
+    age: u32,
+}
+
+#[post("/users")]
+async fn create_user(#[b(auto)] user: User, accept: Accept) -> Response {
+    accept.respond(&user)
+}
+
+fn post(content_type: Option<&str>, accept: Option<&str>, body: &str) -> Request<Body> {
+    let mut req = Request::post("/users");
+    if let Some(content_type) = content_type {
+        req = req.header(header::CONTENT_TYPE, content_type);
+    }
+    if let Some(accept) = accept {
+        req = req.header(header::ACCEPT, accept);
+    }
+    req.body(Body::from(body.to_string())).unwrap()
+}
+
+async fn call(req: Request<Body>) -> Response {
+    send(&collect_router(), req).await
+}
+
+const JSON: &str = r#"{"name":"ann","age":30}"#;
+
+#[tokio::test]
+async fn the_decoder_follows_content_type() {
+    let res = call(post(Some("application/json"), None, JSON)).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(json(res).await, json!({"name": "ann", "age": 30}));
+
+    let res = call(post(Some("application/problem+json; charset=utf-8"), None, JSON)).await;
+    assert_eq!(json(res).await["name"], "ann");
+
+    let res = call(post(Some("application/x-www-form-urlencoded"), None, "name=bob&age=40")).await;
+    assert_eq!(json(res).await, json!({"name": "bob", "age": 40}));
+
+    let res = call(post(Some("text/xml"), None, "<User><name>cy</name><age>50</age></User>")).await;
+    assert_eq!(json(res).await, json!({"name": "cy", "age": 50}));
+}
+
+#[tokio::test]
+async fn unsupported_content_types() {
+    let res = call(post(Some("text/plain"), None, JSON)).await;
+    assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(
+        json(res).await,
+        json!({
+            "status": 415,
+            "code": "unsupported_media_type",
+            "message": "Unsupported content type `text/plain`",
+            "param": "user",
+        })
+    );
+
+    let res = call(post(None, None, JSON)).await;
+    assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(json(res).await["message"], "Missing `Content-Type` header");
+}
+
+#[tokio::test]
+async fn undecodable_bodies() {
+    let res = call(post(Some("application/json"), None, "{")).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(json(res).await["code"], "invalid_body");
+
+    let res = call(post(Some("application/xml"), None, "<User><name>x</name></User>")).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(json(res).await["code"], "invalid_body");
+}
+
+#[tokio::test]
+async fn the_response_follows_accept() {
+    let res = call(post(Some("application/json"), Some("*/*"), JSON)).await;
+    assert_eq!(header_str(&res, header::CONTENT_TYPE), "application/json");
+
+    let res = call(post(Some("application/json"), Some("text/html, application/xml;q=0.5"), JSON)).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(header_str(&res, header::CONTENT_TYPE), "application/xml");
+    assert_eq!(text(res).await, "<User><name>ann</name><age>30</age></User>");
+
+    let res = call(post(Some("application/json"), Some("application/json;q=0.1, application/cbor"), JSON)).await;
+    assert_eq!(header_str(&res, header::CONTENT_TYPE), "application/cbor");
+
+    let res = call(post(Some("application/json"), Some("application/msgpack;q=0, application/json"), JSON)).await;
+    assert_eq!(header_str(&res, header::CONTENT_TYPE), "application/json");
+}
+
+#[tokio::test]
+async fn nothing_acceptable() {
+    let res = call(post(Some("application/json"), Some("text/html, image/png"), JSON)).await;
+    assert_eq!(res.status(), StatusCode::NOT_ACCEPTABLE);
+    let body = json(res).await;
+    assert_eq!(body["status"], 406);
+    assert_eq!(body["code"], "not_acceptable");
+}
@@ -0,0 +1,132 @@
+//! Fixtures shared by the integration tests.
+#![allow(dead_code)]
+
+use std::{
+    convert::Infallible,
+    path::{Path, PathBuf},
+};
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header::HeaderName, Request},
+    response::Response,
+};
+use exum::layers::static_layer::StaticFileServiceBuilder;
+use serde_json::Value;
+use tower::{Service, ServiceExt};
+
+/// A temp dir holding the served `root/` and an `outside/` directory next to it.
+pub struct Tree {
+    dir: tempfile::TempDir,
+}
+
+impl Tree {
+    pub fn new() -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("root")).unwrap();
+        std::fs::create_dir_all(dir.path().join("outside")).unwrap();
+        Self { dir }
+    }
+
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    pub fn root(&self) -> PathBuf {
+        self.dir.path().join("root")
+    }
+
+    pub fn outside(&self) -> PathBuf {
+        self.dir.path().join("outside")
+    }
+
+    /// Writes `content` to `rel` below `root/`, creating the missing directories.
+    pub fn file(self, rel: &str, content: impl AsRef<[u8]>) -> Self {
+        write(&self.root().join(rel), content.as_ref());
+        self
+    }
+
+    /// Writes `content` to `rel` below `outside/`.
+    pub fn outside_file(self, rel: &str, content: impl AsRef<[u8]>) -> Self {
+        write(&self.outside().join(rel), content.as_ref());
+        self
+    }
+
+    /// Creates `rel` below `root/` pointing to `target`, relative to the tree.
+    #[cfg(unix)]
+    pub fn symlink(self, rel: &str, target: &str) -> Self {
+        std::os::unix::fs::symlink(self.dir.path().join(target), self.root().join(rel)).unwrap();
+        self
+    }
+
+    pub fn read(&self, rel: &str) -> Option<String> {
+        std::fs::read_to_string(self.root().join(rel)).ok()
+    }
+
+    pub fn builder(&self) -> StaticFileServiceBuilder {
+        StaticFileServiceBuilder::new(self.root())
+    }
+
+    /// Every file and directory below the tree, directories ending with `/`, so stray writes,
+    /// temp files and empty directories show up. Symlinks are not followed.
+    pub fn files(&self) -> Vec<String> {
+        fn walk(dir: &Path, base: &Path, files: &mut Vec<String>) {
+            for entry in std::fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                let rel = path.strip_prefix(base).unwrap().to_string_lossy().into_owned();
+                if path.is_symlink() {
+                    continue;
+                }
+                if path.is_dir() {
+                    files.push(format!("{}/", rel));
+                    walk(&path, base, files);
+                } else {
+                    files.push(rel);
+                }
+            }
+        }
+        let mut files = Vec::new();
+        walk(self.dir.path(), self.dir.path(), &mut files);
+        files.sort();
+        files
+    }
+}
+
+fn write(path: &Path, content: &[u8]) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, content).unwrap();
+}
+
+pub async fn send<S>(service: &S, req: Request<Body>) -> Response
+where
+    S: Service<Request<Body>, Response = Response, Error = Infallible> + Clone,
+{
+    service.clone().oneshot(req).await.unwrap()
+}
+
+pub async fn get<S>(service: &S, path: &str, headers: &[(HeaderName, &str)]) -> Response
+where
+    S: Service<Request<Body>, Response = Response, Error = Infallible> + Clone,
+{
+    let mut req = Request::get(path);
+    for (name, value) in headers {
+        req = req.header(name, *value);
+    }
+    send(service, req.body(Body::empty()).unwrap()).await
+}
+
+pub async fn body(res: Response) -> Vec<u8> {
+    to_bytes(res.into_body(), usize::MAX).await.unwrap().to_vec()
+}
+
+pub async fn text(res: Response) -> String {
+    String::from_utf8(body(res).await).unwrap()
+}
+
+pub async fn json(res: Response) -> Value {
+    serde_json::from_slice(&body(res).await).unwrap()
+}
+
+pub fn header_str(res: &Response, name: HeaderName) -> &str {
+    res.headers().get(name).unwrap().to_str().unwrap()
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use axum::{
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+};
+use common::get;
+use exum::*;
+
+#[guard]
+async fn is_admin(parts: &Parts) -> Result<(), Response> {
+    match parts.headers.get("x-role") {
+        Some(role) if role == "admin" => Ok(()),
+        _ => Err(StatusCode::FORBIDDEN.into_response()),
+    }
+}
+
+#[guard]
+async fn has_team(parts: &Parts) -> Result<(), Response> {
+    match parts.headers.get("x-team") {
+        Some(_) => Ok(()),
+        None => Err(StatusCode::FORBIDDEN.into_response()),
+    }
+}
+
+#[derive(Controller)]
+struct ParentController;
+
+#[controller(prefix = "/p", children = [ChildController])]
+#[guard(is_admin)]
+impl ParentController {
+    #[get("/own")]
+    async fn own() -> &'static str {
+        "own"
+    }
+}
+
+#[derive(Controller)]
+struct ChildController;
+
+#[controller(prefix = "/child", children = [GrandchildController])]
+impl ChildController {
+    #[get("/x")]
+    async fn cx() -> &'static str {
+        "child"
+    }
+}
+
+#[derive(Controller)]
+struct GrandchildController;
+
+#[controller(prefix = "/grand")]
+#[guard(has_team)]
+impl GrandchildController {
+    #[get("/y")]
+    async fn gy() -> &'static str {
+        "grandchild"
+    }
+}
+
+fn meta(handler: &str) -> &'static RouteMeta {
+    route_metas().find(|meta| meta.handler == handler).unwrap()
+}
+
+#[tokio::test]
+async fn children_run_the_parent_guards() {
+    let router = collect_router();
+    assert_eq!(get(&router, "/p/child/x", &[]).await.status(), StatusCode::FORBIDDEN);
+    assert_eq!(get(&router, "/p/child/x", &[("x-role".parse().unwrap(), "admin")]).await.status(), StatusCode::OK);
+    assert_eq!(get(&router, "/p/child/grand/y", &[("x-role".parse().unwrap(), "admin")]).await.status(), StatusCode::FORBIDDEN);
+    let both = [("x-role".parse().unwrap(), "admin"), ("x-team".parse().unwrap(), "core")];
+    assert_eq!(get(&router, "/p/child/grand/y", &both).await.status(), StatusCode::OK);
+}
+
+#[test]
+fn route_metas_report_inherited_guards() {
+    assert_eq!(meta("own").all_guards(), ["is_admin"]);
+    assert_eq!(meta("cx").guards, [] as [&str; 0]);
+    assert_eq!(meta("cx").all_guards(), ["is_admin"]);
+    assert_eq!(meta("gy").guards, ["has_team"]);
+    assert_eq!(meta("gy").all_guards(), ["is_admin", "has_team"]);
+    assert_eq!(meta("gy").full_path(), "/p/child/grand/y");
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use axum::http::{header, HeaderName, StatusCode};
+use common::{get, json, text};
+use exum::*;
+use serde_json::json;
+
+#[get("/trace")]
+async fn trace(
+    #[h("x-request-id")] req_id: String,
+    #[h] user_agent: Option<String>,
+    #[h] x_retry_count: u32,
+) -> String {
+    format!("{} {:?} {}", req_id, user_agent, x_retry_count)
+}
+
+#[get("/me")]
+async fn me(#[cookie] session: Option<String>, #[cookie("theme")] theme: String, #[cookie] visits: Option<u32>) -> String {
+    format!("{:?} {} {:?}", session, theme, visits)
+}
+
+fn name(name: &'static str) -> HeaderName {
+    HeaderName::from_static(name)
+}
+
+async fn status_and_body(path: &str, headers: &[(HeaderName, &str)]) -> (StatusCode, serde_json::Value) {
+    let res = get(&collect_router(), path, headers).await;
+    (res.status(), json(res).await)
+}
+
+#[tokio::test]
+async fn headers_are_parsed() {
+    let headers = [(name("x-request-id"), "r1"), (header::USER_AGENT, "curl"), (name("x-retry-count"), "3")];
+    let res = get(&collect_router(), "/trace", &headers).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(text(res).await, "r1 Some(\"curl\") 3");
+
+    let headers = [(name("x-request-id"), "r1"), (name("x-retry-count"), "0")];
+    let res = get(&collect_router(), "/trace", &headers).await;
+    assert_eq!(text(res).await, "r1 None 0");
+}
+
+#[tokio::test]
+async fn missing_headers_are_bad_requests() {
+    let (status, body) = status_and_body("/trace", &[(name("x-request-id"), "r1")]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        body,
+        json!({
+            "status": 400,
+            "code": "missing_parameter",
+            "message": "Missing required header `x-retry-count`",
+            "param": "x-retry-count",
+            "request_id": "r1",
+        })
+    );
+}
+
+#[tokio::test]
+async fn unparsable_headers_are_bad_requests() {
+    let headers = [(name("x-request-id"), "r1"), (name("x-retry-count"), "many")];
+    let (status, body) = status_and_body("/trace", &headers).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "invalid_parameter");
+    assert_eq!(body["param"], "x-retry-count");
+    assert_eq!(body["message"], "Invalid header `x-retry-count`: invalid digit found in string");
+}
+
+#[tokio::test]
+async fn cookies_are_parsed() {
+    let res = get(&collect_router(), "/me", &[(header::COOKIE, "session=abc; theme=\"dark\"; visits=4")]).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(text(res).await, "Some(\"abc\") dark Some(4)");
+
+    // across several `Cookie` headers
+    let res = get(&collect_router(), "/me", &[(header::COOKIE, "a=1"), (header::COOKIE, "theme=light")]).await;
+    assert_eq!(text(res).await, "None light None");
+}
+
+#[tokio::test]
+async fn missing_and_unparsable_cookies_are_bad_requests() {
+    let (status, body) = status_and_body("/me", &[(header::COOKIE, "session=abc")]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "missing_parameter");
+    assert_eq!(body["param"], "theme");
+    assert_eq!(body["message"], "Missing required cookie `theme`");
+
+    let (status, body) = status_and_body("/me", &[(header::COOKIE, "theme=dark; visits=-1")]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "invalid_parameter");
+    assert_eq!(body["param"], "visits");
+    assert_eq!(body["message"], "Invalid cookie `visits`: invalid digit found in string");
+}
@@ -0,0 +1,178 @@
+mod common;
+
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+    Router,
+};
+use common::{json, send, text};
+use exum::*;
+
+#[derive(MultipartForm)]
+struct Profile {
+    title: String,
+    tags: Vec<String>,
+    age: Option<u32>,
+    #[multipart(max_size = 8)]
+    nickname: Option<String>,
+    #[multipart(content_types = ["image/*"])]
+    avatar: Option<UploadedFile>,
+}
+
+#[post("/profile")]
+async fn profile(#[b(multipart)] form: Profile) -> String {
+    let avatar = match &form.avatar {
+        Some(file) => format!("{}:{}", file.file_name.as_deref().unwrap_or_default(), file.size),
+        None => "-".to_string(),
+    };
+    format!("{} {:?} {:?} {:?} {}", form.title, form.tags, form.age, form.nickname, avatar)
+}
+
+#[derive(MultipartForm)]
+#[multipart(max_total_size = 32)]
+struct Note {
+    body: String,
+}
+
+#[post("/note")]
+async fn note(#[b(multipart)] form: Note) -> String {
+    form.body
+}
+
+const BOUNDARY: &str = "exum-test-boundary";
+
+enum Part<'a> {
+    Text(&'a str, &'a str),
+    File(&'a str, &'a str, &'a str, &'a [u8]),
+}
+
+fn multipart(path: &str, parts: &[Part]) -> Request<Body> {
+    let mut body = Vec::new();
+    for part in parts {
+        body.extend_from_slice(format!("--{}\r\n", BOUNDARY).as_bytes());
+        match part {
+            Part::Text(name, value) => {
+                body.extend_from_slice(format!("Content-Disposition: form-data; name=\"{}\"\r\n\r\n", name).as_bytes());
+                body.extend_from_slice(value.as_bytes());
+            }
+            Part::File(name, file_name, content_type, bytes) => {
+                body.extend_from_slice(
+                    format!(
+                        "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+                        name, file_name, content_type
+                    )
+                    .as_bytes(),
+                );
+                body.extend_from_slice(bytes);
+            }
+        }
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{}--\r\n", BOUNDARY).as_bytes());
+    Request::post(path)
+        .header(header::CONTENT_TYPE, format!("multipart/form-data; boundary={}", BOUNDARY))
+        .body(Body::from(body))
+        .unwrap()
+}
+
+fn router() -> Router {
+    collect_router()
+}
+
+#[tokio::test]
+async fn text_fields_and_files() {
+    let res = send(
+        &router(),
+        multipart(
+            "/profile",
+            &[
+                Part::Text("title", "hello"),
+                Part::Text("tags", "a"),
+                Part::Text("tags", "b"),
+                Part::Text("age", "42"),
+                Part::Text("nickname", "bob"),
+                Part::Text("unknown", "ignored"),
+                Part::File("avatar", "me.png", "image/png", b"png bytes"),
+            ],
+        ),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(text(res).await, "hello [\"a\", \"b\"] Some(42) Some(\"bob\") me.png:9");
+
+    let res = send(&router(), multipart("/profile", &[Part::Text("title", "bare")])).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(text(res).await, "bare [] None None -");
+}
+
+#[tokio::test]
+async fn missing_and_invalid_fields() {
+    let res = send(&router(), multipart("/profile", &[Part::Text("tags", "a")])).await;
+    assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = json(res).await;
+    assert_eq!(body["status"], 422);
+    assert_eq!(body["code"], "missing_field");
+    assert_eq!(body["param"], "title");
+
+    let res = send(&router(), multipart("/profile", &[Part::Text("title", "t"), Part::Text("age", "old")])).await;
+    assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = json(res).await;
+    assert_eq!(body["code"], "invalid_field");
+    assert_eq!(body["param"], "age");
+}
+
+#[tokio::test]
+async fn field_size_limit() {
+    let res = send(
+        &router(),
+        multipart("/profile", &[Part::Text("title", "t"), Part::Text("nickname", "far too long")]),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let body = json(res).await;
+    assert_eq!(body["code"], "payload_too_large");
+    assert_eq!(body["param"], "nickname");
+    assert_eq!(body["message"], "Field `nickname` exceeds the size limit of 8 bytes");
+}
+
+#[tokio::test]
+async fn total_size_limit() {
+    let res = send(&router(), multipart("/note", &[Part::Text("body", "short")])).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(text(res).await, "short");
+
+    // no single field has a limit, together they are over it
+    let long = "x".repeat(20);
+    let res = send(&router(), multipart("/note", &[Part::Text("other", &long), Part::Text("body", &long)])).await;
+    assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let body = json(res).await;
+    assert_eq!(body["code"], "payload_too_large");
+    assert_eq!(body["message"], "Multipart body exceeds the size limit");
+}
+
+#[tokio::test]
+async fn disallowed_content_type() {
+    let res = send(
+        &router(),
+        multipart(
+            "/profile",
+            &[Part::Text("title", "t"), Part::File("avatar", "me.txt", "text/plain", b"text")],
+        ),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    let body = json(res).await;
+    assert_eq!(body["code"], "unsupported_content_type");
+    assert_eq!(body["param"], "avatar");
+}
+
+#[tokio::test]
+async fn not_a_multipart_body() {
+    let req = Request::post("/profile")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from("{}"))
+        .unwrap();
+    let res = send(&router(), req).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(json(res).await["code"], "invalid_multipart");
+}
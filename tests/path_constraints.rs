@@ -0,0 +1,128 @@
+mod common;
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use common::{get, json, text};
+use exum::{rejection::ExumRejection, *};
+
+#[get("/users/{id:u64}")]
+async fn get_user(id: u64) -> String {
+    format!("user {}", id)
+}
+
+#[get("/posts/{slug:[a-z-]+}")]
+async fn get_post(slug: String) -> String {
+    slug
+}
+
+#[get("/reports/:day:date")]
+async fn report(day: String) -> String {
+    day
+}
+
+#[get("/keys/{key:uuid}")]
+async fn get_key(key: String) -> String {
+    key
+}
+
+#[get("/pages/{n:u8}")]
+async fn page(n: u8, #[q] lang: String) -> String {
+    format!("{} {}", n, lang)
+}
+
+struct ApiError(StatusCode, &'static str);
+
+impl From<ExumRejection> for ApiError {
+    fn from(err: ExumRejection) -> Self {
+        ApiError(err.status, err.code)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, format!("api error: {}", self.1)).into_response()
+    }
+}
+
+#[derive(Controller)]
+struct ShopController;
+
+#[controller("/shops/{shop:[a-z]+}", error = ApiError)]
+impl ShopController {
+    #[get("/items/{n:u32}")]
+    async fn item(shop: String, n: u32) -> String {
+        format!("{} {}", shop, n)
+    }
+}
+
+async fn error(path: &str) -> (StatusCode, serde_json::Value) {
+    let res = get(&collect_router(), path, &[]).await;
+    (res.status(), json(res).await)
+}
+
+#[tokio::test]
+async fn matching_values_reach_the_handler() {
+    let router = collect_router();
+    assert_eq!(text(get(&router, "/users/42", &[]).await).await, "user 42");
+    assert_eq!(text(get(&router, "/posts/hello-world", &[]).await).await, "hello-world");
+    assert_eq!(text(get(&router, "/reports/2024-02-29", &[]).await).await, "2024-02-29");
+    let uuid = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+    assert_eq!(text(get(&router, &format!("/keys/{}", uuid), &[]).await).await, uuid);
+}
+
+#[tokio::test]
+async fn type_constraints_are_bad_requests() {
+    let (status, body) = error("/users/abc").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["status"], 400);
+    assert_eq!(body["code"], "invalid_path");
+    assert_eq!(body["param"], "id");
+    assert_eq!(body["message"], "path parameter `id` must be a u64, got `abc`");
+
+    // a real date, not only the shape of one
+    let (status, body) = error("/reports/2023-02-29").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["param"], "day");
+
+    let (status, body) = error("/keys/67e55044-10b1-426f-9247").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["param"], "key");
+}
+
+#[tokio::test]
+async fn pattern_constraints_are_not_found() {
+    let (status, body) = error("/posts/Hello").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "not_found");
+    assert_eq!(body["param"], "slug");
+    assert_eq!(body["message"], "path parameter `slug` does not match `[a-z-]+`");
+}
+
+#[tokio::test]
+async fn constraints_are_checked_before_other_extractors() {
+    // `lang` is missing as well, the path is reported
+    let (status, body) = error("/pages/300").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "invalid_path");
+    assert_eq!(body["param"], "n");
+
+    let (status, body) = error("/pages/3").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "invalid_query");
+}
+
+#[tokio::test]
+async fn controller_prefix_constraints() {
+    let router = collect_router();
+    assert_eq!(text(get(&router, "/shops/acme/items/7", &[]).await).await, "acme 7");
+
+    let res = get(&router, "/shops/ACME/items/7", &[]).await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    assert_eq!(text(res).await, "api error: not_found");
+
+    let res = get(&router, "/shops/acme/items/x", &[]).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(text(res).await, "api error: invalid_path");
+}
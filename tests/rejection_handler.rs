@@ -0,0 +1,159 @@
+mod common;
+
+use axum::{
+    body::Body,
+    extract::FromRequestParts,
+    http::{header, request::Parts, HeaderValue, Request, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use common::{get, header_str, json, send, text};
+use exum::{rejection::ExumRejection, *};
+use serde::Deserialize;
+use serde_json::json;
+
+#[rejection_handler]
+fn on_reject(err: ExumRejection) -> (StatusCode, Json<serde_json::Value>) {
+    (
+        err.status,
+        Json(json!({
+            "success": false,
+            "error": err.code,
+            "param": err.param,
+            "requestId": err.request_id,
+        })),
+    )
+}
+
+#[derive(Deserialize)]
+struct Item {
+    name: String,
+}
+
+#[get("/items/{id}")]
+async fn get_item(id: u32) -> String {
+    id.to_string()
+}
+
+#[get("/search")]
+async fn search(#[q] page: u32) -> String {
+    page.to_string()
+}
+
+#[post("/items")]
+async fn create_item(#[b] item: Item) -> String {
+    item.name
+}
+
+#[get("/retry")]
+async fn retry(#[h] x_retry_count: u32) -> String {
+    x_retry_count.to_string()
+}
+
+/// A hand-written extractor reusing the exum rejection.
+struct ApiKey(String);
+
+impl<S: Send + Sync> FromRequestParts<S> for ApiKey {
+    type Rejection = ExumRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        match parts.headers.get("x-api-key") {
+            Some(key) => Ok(ApiKey(key.to_str().unwrap_or_default().to_string())),
+            None => Err(ExumRejection::new(StatusCode::UNAUTHORIZED, "unauthorized", "API key required")
+                .with_header(header::WWW_AUTHENTICATE, HeaderValue::from_static("ApiKey"))),
+        }
+    }
+}
+
+#[get("/secret")]
+async fn secret(ApiKey(key): ApiKey) -> String {
+    format!("secret for {}", key)
+}
+
+struct ApiError(StatusCode, &'static str);
+
+impl From<ExumRejection> for ApiError {
+    fn from(err: ExumRejection) -> Self {
+        ApiError(err.status, err.code)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, format!("api error: {}", self.1)).into_response()
+    }
+}
+
+#[derive(Controller)]
+struct OrderController;
+
+#[controller(prefix = "/orders", error = ApiError)]
+impl OrderController {
+    #[get("/{id}")]
+    async fn get_order(id: u32) -> String {
+        id.to_string()
+    }
+}
+
+fn with_request_id(path: &str) -> Request<Body> {
+    Request::get(path).header("x-request-id", "abc").body(Body::empty()).unwrap()
+}
+
+#[tokio::test]
+async fn every_extractor_goes_through_the_handler() {
+    let router = collect_router();
+
+    let res = send(&router, with_request_id("/items/nope")).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        json(res).await,
+        json!({"success": false, "error": "invalid_path", "param": "id", "requestId": "abc"})
+    );
+
+    let res = send(&router, with_request_id("/search?page=x")).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        json(res).await,
+        json!({"success": false, "error": "invalid_query", "param": "page", "requestId": "abc"})
+    );
+
+    let req = Request::post("/items").header(header::CONTENT_TYPE, "text/plain").body(Body::from("x")).unwrap();
+    let res = send(&router, req).await;
+    assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(
+        json(res).await,
+        json!({"success": false, "error": "invalid_body", "param": "item", "requestId": null})
+    );
+
+    let res = get(&router, "/retry", &[("x-retry-count".parse().unwrap(), "many")]).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        json(res).await,
+        json!({"success": false, "error": "invalid_parameter", "param": "x-retry-count", "requestId": null})
+    );
+}
+
+#[tokio::test]
+async fn successful_requests_are_untouched() {
+    let router = collect_router();
+    assert_eq!(text(get(&router, "/items/7", &[]).await).await, "7");
+    assert_eq!(text(get(&router, "/search?page=2", &[]).await).await, "2");
+}
+
+#[tokio::test]
+async fn rejection_headers_are_kept() {
+    let res = get(&collect_router(), "/secret", &[]).await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(header_str(&res, header::WWW_AUTHENTICATE), "ApiKey");
+    assert_eq!(json(res).await["error"], "unauthorized");
+
+    let res = get(&collect_router(), "/secret", &[("x-api-key".parse().unwrap(), "k1")]).await;
+    assert_eq!(text(res).await, "secret for k1");
+}
+
+#[tokio::test]
+async fn controller_error_types_win() {
+    let res = get(&collect_router(), "/orders/nope", &[]).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(text(res).await, "api error: invalid_path");
+}
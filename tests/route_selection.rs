@@ -0,0 +1,58 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use exum::*;
+use tower::ServiceExt;
+
+mod users {
+    use exum::*;
+
+    #[get("/users/{id}")]
+    pub async fn get_user(id: u32) -> String {
+        format!("user {}", id)
+    }
+}
+
+mod admin {
+    pub mod users {
+        use exum::*;
+
+        #[get("/admin/users/{id}")]
+        pub async fn get_user(id: u32) -> String {
+            format!("admin {}", id)
+        }
+    }
+}
+
+async fn get(router: &Router, path: &str) -> (StatusCode, String) {
+    let res = router.clone().oneshot(Request::get(path).body(Body::empty()).unwrap()).await.unwrap();
+    let status = res.status();
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn qualified_handler_selects_one_module() {
+    let router = collect_router_from(&RouteSelection::new().handler("admin::users::get_user"));
+    assert_eq!(get(&router, "/admin/users/1").await, (StatusCode::OK, "admin 1".to_string()));
+    assert_eq!(get(&router, "/users/1").await.0, StatusCode::NOT_FOUND);
+
+    // `users::get_user` ends the module path of both, the crate name picks the top-level one
+    let router = collect_router_from(&RouteSelection::new().handler("route_selection::users::get_user"));
+    assert_eq!(get(&router, "/users/2").await, (StatusCode::OK, "user 2".to_string()));
+    assert_eq!(get(&router, "/admin/users/2").await.0, StatusCode::NOT_FOUND);
+}
+
+#[test]
+#[should_panic(expected = "matches route functions in several modules")]
+fn ambiguous_handler_name() {
+    let _ = collect_router_from(&RouteSelection::new().handler("get_user"));
+}
+
+#[test]
+#[should_panic(expected = "route_selection::admin::users, route_selection::users")]
+fn partial_module_path_still_ambiguous() {
+    let _ = collect_router_from(&RouteSelection::new().handler("users::get_user"));
+}
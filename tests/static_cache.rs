@@ -0,0 +1,115 @@
+mod common;
+
+use std::time::{Duration, UNIX_EPOCH};
+
+use axum::http::StatusCode;
+use common::{get, text, Tree};
+use exum::layers::{static_layer::StaticFileService, CacheStats, Revalidate, StaticCache};
+
+fn service(tree: &Tree, cache: &StaticCache) -> StaticFileService {
+    tree.builder().cache(cache.clone()).build()
+}
+
+async fn read(service: &StaticFileService, path: &str) -> String {
+    let res = get(service, path, &[]).await;
+    assert_eq!(res.status(), StatusCode::OK, "{}", path);
+    text(res).await
+}
+
+/// Rewrites `rel` with another mtime, so the change shows even within the same second.
+fn rewrite(tree: &Tree, rel: &str, content: &str, secs: u64) {
+    let path = tree.root().join(rel);
+    std::fs::write(&path, content).unwrap();
+    let file = std::fs::File::options().write(true).open(&path).unwrap();
+    file.set_modified(UNIX_EPOCH + Duration::from_secs(secs)).unwrap();
+}
+
+#[tokio::test]
+async fn repeat_get_is_a_hit() {
+    let tree = Tree::new().file("a.txt", "hello");
+    let cache = StaticCache::new(1024);
+    let service = service(&tree, &cache);
+    assert_eq!(read(&service, "/a.txt").await, "hello");
+    let first = cache.stats();
+    assert_eq!((first.content_hits, first.content_misses, first.entries, first.bytes), (0, 1, 1, 5));
+
+    assert_eq!(read(&service, "/a.txt").await, "hello");
+    let second = cache.stats();
+    assert_eq!(second.misses, first.misses);
+    assert_eq!(second.content_misses, first.content_misses);
+    assert!(second.hits > first.hits);
+    assert_eq!(second.content_hits, 1);
+    assert!(second.hit_rate() > first.hit_rate());
+}
+
+#[test]
+fn hit_rate() {
+    assert_eq!(CacheStats::default().hit_rate(), 0.0);
+    let stats = CacheStats { hits: 3, misses: 1, content_hits: 1, content_misses: 3, ..CacheStats::default() };
+    assert_eq!(stats.hit_rate(), 0.5);
+    let stats = CacheStats { hits: 2, content_hits: 2, ..CacheStats::default() };
+    assert_eq!(stats.hit_rate(), 1.0);
+}
+
+#[tokio::test]
+async fn mtime_changes_are_picked_up() {
+    let tree = Tree::new().file("a.txt", "");
+    rewrite(&tree, "a.txt", "version one", 1_700_000_000);
+    let cache = StaticCache::new(1024).revalidate(Revalidate::Mtime);
+    let service = service(&tree, &cache);
+    assert_eq!(read(&service, "/a.txt").await, "version one");
+    assert_eq!(read(&service, "/a.txt").await, "version one");
+    let misses = cache.stats().content_misses;
+
+    // same size, only the mtime tells them apart
+    rewrite(&tree, "a.txt", "version two", 1_700_000_001);
+    assert_eq!(read(&service, "/a.txt").await, "version two");
+    assert_eq!(cache.stats().content_misses, misses + 1);
+}
+
+#[tokio::test]
+async fn manual_revalidation() {
+    let tree = Tree::new().file("a.txt", "").file("b.txt", "bbb");
+    rewrite(&tree, "a.txt", "old", 1_700_000_000);
+    let cache = StaticCache::new(1024).revalidate(Revalidate::Manual);
+    let service = service(&tree, &cache);
+    assert_eq!(read(&service, "/a.txt").await, "old");
+    assert_eq!(read(&service, "/b.txt").await, "bbb");
+
+    // trusted until invalidated
+    rewrite(&tree, "a.txt", "new", 1_700_000_001);
+    assert_eq!(read(&service, "/a.txt").await, "old");
+    cache.invalidate("/a.txt");
+    assert_eq!(cache.stats().entries, 1);
+    assert_eq!(read(&service, "/a.txt").await, "new");
+
+    rewrite(&tree, "a.txt", "newer", 1_700_000_002);
+    rewrite(&tree, "b.txt", "BBB", 1_700_000_002);
+    assert_eq!(read(&service, "/a.txt").await, "new");
+    cache.clear();
+    assert_eq!((cache.stats().entries, cache.stats().bytes), (0, 0));
+    assert_eq!(read(&service, "/a.txt").await, "newer");
+    assert_eq!(read(&service, "/b.txt").await, "BBB");
+}
+
+#[tokio::test]
+async fn content_is_evicted_over_the_byte_cap() {
+    let tree = Tree::new().file("a.txt", "aaaaaa").file("b.txt", "bbbbbb").file("big.txt", "x".repeat(11));
+    let cache = StaticCache::new(10);
+    let service = service(&tree, &cache);
+    assert_eq!(read(&service, "/a.txt").await, "aaaaaa");
+    assert_eq!(cache.stats().bytes, 6);
+
+    // 6 + 6 > 10: the least recently used entry goes
+    assert_eq!(read(&service, "/b.txt").await, "bbbbbb");
+    let stats = cache.stats();
+    assert_eq!((stats.evictions, stats.entries, stats.bytes), (1, 1, 6));
+
+    let misses = stats.content_misses;
+    assert_eq!(read(&service, "/a.txt").await, "aaaaaa");
+    assert_eq!(cache.stats().content_misses, misses + 1);
+
+    // larger than the whole cache: served, never stored
+    assert_eq!(read(&service, "/big.txt").await, "x".repeat(11));
+    assert!(cache.stats().bytes <= 10);
+}
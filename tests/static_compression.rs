@@ -0,0 +1,110 @@
+mod common;
+
+use std::io::Read;
+
+use axum::http::{header, StatusCode};
+use common::{body, get, header_str, Tree};
+use exum::layers::{static_layer::StaticFileService, Encoding};
+
+const SCRIPT: &str = "console.log('plain');";
+
+fn precompressed() -> (Tree, StaticFileService) {
+    let tree = Tree::new()
+        .file("app.js", SCRIPT)
+        .file("app.js.br", "brotli bytes")
+        .file("app.js.gz", "gzip bytes")
+        .file("only-gz.js", SCRIPT)
+        .file("only-gz.js.gz", "gzip only");
+    let service = tree.builder().precompressed(&[Encoding::Brotli, Encoding::Gzip]).build();
+    (tree, service)
+}
+
+#[tokio::test]
+async fn siblings_follow_accept_encoding() {
+    let (_tree, service) = precompressed();
+
+    let res = get(&service, "/app.js", &[(header::ACCEPT_ENCODING, "gzip, br")]).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(header_str(&res, header::CONTENT_ENCODING), "br");
+    assert_eq!(header_str(&res, header::VARY), "accept-encoding");
+    assert_eq!(header_str(&res, header::CONTENT_TYPE), "text/javascript; charset=utf-8");
+    assert_eq!(body(res).await, b"brotli bytes");
+
+    let res = get(&service, "/app.js", &[(header::ACCEPT_ENCODING, "gzip")]).await;
+    assert_eq!(header_str(&res, header::CONTENT_ENCODING), "gzip");
+    assert_eq!(body(res).await, b"gzip bytes");
+
+    let res = get(&service, "/app.js", &[(header::ACCEPT_ENCODING, "br;q=0.5, gzip")]).await;
+    assert_eq!(header_str(&res, header::CONTENT_ENCODING), "gzip");
+
+    // the best encoding without a sibling is skipped
+    let res = get(&service, "/only-gz.js", &[(header::ACCEPT_ENCODING, "br, gzip")]).await;
+    assert_eq!(header_str(&res, header::CONTENT_ENCODING), "gzip");
+    assert_eq!(body(res).await, b"gzip only");
+}
+
+#[tokio::test]
+async fn identity_gets_the_plain_file() {
+    let (_tree, service) = precompressed();
+
+    for accept_encoding in [Some("identity"), Some("br;q=0, gzip;q=0"), None] {
+        let headers = match accept_encoding {
+            Some(value) => vec![(header::ACCEPT_ENCODING, value)],
+            None => Vec::new(),
+        };
+        let res = get(&service, "/app.js", &headers).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.headers().get(header::CONTENT_ENCODING).is_none(), "{:?}", accept_encoding);
+        // caches still have to key the plain response on the header
+        assert_eq!(header_str(&res, header::VARY), "accept-encoding");
+        assert_eq!(body(res).await, SCRIPT.as_bytes());
+    }
+}
+
+#[tokio::test]
+async fn variants_get_their_own_etag() {
+    let (_tree, service) = precompressed();
+    let plain = get(&service, "/app.js", &[]).await;
+    let br = get(&service, "/app.js", &[(header::ACCEPT_ENCODING, "br")]).await;
+    assert_ne!(header_str(&plain, header::ETAG), header_str(&br, header::ETAG));
+}
+
+#[tokio::test]
+async fn compressing_on_the_fly() {
+    let text = "compress me ".repeat(200);
+    let tree = Tree::new().file("big.txt", &text).file("small.txt", "tiny").file("image.png", &text);
+    let service = tree.builder().compress_on_the_fly(&[Encoding::Gzip], 64).build();
+
+    let res = get(&service, "/big.txt", &[(header::ACCEPT_ENCODING, "gzip")]).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(header_str(&res, header::CONTENT_ENCODING), "gzip");
+    assert_eq!(header_str(&res, header::VARY), "accept-encoding");
+    assert_eq!(header_str(&res, header::ACCEPT_RANGES), "none");
+    assert!(res.headers().get(header::CONTENT_LENGTH).is_none());
+    let mut decoded = String::new();
+    flate2::read::GzDecoder::new(&body(res).await[..]).read_to_string(&mut decoded).unwrap();
+    assert_eq!(decoded, text);
+
+    let res = get(&service, "/big.txt", &[(header::ACCEPT_ENCODING, "identity")]).await;
+    assert!(res.headers().get(header::CONTENT_ENCODING).is_none());
+    assert_eq!(body(res).await, text.as_bytes());
+
+    // below the minimum size, and not compressible
+    for path in ["/small.txt", "/image.png"] {
+        let res = get(&service, path, &[(header::ACCEPT_ENCODING, "gzip")]).await;
+        assert!(res.headers().get(header::CONTENT_ENCODING).is_none(), "{}", path);
+    }
+}
+
+#[tokio::test]
+async fn siblings_win_over_compressing_on_the_fly() {
+    let (tree, _) = precompressed();
+    let service = tree
+        .builder()
+        .precompressed(&[Encoding::Gzip])
+        .compress_on_the_fly(&[Encoding::Gzip], 0)
+        .build();
+    let res = get(&service, "/app.js", &[(header::ACCEPT_ENCODING, "gzip")]).await;
+    assert_eq!(header_str(&res, header::CONTENT_ENCODING), "gzip");
+    assert_eq!(body(res).await, b"gzip bytes");
+}
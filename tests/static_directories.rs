@@ -0,0 +1,129 @@
+mod common;
+
+use axum::http::{header, StatusCode};
+use common::{get, header_str, json, text, Tree};
+use exum::layers::{DirectoryListing, ListingFormat, ListingSort};
+
+fn tree() -> Tree {
+    Tree::new()
+        .file("index.html", "<h1>home</h1>")
+        .file("docs/index.htm", "docs htm")
+        .file("docs/guide.md", "# guide")
+        .file("both/index.html", "both html")
+        .file("both/index.htm", "both htm")
+        .file("files/b.txt", "bb")
+        .file("files/a.txt", "aaaa")
+        .file("files/.env", "SECRET=1")
+        .file("files/sub/c.txt", "c")
+}
+
+#[tokio::test]
+async fn index_files_are_tried_in_order() {
+    let tree = tree();
+    let service = tree.builder().build();
+    let res = get(&service, "/", &[]).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(header_str(&res, header::CONTENT_TYPE), "text/html; charset=utf-8");
+    assert_eq!(text(res).await, "<h1>home</h1>");
+    // only `index.html` by default
+    assert_eq!(get(&service, "/docs/", &[]).await.status(), StatusCode::NOT_FOUND);
+
+    let service = tree.builder().index_files(&["index.htm", "index.html"]).build();
+    assert_eq!(text(get(&service, "/docs/", &[]).await).await, "docs htm");
+    assert_eq!(text(get(&service, "/both/", &[]).await).await, "both htm");
+    assert_eq!(text(get(&service, "/", &[]).await).await, "<h1>home</h1>");
+
+    let service = tree.builder().index_files(&[]).build();
+    assert_eq!(get(&service, "/", &[]).await.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn directories_are_redirected_to_the_trailing_slash() {
+    let tree = tree();
+    let service = tree.builder().build();
+    let res = get(&service, "/both", &[]).await;
+    assert_eq!(res.status(), StatusCode::PERMANENT_REDIRECT);
+    assert_eq!(header_str(&res, header::LOCATION), "/both/");
+
+    let res = get(&service, "/both?lang=en", &[]).await;
+    assert_eq!(header_str(&res, header::LOCATION), "/both/?lang=en");
+
+    // files are not redirected
+    assert_eq!(get(&service, "/index.html", &[]).await.status(), StatusCode::OK);
+
+    let service = tree.builder().redirect_trailing_slash(false).build();
+    let res = get(&service, "/both", &[]).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(text(res).await, "both html");
+}
+
+#[tokio::test]
+async fn directories_without_an_index_are_listed() {
+    let tree = tree();
+    assert_eq!(get(&tree.builder().build(), "/files/", &[]).await.status(), StatusCode::NOT_FOUND);
+
+    let service = tree.builder().directory_listing(DirectoryListing::new().format(ListingFormat::Json)).build();
+    let res = get(&service, "/files", &[]).await;
+    assert_eq!(res.status(), StatusCode::PERMANENT_REDIRECT);
+
+    let listing = json(get(&service, "/files/", &[]).await).await;
+    assert_eq!(listing["path"], "/files/");
+    let names: Vec<_> = listing["entries"].as_array().unwrap().iter().map(|e| e["name"].clone()).collect();
+    // directories first, hidden files left out
+    assert_eq!(names, ["sub", "a.txt", "b.txt"]);
+    assert_eq!(listing["entries"][0]["is_dir"], true);
+    assert_eq!(listing["entries"][0]["size"], serde_json::Value::Null);
+    assert_eq!(listing["entries"][1]["size"], 4);
+
+    let listing = json(get(&service, "/files/?sort=size&order=asc", &[]).await).await;
+    assert_eq!(listing["entries"][1]["name"], "b.txt");
+
+    // a directory with an index file is not listed
+    assert_eq!(text(get(&service, "/both/", &[]).await).await, "both html");
+
+    let service = tree
+        .builder()
+        .directory_listing(DirectoryListing::new().format(ListingFormat::Json).sort(ListingSort::Name, true).show_hidden(true))
+        .build();
+    let listing = json(get(&service, "/files/", &[]).await).await;
+    let names: Vec<_> = listing["entries"].as_array().unwrap().iter().map(|e| e["name"].clone()).collect();
+    assert_eq!(names, ["sub", "b.txt", "a.txt", ".env"]);
+}
+
+#[tokio::test]
+async fn listing_format_follows_accept() {
+    let tree = tree();
+    let service = tree.builder().directory_listing(DirectoryListing::new()).build();
+
+    let res = get(&service, "/files/", &[(header::ACCEPT, "application/json")]).await;
+    assert_eq!(header_str(&res, header::CONTENT_TYPE), "application/json");
+    assert_eq!(header_str(&res, header::VARY), "accept");
+
+    let res = get(&service, "/files/", &[(header::ACCEPT, "text/html,application/json")]).await;
+    assert_eq!(header_str(&res, header::CONTENT_TYPE), "text/html; charset=utf-8");
+    let html = text(res).await;
+    assert!(html.contains("<title>Index of /files/</title>"));
+    assert!(html.contains("<a href=\"../\">../</a>"));
+    assert!(html.contains("<a href=\"./sub/\">sub/</a>"));
+    assert!(html.contains("<a href=\"./a.txt\">a.txt</a>"));
+}
+
+#[tokio::test]
+async fn listed_names_are_escaped() {
+    let tree = Tree::new()
+        .file("<img src=x onerror=alert(1)>.txt", "x")
+        .file("a&b \"c'.txt", "x")
+        .file("q?#.txt", "x");
+    let service = tree.builder().directory_listing(DirectoryListing::new().format(ListingFormat::Html)).build();
+    let html = text(get(&service, "/", &[]).await).await;
+
+    assert!(!html.contains("<img"), "{}", html);
+    assert!(html.contains(
+        "<a href=\"./%3Cimg%20src=x%20onerror=alert(1)%3E.txt\">&lt;img src=x onerror=alert(1)&gt;.txt</a>"
+    ));
+    assert!(html.contains("<a href=\"./a&amp;b%20%22c&#39;.txt\">a&amp;b &quot;c&#39;.txt</a>"));
+    // `?` and `#` would otherwise end the path of the link
+    assert!(html.contains("<a href=\"./q%3F%23.txt\">q?#.txt</a>"));
+    // no parent link at the root
+    assert!(!html.contains("../"));
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use axum::http::{header, StatusCode};
+use common::{body, get, header_str, text};
+use exum::layers::{
+    embed::{EmbeddedAssets, EmbeddedFile, EmbeddedFileService},
+    Encoding,
+};
+
+static FILES: &[EmbeddedFile] = &[
+    EmbeddedFile {
+        path: "404.html",
+        bytes: b"<h1>missing</h1>",
+        content_type: "text/html; charset=utf-8",
+        etag: "\"0000000000000404\"",
+        modified: 1_700_000_000,
+        encoded: &[],
+    },
+    EmbeddedFile {
+        path: "app.js",
+        bytes: b"console.log('0123456789');",
+        content_type: "text/javascript; charset=utf-8",
+        etag: "\"00000000000a9915\"",
+        modified: 1_700_000_000,
+        encoded: &[(Encoding::Brotli, b"brotli bytes")],
+    },
+    EmbeddedFile {
+        path: "index.html",
+        bytes: b"<h1>home</h1>",
+        content_type: "text/html; charset=utf-8",
+        etag: "\"000000000000feed\"",
+        modified: 0,
+        encoded: &[],
+    },
+];
+
+static ASSETS: EmbeddedAssets = EmbeddedAssets::new("./missing-dist", FILES, false);
+
+fn service() -> EmbeddedFileService {
+    EmbeddedFileService::builder(ASSETS).error_page(StatusCode::NOT_FOUND, "404.html").build()
+}
+
+#[tokio::test]
+async fn files_use_the_compile_time_tag_and_mtime() {
+    let service = service();
+    let res = get(&service, "/app.js", &[]).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(header_str(&res, header::ETAG), "\"00000000000a9915\"");
+    assert_eq!(header_str(&res, header::LAST_MODIFIED), "Tue, 14 Nov 2023 22:13:20 GMT");
+    assert_eq!(header_str(&res, header::CONTENT_TYPE), "text/javascript; charset=utf-8");
+    assert_eq!(header_str(&res, header::X_CONTENT_TYPE_OPTIONS), "nosniff");
+    assert_eq!(body(res).await, b"console.log('0123456789');");
+
+    let res = get(&service, "/app.js", &[(header::IF_NONE_MATCH, "\"00000000000a9915\"")]).await;
+    assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+    let res = get(&service, "/", &[]).await;
+    assert_eq!(header_str(&res, header::ETAG), "\"000000000000feed\"");
+    assert!(res.headers().get(header::LAST_MODIFIED).is_none());
+}
+
+#[tokio::test]
+async fn precompressed_variants() {
+    let service = service();
+    let res = get(&service, "/app.js", &[(header::ACCEPT_ENCODING, "gzip, br")]).await;
+    assert_eq!(header_str(&res, header::CONTENT_ENCODING), "br");
+    assert_eq!(header_str(&res, header::ETAG), "\"00000000000a9915-br\"");
+    assert_eq!(header_str(&res, header::VARY), "accept-encoding");
+    assert_eq!(header_str(&res, header::CONTENT_TYPE), "text/javascript; charset=utf-8");
+    assert_eq!(body(res).await, b"brotli bytes");
+
+    let res = get(&service, "/app.js", &[(header::ACCEPT_ENCODING, "gzip")]).await;
+    assert!(res.headers().get(header::CONTENT_ENCODING).is_none());
+    assert_eq!(body(res).await, b"console.log('0123456789');");
+}
+
+#[tokio::test]
+async fn ranges() {
+    let service = service();
+    let res = get(&service, "/app.js", &[(header::RANGE, "bytes=13-22")]).await;
+    assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(header_str(&res, header::CONTENT_RANGE), "bytes 13-22/26");
+    assert_eq!(body(res).await, b"0123456789");
+
+    let res = get(&service, "/app.js", &[(header::RANGE, "bytes=0-6,13-14")]).await;
+    assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+    assert!(header_str(&res, header::CONTENT_TYPE).starts_with("multipart/byteranges; boundary="));
+    let body = text(res).await;
+    assert!(body.contains("Content-Range: bytes 0-6/26\r\n\r\nconsole\r\n"));
+    assert!(body.contains("Content-Range: bytes 13-14/26\r\n\r\n01\r\n"));
+
+    let res = get(&service, "/app.js", &[(header::RANGE, "bytes=0-1"), (header::IF_RANGE, "\"stale\"")]).await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn error_pages_get_file_headers() {
+    let service = service();
+    let res = get(&service, "/nope.js", &[]).await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    assert_eq!(header_str(&res, header::CONTENT_TYPE), "text/html; charset=utf-8");
+    assert_eq!(header_str(&res, header::X_CONTENT_TYPE_OPTIONS), "nosniff");
+    assert_eq!(body(res).await, b"<h1>missing</h1>");
+}
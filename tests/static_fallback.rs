@@ -0,0 +1,111 @@
+mod common;
+
+use axum::http::{header, StatusCode};
+use common::{get, header_str, text, Tree};
+use exum::layers::{
+    vfs::{VfsFuture, VfsMetadata, VfsReader, VirtualFs},
+    DotFiles, SpaFallback,
+};
+
+fn tree() -> Tree {
+    Tree::new()
+        .file("index.html", "<p>app</p>")
+        .file("admin/index.html", "<p>admin</p>")
+        .file("assets/app.js", "app")
+        .file(".env", "SECRET=1")
+        .file("errors/404.html", "<h1>not found</h1>")
+        .file("errors/403.html", "<h1>forbidden</h1>")
+        .file("errors/500.html", "<h1>broken</h1>")
+}
+
+const HTML: (header::HeaderName, &str) = (header::ACCEPT, "text/html,application/xhtml+xml");
+
+#[tokio::test]
+async fn asset_misses_are_not_served_the_app() {
+    let tree = tree();
+    let service = tree.builder().spa_fallback(SpaFallback::new("index.html")).build();
+    let res = get(&service, "/assets/missing.js", &[]).await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    assert!(!text(res).await.contains("app</p>"));
+
+    // without html_only every miss falls back
+    let service = tree.builder().spa_fallback(SpaFallback::new("index.html").html_only(false)).build();
+    assert_eq!(text(get(&service, "/assets/missing.js", &[]).await).await, "<p>app</p>");
+}
+
+#[tokio::test]
+async fn navigations_get_the_fallback_file_and_status() {
+    let tree = tree();
+    let service = tree.builder().spa_fallback(SpaFallback::new("index.html")).build();
+    for (path, headers) in [("/settings/profile", &[HTML][..]), ("/settings/profile", &[]), ("/report.v2", &[HTML])] {
+        let res = get(&service, path, headers).await;
+        assert_eq!(res.status(), StatusCode::OK, "{}", path);
+        assert_eq!(header_str(&res, header::CONTENT_TYPE), "text/html; charset=utf-8");
+        assert_eq!(text(res).await, "<p>app</p>");
+    }
+    // existing files are served as they are
+    assert_eq!(text(get(&service, "/assets/app.js", &[HTML]).await).await, "app");
+
+    let service = tree.builder().spa_fallback(SpaFallback::new("/index.html").status(StatusCode::NOT_FOUND)).build();
+    let res = get(&service, "/gone", &[HTML]).await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    assert_eq!(text(res).await, "<p>app</p>");
+}
+
+#[tokio::test]
+async fn the_longest_prefix_picks_the_app() {
+    let tree = tree();
+    let service = tree
+        .builder()
+        .spa_fallback(SpaFallback::new("index.html"))
+        .spa_fallback(SpaFallback::new("admin/index.html").prefix("/admin/"))
+        .build();
+    assert_eq!(text(get(&service, "/admin/users/1", &[HTML]).await).await, "<p>admin</p>");
+    assert_eq!(text(get(&service, "/admin/settings", &[HTML]).await).await, "<p>admin</p>");
+    // an existing directory is not a miss
+    assert_eq!(get(&service, "/admin", &[HTML]).await.status(), StatusCode::PERMANENT_REDIRECT);
+    assert_eq!(text(get(&service, "/administrator", &[HTML]).await).await, "<p>app</p>");
+    assert_eq!(text(get(&service, "/users/1", &[HTML]).await).await, "<p>app</p>");
+}
+
+/// Fails every lookup with an error that is neither "not found" nor "permission denied".
+struct BrokenFs;
+
+impl VirtualFs for BrokenFs {
+    fn metadata<'a>(&'a self, _: &'a str) -> VfsFuture<'a, VfsMetadata> {
+        Box::pin(async { Err(std::io::Error::other("disk on fire")) })
+    }
+
+    fn open<'a>(&'a self, _: &'a str, _: u64) -> VfsFuture<'a, VfsReader> {
+        Box::pin(async { Err(std::io::Error::other("disk on fire")) })
+    }
+}
+
+#[tokio::test]
+async fn custom_error_pages() {
+    let tree = tree();
+    let service = tree
+        .builder()
+        .dotfiles(DotFiles::Deny)
+        .mount_fs("broken", BrokenFs)
+        .error_page(StatusCode::NOT_FOUND, "errors/404.html")
+        .error_page(StatusCode::FORBIDDEN, "errors/403.html")
+        .error_page(StatusCode::INTERNAL_SERVER_ERROR, "errors/500.html")
+        .build();
+    for (path, status, page) in [
+        ("/missing.txt", StatusCode::NOT_FOUND, "<h1>not found</h1>"),
+        ("/.env", StatusCode::FORBIDDEN, "<h1>forbidden</h1>"),
+        ("/broken/file.txt", StatusCode::INTERNAL_SERVER_ERROR, "<h1>broken</h1>"),
+    ] {
+        let res = get(&service, path, &[]).await;
+        assert_eq!(res.status(), status, "{}", path);
+        assert_eq!(header_str(&res, header::CONTENT_TYPE), "text/html; charset=utf-8");
+        assert_eq!(text(res).await, page);
+    }
+
+    // a status without a page keeps the plain response
+    let plain = tree.builder().error_page(StatusCode::FORBIDDEN, "errors/403.html").build();
+    let res = get(&plain, "/missing.txt", &[]).await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    assert_ne!(text(res).await, "<h1>not found</h1>");
+}
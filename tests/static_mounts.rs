@@ -0,0 +1,78 @@
+mod common;
+
+use axum::http::{header, StatusCode};
+use common::{get, header_str, json, text, Tree};
+use exum::layers::{static_layer::StaticFileServiceBuilder, vfs::MemoryFs, DirectoryListing, ListingFormat};
+
+/// `root/` is the theme, `outside/` the base directory under it.
+fn tree() -> Tree {
+    Tree::new()
+        .file("style.css", "theme css")
+        .file("pages/about.html", "theme about")
+        .outside_file("style.css", "base css")
+        .outside_file("pages/about.html", "base about")
+        .outside_file("pages/contact.html", "base contact")
+        .outside_file("logo.svg", "<svg/>")
+}
+
+#[tokio::test]
+async fn roots_are_searched_in_order() {
+    let tree = tree();
+    let service = tree.builder().root(tree.outside()).build();
+    assert_eq!(text(get(&service, "/style.css", &[]).await).await, "theme css");
+    assert_eq!(text(get(&service, "/pages/about.html", &[]).await).await, "theme about");
+    // only in the base
+    assert_eq!(text(get(&service, "/pages/contact.html", &[]).await).await, "base contact");
+    assert_eq!(text(get(&service, "/logo.svg", &[]).await).await, "<svg/>");
+    assert_eq!(get(&service, "/nowhere.css", &[]).await.status(), StatusCode::NOT_FOUND);
+
+    // the other way round
+    let reversed = StaticFileServiceBuilder::new(tree.outside()).root(tree.root()).build();
+    assert_eq!(text(get(&reversed, "/style.css", &[]).await).await, "base css");
+    assert_eq!(text(get(&reversed, "/pages/about.html", &[]).await).await, "base about");
+}
+
+#[tokio::test]
+async fn prefixed_mounts() {
+    let tree = tree();
+    let service = tree.builder().mount("/base", tree.outside()).build();
+    assert_eq!(text(get(&service, "/base/style.css", &[]).await).await, "base css");
+    assert_eq!(text(get(&service, "/style.css", &[]).await).await, "theme css");
+    // the prefix only applies below it
+    assert_eq!(get(&service, "/contact.html", &[]).await.status(), StatusCode::NOT_FOUND);
+    assert_eq!(get(&service, "/basement/style.css", &[]).await.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn memory_fs() {
+    let tree = tree();
+    let generated = MemoryFs::new().file("version.txt", "1.4.2").file("/docs/api/index.json", "{}").file("style.css", "memory css");
+    let service = tree
+        .builder()
+        .mount_fs("/generated", generated.clone())
+        .mount_fs("/", generated)
+        .directory_listing(DirectoryListing::new().format(ListingFormat::Json))
+        .build();
+
+    let res = get(&service, "/generated/version.txt", &[]).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(header_str(&res, header::CONTENT_TYPE), "text/plain; charset=utf-8");
+    assert_eq!(header_str(&res, header::CONTENT_LENGTH), "5");
+    let etag = header_str(&res, header::ETAG).to_string();
+    assert_eq!(text(res).await, "1.4.2");
+
+    let res = get(&service, "/generated/version.txt", &[(header::IF_NONE_MATCH, &etag)]).await;
+    assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+    let res = get(&service, "/generated/version.txt", &[(header::RANGE, "bytes=2-")]).await;
+    assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(text(res).await, "4.2");
+
+    // implied directories are listed
+    let listing = json(get(&service, "/generated/docs/", &[]).await).await;
+    assert_eq!(listing["entries"][0]["name"], "api");
+    assert_eq!(get(&service, "/generated/missing.txt", &[]).await.status(), StatusCode::NOT_FOUND);
+
+    // the disk root was added first and wins; the memory root fills in the rest
+    assert_eq!(text(get(&service, "/style.css", &[]).await).await, "theme css");
+    assert_eq!(text(get(&service, "/version.txt", &[]).await).await, "1.4.2");
+}
@@ -0,0 +1,186 @@
+mod common;
+
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+};
+use common::{body, get, header_str, send, Tree};
+use exum::layers::static_layer::StaticFileService;
+
+const SIZES: &[usize] = &[1, 10, 4096, 100_000];
+
+fn content(size: usize) -> Vec<u8> {
+    (0..size).map(|i| (i % 251) as u8).collect()
+}
+
+fn setup() -> (Tree, StaticFileService) {
+    let mut tree = Tree::new().file("empty.bin", b"");
+    for size in SIZES {
+        tree = tree.file(&format!("{}.bin", size), content(*size));
+    }
+    let service = StaticFileService::new(tree.root(), false);
+    (tree, service)
+}
+
+#[tokio::test]
+async fn full_file_advertises_ranges() {
+    let (_tree, service) = setup();
+    for size in SIZES {
+        let res = get(&service, &format!("/{}.bin", size), &[]).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(header_str(&res, header::ACCEPT_RANGES), "bytes");
+        assert_eq!(header_str(&res, header::CONTENT_LENGTH), size.to_string());
+        assert_eq!(body(res).await, content(*size));
+    }
+}
+
+#[tokio::test]
+async fn single_ranges() {
+    let (_tree, service) = setup();
+    for size in SIZES {
+        let size = *size as u64;
+        let data = content(size as usize);
+        let path = format!("/{}.bin", size);
+        // (header, expected start, expected end)
+        let cases = [
+            ("bytes=0-0".to_string(), 0, 0),
+            (format!("bytes=0-{}", size + 10), 0, size - 1),
+            (format!("bytes={}-", size / 2), size / 2, size - 1),
+            ("bytes=-1".to_string(), size - 1, size - 1),
+            (format!("bytes=-{}", size * 2), 0, size - 1),
+        ];
+        for (range, start, end) in cases {
+            let res = get(&service, &path, &[(header::RANGE, &range)]).await;
+            assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT, "{} on {} bytes", range, size);
+            assert_eq!(header_str(&res, header::CONTENT_RANGE), format!("bytes {}-{}/{}", start, end, size));
+            assert_eq!(header_str(&res, header::CONTENT_LENGTH), (end - start + 1).to_string());
+            assert_eq!(body(res).await, &data[start as usize..=end as usize]);
+        }
+    }
+}
+
+#[tokio::test]
+async fn unsatisfiable_ranges() {
+    let (_tree, service) = setup();
+    for size in SIZES {
+        let res = get(&service, &format!("/{}.bin", size), &[(header::RANGE, &format!("bytes={}-", size))]).await;
+        assert_eq!(res.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(header_str(&res, header::CONTENT_RANGE), format!("bytes */{}", size));
+    }
+    let res = get(&service, "/empty.bin", &[(header::RANGE, "bytes=0-")]).await;
+    assert_eq!(res.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+    assert_eq!(header_str(&res, header::CONTENT_RANGE), "bytes */0");
+}
+
+#[tokio::test]
+async fn malformed_ranges_are_ignored() {
+    let (_tree, service) = setup();
+    for range in ["bytes=abc", "items=0-1", "bytes=5-2", "bytes"] {
+        let res = get(&service, "/100000.bin", &[(header::RANGE, range)]).await;
+        assert_eq!(res.status(), StatusCode::OK, "{}", range);
+        assert_eq!(body(res).await.len(), 100_000);
+    }
+}
+
+#[tokio::test]
+async fn multiple_ranges() {
+    let (_tree, service) = setup();
+    let data = content(100_000);
+    let res = get(&service, "/100000.bin", &[(header::RANGE, "bytes=0-9, 50000-50009, -5")]).await;
+    assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+    let content_type = header_str(&res, header::CONTENT_TYPE).to_string();
+    let boundary = content_type.strip_prefix("multipart/byteranges; boundary=").unwrap().to_string();
+    let length: usize = header_str(&res, header::CONTENT_LENGTH).parse().unwrap();
+    let body = body(res).await;
+    assert_eq!(body.len(), length);
+
+    let delimiter = format!("--{}", boundary);
+    let mut parts = Vec::new();
+    let mut rest = &body[..];
+    while let Some(pos) = find(rest, delimiter.as_bytes()) {
+        rest = &rest[pos + delimiter.len()..];
+        if rest.starts_with(b"--") {
+            break;
+        }
+        let head_end = find(rest, b"\r\n\r\n").unwrap();
+        let head = String::from_utf8(rest[..head_end].to_vec()).unwrap();
+        let data_start = head_end + 4;
+        let data_end = data_start + find(&rest[data_start..], format!("\r\n{}", delimiter).as_bytes()).unwrap();
+        parts.push((head, rest[data_start..data_end].to_vec()));
+        rest = &rest[data_end..];
+    }
+    let expected = [(0, 9), (50_000, 50_009), (99_995, 99_999)];
+    assert_eq!(parts.len(), expected.len());
+    for ((head, part), (start, end)) in parts.iter().zip(expected) {
+        assert!(head.contains(&format!("Content-Range: bytes {}-{}/100000", start, end)), "{}", head);
+        assert!(head.contains("Content-Type: application/octet-stream"));
+        assert_eq!(part, &data[start..=end]);
+    }
+}
+
+#[tokio::test]
+async fn overlapping_ranges_are_merged() {
+    let (_tree, service) = setup();
+    let data = content(4096);
+    let res = get(&service, "/4096.bin", &[(header::RANGE, "bytes=10-20, 15-30, 31-40")]).await;
+    assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(header_str(&res, header::CONTENT_RANGE), "bytes 10-40/4096");
+    assert_eq!(body(res).await, &data[10..=40]);
+}
+
+#[tokio::test]
+async fn if_range() {
+    let (tree, service) = setup();
+    let modified = std::fs::metadata(tree.root().join("4096.bin")).unwrap().modified().unwrap();
+    let current = httpdate::fmt_http_date(modified);
+    let stale = httpdate::fmt_http_date(modified - std::time::Duration::from_secs(3600));
+
+    let res = get(&service, "/4096.bin", &[(header::RANGE, "bytes=0-9"), (header::IF_RANGE, &current)]).await;
+    assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+
+    for validator in [stale.as_str(), "\"some-etag\"", "W/\"weak\""] {
+        let res = get(&service, "/4096.bin", &[(header::RANGE, "bytes=0-9"), (header::IF_RANGE, validator)]).await;
+        assert_eq!(res.status(), StatusCode::OK, "{}", validator);
+        assert_eq!(body(res).await.len(), 4096);
+    }
+}
+
+#[tokio::test]
+async fn head_reports_length() {
+    let (_tree, service) = setup();
+    let res = send(&service, Request::head("/100000.bin").body(Body::empty()).unwrap()).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(header_str(&res, header::CONTENT_LENGTH), "100000");
+    assert_eq!(header_str(&res, header::ACCEPT_RANGES), "bytes");
+    assert!(body(res).await.is_empty());
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+#[tokio::test]
+async fn strong_etag_changes_within_the_same_second() {
+    use exum::layers::{static_layer::StaticFileServiceBuilder, ETagStrategy};
+
+    let tree = Tree::new().file("v.bin", b"version one");
+    let path = tree.root().join("v.bin");
+    let second = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+    std::fs::File::options().write(true).open(&path).unwrap().set_modified(second).unwrap();
+    let service = StaticFileServiceBuilder::new(tree.root()).etag(ETagStrategy::Strong).build();
+    let old = get(&service, "/v.bin", &[]).await;
+    let old_etag = header_str(&old, header::ETAG).to_string();
+
+    // same size, same second, different content
+    std::fs::write(&path, b"version two").unwrap();
+    let later = second + std::time::Duration::from_millis(500);
+    std::fs::File::options().write(true).open(&path).unwrap().set_modified(later).unwrap();
+    let new = get(&service, "/v.bin", &[]).await;
+    assert_ne!(header_str(&new, header::ETAG), old_etag);
+
+    let res = get(&service, "/v.bin", &[(header::RANGE, "bytes=8-"), (header::IF_RANGE, &old_etag)]).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(body(res).await, b"version two");
+    let res = get(&service, "/v.bin", &[(header::IF_NONE_MATCH, &old_etag)]).await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
@@ -0,0 +1,133 @@
+#![cfg(unix)]
+
+mod common;
+
+use axum::{
+    http::{header, StatusCode},
+    response::Response,
+};
+use common::{text, Tree};
+use exum::layers::{static_layer::StaticFileService, DirectoryListing, DotFiles, ListingFormat, SymlinkPolicy};
+
+/// `root/linked` points to `outside/`, `root/inner` to `root/docs` and `root/secret.txt` to
+/// `outside/secret.txt`.
+fn tree() -> Tree {
+    Tree::new()
+        .file("docs/readme.txt", "readme")
+        .file(".git/config", "[core]")
+        .file(".env", "SECRET=1")
+        .file("app.js", "app")
+        .file("app.js.map", "map")
+        .file("report.PDF", "pdf")
+        .outside_file("secret.txt", "outside secret")
+        .symlink("linked", "outside")
+        .symlink("inner", "root/docs")
+        .symlink("secret.txt", "outside/secret.txt")
+}
+
+async fn get(service: &StaticFileService, path: &str) -> Response {
+    common::get(service, path, &[]).await
+}
+
+fn listing_names(json: &str) -> Vec<String> {
+    let value: serde_json::Value = serde_json::from_str(json).unwrap();
+    value["entries"].as_array().unwrap().iter().map(|e| e["name"].as_str().unwrap().to_string()).collect()
+}
+
+#[tokio::test]
+async fn listings_hide_symlinks_the_policy_does_not_follow() {
+    let tree = tree();
+    let listing = DirectoryListing::new().format(ListingFormat::Json);
+    for (policy, expected) in [
+        (SymlinkPolicy::WithinRoot, vec!["docs", "inner", "app.js", "app.js.map", "report.PDF"]),
+        (SymlinkPolicy::Never, vec!["docs", "app.js", "app.js.map", "report.PDF"]),
+        (SymlinkPolicy::Always, vec!["docs", "inner", "linked", "app.js", "app.js.map", "report.PDF", "secret.txt"]),
+    ] {
+        let service = tree.builder().follow_symlinks(policy).directory_listing(listing.clone()).build();
+        let res = get(&service, "/").await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[header::CONTENT_TYPE], "application/json");
+        assert_eq!(listing_names(&text(res).await), expected, "{:?}", policy);
+    }
+}
+
+#[tokio::test]
+async fn dotfiles() {
+    let tree = tree();
+    for path in ["/.env", "/.git/config", "/%2Eenv", "/docs/../.env"] {
+        let ignore = tree.builder().build();
+        assert_eq!(get(&ignore, path).await.status(), StatusCode::NOT_FOUND, "{}", path);
+        let deny = tree.builder().dotfiles(DotFiles::Deny).build();
+        assert_eq!(get(&deny, path).await.status(), StatusCode::FORBIDDEN, "{}", path);
+    }
+    let allow = tree.builder().dotfiles(DotFiles::Allow).build();
+    assert_eq!(text(get(&allow, "/.env").await).await, "SECRET=1");
+    assert_eq!(text(get(&allow, "/.git/config").await).await, "[core]");
+}
+
+#[tokio::test]
+async fn ignored_dotfiles_still_fall_back() {
+    let tree = tree();
+    std::fs::write(tree.root().join("index.html"), "spa").unwrap();
+    let service = tree.builder().with_spa_fallback(true).build();
+    let res = common::get(&service, "/.env", &[(header::ACCEPT, "text/html")]).await;
+    assert_eq!(text(res).await, "spa");
+}
+
+#[tokio::test]
+async fn symlinks_escaping_the_root() {
+    let tree = tree();
+    let cases = [
+        (SymlinkPolicy::WithinRoot, [StatusCode::OK, StatusCode::NOT_FOUND, StatusCode::NOT_FOUND]),
+        (SymlinkPolicy::Never, [StatusCode::NOT_FOUND, StatusCode::NOT_FOUND, StatusCode::NOT_FOUND]),
+        (SymlinkPolicy::Always, [StatusCode::OK, StatusCode::OK, StatusCode::OK]),
+    ];
+    for (policy, expected) in cases {
+        let service = tree.builder().follow_symlinks(policy).build();
+        for (path, status) in ["/inner/readme.txt", "/linked/secret.txt", "/secret.txt"].into_iter().zip(expected) {
+            let res = get(&service, path).await;
+            assert_eq!(res.status(), status, "{:?} {}", policy, path);
+            if status == StatusCode::NOT_FOUND {
+                assert!(!text(res).await.contains("secret"));
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn extension_lists() {
+    let tree = tree();
+    let deny = tree.builder().deny_extensions(&["MAP"]).build();
+    assert_eq!(get(&deny, "/app.js").await.status(), StatusCode::OK);
+    assert_eq!(get(&deny, "/app.js.map").await.status(), StatusCode::NOT_FOUND);
+
+    let allow = tree.builder().allow_extensions(&["js", "pdf"]).build();
+    assert_eq!(get(&allow, "/app.js").await.status(), StatusCode::OK);
+    assert_eq!(get(&allow, "/report.PDF").await.status(), StatusCode::OK);
+    assert_eq!(get(&allow, "/app.js.map").await.status(), StatusCode::NOT_FOUND);
+    assert_eq!(get(&allow, "/docs/readme.txt").await.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn nosniff() {
+    let tree = tree();
+    let default = tree.builder().build();
+    let res = get(&default, "/app.js").await;
+    assert_eq!(res.headers()[header::X_CONTENT_TYPE_OPTIONS], "nosniff");
+    let disabled = tree.builder().nosniff(false).build();
+    assert!(get(&disabled, "/app.js").await.headers().get(header::X_CONTENT_TYPE_OPTIONS).is_none());
+}
+
+#[tokio::test]
+async fn attachments() {
+    let tree = tree();
+    std::fs::write(tree.root().join("docs/naïve \"plan\".pdf"), "pdf").unwrap();
+    let service = tree.builder().attachment("*.pdf").attachment("docs/**").build();
+    let res = get(&service, "/docs/na%C3%AFve%20%22plan%22.pdf").await;
+    assert_eq!(
+        res.headers()[header::CONTENT_DISPOSITION],
+        "attachment; filename=\"na_ve _plan_.pdf\"; filename*=UTF-8''na%C3%AFve%20%22plan%22%2Epdf"
+    );
+    assert!(get(&service, "/docs/readme.txt").await.headers().contains_key(header::CONTENT_DISPOSITION));
+    assert!(!get(&service, "/app.js").await.headers().contains_key(header::CONTENT_DISPOSITION));
+}
@@ -0,0 +1,291 @@
+#![cfg(unix)]
+
+mod common;
+
+use axum::{
+    body::Body,
+    http::{header, Method, Request, StatusCode},
+    response::Response,
+};
+use common::{json, send, Tree};
+use exum::layers::{static_layer::StaticFileService, Overwrite, UploadPolicy};
+
+const TOKEN: &str = "Bearer upload-token";
+
+/// `root/linked` points to `outside/`.
+fn tree() -> Tree {
+    Tree::new()
+        .file("docs/a.txt", "original")
+        .file(".env", "SECRET=1")
+        .outside_file("secret.txt", "outside secret")
+        .symlink("linked", "outside")
+}
+
+fn uploads(tree: &Tree, policy: UploadPolicy) -> StaticFileService {
+    tree.builder().with_uploads(policy).build()
+}
+
+fn policy() -> UploadPolicy {
+    UploadPolicy::new(|req| req.headers.get(header::AUTHORIZATION).is_some_and(|v| v == TOKEN))
+}
+
+fn put(path: &str, content_type: Option<&str>, body: &str) -> Request<Body> {
+    let mut req = Request::put(path).header(header::AUTHORIZATION, TOKEN);
+    if let Some(content_type) = content_type {
+        req = req.header(header::CONTENT_TYPE, content_type);
+    }
+    req.body(Body::from(body.to_string())).unwrap()
+}
+
+fn delete(path: &str) -> Request<Body> {
+    Request::delete(path).header(header::AUTHORIZATION, TOKEN).body(Body::empty()).unwrap()
+}
+
+/// A multipart form with one part per `(file name, content type, content)`.
+/// File name, declared type and content of a multipart file part.
+type Part<'a> = (&'a str, &'a str, &'a str);
+
+fn post(path: &str, files: &[Part]) -> Request<Body> {
+    let mut body = String::from("--XX\r\nContent-Disposition: form-data; name=\"note\"\r\n\r\nignored\r\n");
+    for (name, content_type, content) in files {
+        body.push_str(&format!(
+            "--XX\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n{}\r\n",
+            name, content_type, content
+        ));
+    }
+    body.push_str("--XX--\r\n");
+    Request::post(path)
+        .header(header::AUTHORIZATION, TOKEN)
+        .header(header::CONTENT_TYPE, "multipart/form-data; boundary=XX")
+        .body(Body::from(body))
+        .unwrap()
+}
+
+async fn code(res: Response) -> (StatusCode, String) {
+    let status = res.status();
+    let body = json(res).await;
+    (status, body["code"].as_str().unwrap_or_default().to_string())
+}
+
+#[tokio::test]
+async fn put_stores_files_and_describes_them() {
+    let tree = tree();
+    let service = uploads(&tree, policy());
+    let res = send(&service, put("/new/dir/b%20c.txt", Some("text/plain"), "hello")).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    assert_eq!(res.headers()[header::LOCATION], "/new/dir/b%20c.txt");
+    let body = json(res).await;
+    assert_eq!(body["path"], "/new/dir/b%20c.txt");
+    assert_eq!(body["size"], 5);
+    assert_eq!(body["content_type"], "text/plain; charset=utf-8");
+    assert_eq!(body["created"], true);
+    assert!(body["etag"].is_string());
+    assert_eq!(tree.read("new/dir/b c.txt").as_deref(), Some("hello"));
+}
+
+#[tokio::test]
+async fn uploads_need_authorization() {
+    let tree = tree();
+    let service = uploads(&tree, policy());
+    let before = tree.files();
+    for method in [Method::PUT, Method::POST, Method::DELETE] {
+        let req = Request::builder()
+            .method(method.clone())
+            .uri("/docs/a.txt")
+            .header(header::AUTHORIZATION, "Bearer wrong")
+            .body(Body::from("changed"))
+            .unwrap();
+        assert_eq!(code(send(&service, req).await).await, (StatusCode::UNAUTHORIZED, "unauthorized".into()), "{}", method);
+    }
+    assert_eq!(tree.files(), before);
+    assert_eq!(tree.read("docs/a.txt").as_deref(), Some("original"));
+    // reads stay public
+    let res = send(&service, Request::get("/docs/a.txt").body(Body::empty()).unwrap()).await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn parent_segments_stay_inside_the_root() {
+    let tree = tree();
+    let service = uploads(&tree, policy());
+    // `..` segments are dropped, as for reads
+    for (path, stored) in [
+        ("/../escape.txt", "escape.txt"),
+        ("/docs/../../escape.txt", "docs/escape.txt"),
+        ("/%2E%2E/%2e%2e/escape.txt", "escape.txt"),
+    ] {
+        let res = send(&service, put(path, None, "x")).await;
+        assert!(res.status().is_success(), "{}", path);
+        assert_eq!(json(res).await["path"], format!("/{}", stored));
+        std::fs::remove_file(tree.root().join(stored)).unwrap();
+    }
+    assert!(!tree.path().join("escape.txt").exists());
+
+    let res = send(&service, post("/../", &[("../../escape.txt", "text/plain", "x")])).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    assert_eq!(json(res).await["files"][0]["path"], "/escape.txt");
+    assert!(!tree.path().join("escape.txt").exists());
+}
+
+#[tokio::test]
+async fn dotfiles_are_not_writable() {
+    let tree = tree();
+    let service = uploads(&tree, policy());
+    let forbidden = (StatusCode::FORBIDDEN, "forbidden_path".to_string());
+    assert_eq!(code(send(&service, put("/.env", None, "SECRET=2")).await).await, forbidden);
+    assert_eq!(code(send(&service, put("/.git/config", None, "x")).await).await, forbidden);
+    assert_eq!(code(send(&service, delete("/.env")).await).await, forbidden);
+    assert_eq!(code(send(&service, post("/docs/", &[(".htaccess", "text/plain", "x")])).await).await, forbidden);
+    assert_eq!(code(send(&service, post("/.hidden/", &[("a.txt", "text/plain", "x")])).await).await, forbidden);
+    assert_eq!(tree.read(".env").as_deref(), Some("SECRET=1"));
+    assert!(!tree.root().join(".git").exists());
+    assert!(!tree.root().join(".hidden").exists());
+}
+
+#[tokio::test]
+async fn symlinked_directories_do_not_escape_the_root() {
+    let tree = tree();
+    let service = uploads(&tree, policy());
+    let before = tree.files();
+    assert!(!send(&service, put("/linked/x.txt", None, "x")).await.status().is_success());
+    assert!(!send(&service, put("/linked/sub/x.txt", None, "x")).await.status().is_success());
+    assert!(!send(&service, post("/linked/", &[("x.txt", "text/plain", "x")])).await.status().is_success());
+    assert!(!send(&service, delete("/linked/secret.txt")).await.status().is_success());
+    assert_eq!(tree.files(), before);
+    assert_eq!(std::fs::read_to_string(tree.outside().join("secret.txt")).unwrap(), "outside secret");
+    assert!(!tree.outside().join("sub").exists());
+}
+
+#[tokio::test]
+async fn size_limit() {
+    let tree = tree();
+    let service = uploads(&tree, policy().max_size(8));
+    let before = tree.files();
+    let too_large = (StatusCode::PAYLOAD_TOO_LARGE, "payload_too_large".to_string());
+    // announced and streamed
+    let announced = Request::put("/big.txt")
+        .header(header::AUTHORIZATION, TOKEN)
+        .header(header::CONTENT_LENGTH, "9")
+        .body(Body::from("123456789"))
+        .unwrap();
+    assert_eq!(code(send(&service, announced).await).await, too_large);
+    assert_eq!(code(send(&service, put("/big.txt", None, "123456789")).await).await, too_large);
+    assert_eq!(code(send(&service, post("/", &[("big.txt", "text/plain", "123456789")])).await).await, too_large);
+    // no temp file left behind
+    assert_eq!(tree.files(), before);
+    assert_eq!(send(&service, put("/fits.txt", None, "12345678")).await.status(), StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn overwrite_modes() {
+    let tree = tree();
+    let reject = uploads(&tree, policy());
+    let res = send(&reject, put("/docs/a.txt", None, "changed")).await;
+    assert_eq!(code(res).await, (StatusCode::CONFLICT, "file_exists".into()));
+    assert_eq!(tree.read("docs/a.txt").as_deref(), Some("original"));
+
+    let replace = uploads(&tree, policy().overwrite(Overwrite::Replace));
+    let res = send(&replace, put("/docs/a.txt", None, "replaced")).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(json(res).await["created"], false);
+    assert_eq!(tree.read("docs/a.txt").as_deref(), Some("replaced"));
+
+    let rename = uploads(&tree, policy().overwrite(Overwrite::Rename));
+    for expected in ["/docs/a-1.txt", "/docs/a-2.txt"] {
+        let res = send(&rename, put("/docs/a.txt", None, "renamed")).await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(json(res).await["path"], expected);
+    }
+    let res = send(&rename, post("/docs/", &[("a.txt", "text/plain", "one"), ("a.txt", "text/plain", "two")])).await;
+    let body = json(res).await;
+    assert_eq!(body["files"][0]["path"], "/docs/a-3.txt");
+    assert_eq!(body["files"][1]["path"], "/docs/a-4.txt");
+    assert_eq!(tree.read("docs/a.txt").as_deref(), Some("replaced"));
+    assert_eq!(tree.read("docs/a-4.txt").as_deref(), Some("two"));
+}
+
+#[tokio::test]
+async fn types_are_checked_as_served() {
+    let tree = tree();
+    let service = uploads(&tree, policy().allowed_types(&["image/*"]));
+    let res = send(&service, put("/x.html", Some("image/png"), "<script>alert(1)</script>")).await;
+    assert_eq!(code(res).await, (StatusCode::UNSUPPORTED_MEDIA_TYPE, "content_type_mismatch".into()));
+    let res = send(&service, put("/x.txt", None, "text")).await;
+    assert_eq!(code(res).await, (StatusCode::UNSUPPORTED_MEDIA_TYPE, "unsupported_content_type".into()));
+    let res = send(&service, put("/x.png", Some("image/png"), "png")).await;
+    assert_eq!(json(res).await["content_type"], "image/png");
+    let res = send(&service, put("/y.png", Some("application/octet-stream"), "png")).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+
+    // scriptable types need an explicit opt-in, whatever the allow-list
+    let open = uploads(&tree, policy());
+    for name in ["/x.html", "/x.svg", "/x.js", "/x.xhtml"] {
+        let res = send(&open, put(name, None, "<script>alert(1)</script>")).await;
+        assert_eq!(code(res).await, (StatusCode::UNSUPPORTED_MEDIA_TYPE, "unsupported_content_type".into()), "{}", name);
+    }
+    let active = uploads(&tree, policy().allow_active_content(true));
+    assert_eq!(send(&active, put("/page.html", None, "<p>hi</p>")).await.status(), StatusCode::CREATED);
+    assert!(tree.read("x.html").is_none());
+}
+
+#[tokio::test]
+async fn multipart_forms_are_stored_all_or_nothing() {
+    let tree = tree();
+    let service = uploads(&tree, policy().max_size(8).max_files(2));
+    let before = tree.files();
+    let failing: [(&[Part], StatusCode); 5] = [
+        (&[("ok.txt", "text/plain", "ok"), ("big.txt", "text/plain", "123456789")], StatusCode::PAYLOAD_TOO_LARGE),
+        (&[("ok.txt", "text/plain", "ok"), ("x.html", "text/html", "<p>")], StatusCode::UNSUPPORTED_MEDIA_TYPE),
+        (&[("ok.txt", "text/plain", "ok"), ("a.txt", "text/plain", "clash")], StatusCode::CONFLICT),
+        (&[("ok.txt", "text/plain", "ok"), ("ok.txt", "text/plain", "again")], StatusCode::CONFLICT),
+        (&[("1.txt", "text/plain", "1"), ("2.txt", "text/plain", "2"), ("3.txt", "text/plain", "3")], StatusCode::PAYLOAD_TOO_LARGE),
+    ];
+    for (files, status) in failing {
+        let res = send(&service, post("/docs/", files)).await;
+        assert_eq!(res.status(), status, "{:?}", files);
+        assert_eq!(tree.files(), before, "{:?}", files);
+    }
+
+    let res = send(&service, post("/docs", &[("b.txt", "text/plain", "bee"), ("c.txt", "", "sea")])).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let body = json(res).await;
+    assert_eq!(body["files"][0]["path"], "/docs/b.txt");
+    assert_eq!(body["files"][1]["path"], "/docs/c.txt");
+    assert_eq!(tree.read("docs/c.txt").as_deref(), Some("sea"));
+}
+
+#[tokio::test]
+async fn delete_removes_files() {
+    let tree = tree();
+    let service = uploads(&tree, policy());
+    assert_eq!(send(&service, delete("/docs/a.txt")).await.status(), StatusCode::NO_CONTENT);
+    assert!(tree.read("docs/a.txt").is_none());
+    assert_eq!(code(send(&service, delete("/docs/a.txt")).await).await, (StatusCode::NOT_FOUND, "not_found".into()));
+    assert_eq!(code(send(&service, delete("/docs")).await).await, (StatusCode::CONFLICT, "path_conflict".into()));
+
+    std::fs::write(tree.root().join("docs/kept.txt"), "kept").unwrap();
+    let read_only = uploads(&tree, policy().allow_delete(false));
+    let res = send(&read_only, delete("/docs/kept.txt")).await;
+    assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert!(tree.read("docs/kept.txt").is_some());
+}
+
+#[tokio::test]
+async fn rejected_uploads_create_no_directories() {
+    let tree = tree();
+    let service = uploads(&tree, policy().max_size(8).allowed_types(&["text/*"]));
+    let before = tree.files();
+    // streamed over the limit, no Content-Length
+    let res = send(&service, put("/new/deep/big.txt", None, "123456789")).await;
+    assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let res = send(&service, post("/new/form/", &[("ok.txt", "text/plain", "ok"), ("x.png", "image/png", "png")])).await;
+    assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    let res = send(&service, post("/new/form/", &[("ok.txt", "text/plain", "ok"), ("big.txt", "text/plain", "123456789")])).await;
+    assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(tree.files(), before);
+
+    let res = send(&service, post("/new/form/", &[("ok.txt", "text/plain", "ok")])).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    assert_eq!(tree.read("new/form/ok.txt").as_deref(), Some("ok"));
+    assert!(!tree.files().iter().any(|f| f.ends_with(".upload")));
+}
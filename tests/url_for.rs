@@ -0,0 +1,112 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{get, text};
+use exum::*;
+
+#[get("/users/{id:u64}")]
+async fn get_user(id: u64, #[q] page: Option<u32>) -> String {
+    format!("user {} {:?}", id, page)
+}
+
+#[get("/tags/{name}")]
+async fn get_tag(name: String) -> String {
+    name
+}
+
+#[get("/files/{*rest}")]
+async fn get_file(rest: String) -> String {
+    rest
+}
+
+#[get("/文章/{id}")]
+async fn get_article(id: u32) -> String {
+    format!("article {}", id)
+}
+
+mod api {
+    use exum::*;
+
+    #[get("/search")]
+    pub async fn search(#[q] kw: String) -> String {
+        kw
+    }
+}
+
+#[derive(Controller)]
+struct OrgController;
+
+#[controller(prefix = "/orgs", children = [TeamController])]
+impl OrgController {
+    #[get("/{org}")]
+    async fn get_org(org: String) -> String {
+        org
+    }
+}
+
+#[derive(Controller)]
+struct TeamController;
+
+#[controller(prefix = "/teams/")]
+impl TeamController {
+    #[get("/{team}")]
+    async fn get_team(team: String) -> String {
+        team
+    }
+}
+
+/// The body the router answers `url` with.
+async fn follow(url: &str) -> String {
+    let res = get(&collect_router(), url, &[]).await;
+    assert_eq!(res.status(), StatusCode::OK, "{}", url);
+    text(res).await
+}
+
+#[tokio::test]
+async fn path_and_query_parameters() {
+    assert_eq!(url_for!(get_user, id = 5), "/users/5");
+    assert_eq!(url_for!(get_user, id = 5, page = 2), "/users/5?page=2");
+    // order of the arguments does not matter
+    assert_eq!(url_for!(get_user, page = 2, id = 5), "/users/5?page=2");
+    assert_eq!(follow(&url_for!(get_user, id = 5, page = 2)).await, "user 5 Some(2)");
+}
+
+#[tokio::test]
+async fn values_are_encoded() {
+    let url = url_for!(get_tag, name = "a/b c?#%");
+    assert_eq!(url, "/tags/a%2Fb%20c%3F%23%25");
+    assert_eq!(follow(&url).await, "a/b c?#%");
+
+    let url = url_for!(api::search, kw = "中 文&x=1");
+    assert_eq!(url, "/search?kw=%E4%B8%AD%20%E6%96%87%26x%3D1");
+    assert_eq!(follow(&url).await, "中 文&x=1");
+}
+
+#[tokio::test]
+async fn wildcards_keep_their_slashes() {
+    let url = url_for!(get_file, rest = "docs/read me.md");
+    assert_eq!(url, "/files/docs/read%20me%2Emd");
+    assert_eq!(follow(&url).await, "docs/read me.md");
+
+    // dots are encoded too, so a `..` value cannot become a dot-segment
+    assert_eq!(url_for!(get_tag, name = ".."), "/tags/%2E%2E");
+    assert_eq!(url_for!(get_file, rest = "../x"), "/files/%2E%2E/x");
+}
+
+#[tokio::test]
+async fn literal_segments_match_the_registered_route() {
+    let url = url_for!(get_article, id = 3);
+    assert_eq!(url, "/%E6%96%87%E7%AB%A0/3");
+    assert_eq!(follow(&url).await, "article 3");
+}
+
+#[tokio::test]
+async fn controller_routes_get_every_prefix() {
+    let url = url_for!(OrgController::get_org, org = "acme");
+    assert_eq!(url, "/orgs/acme");
+    assert_eq!(follow(&url).await, "acme");
+
+    let url = url_for!(TeamController::get_team, team = "core");
+    assert_eq!(url, "/orgs/teams/core");
+    assert_eq!(follow(&url).await, "core");
+}
@@ -0,0 +1,144 @@
+mod common;
+
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+    Router,
+};
+use common::{get, json, send, text};
+use exum::*;
+use serde::Deserialize;
+use serde_json::json;
+
+#[derive(Deserialize, Validate)]
+struct Address {
+    #[validate(length(min = 2))]
+    city: String,
+}
+
+#[derive(Deserialize, Validate)]
+struct User {
+    #[validate(length(min = 1, max = 20))]
+    name: String,
+    #[validate(range(min = 18, max = 150))]
+    age: u32,
+    #[validate(email)]
+    email: Option<String>,
+    #[validate(nested)]
+    address: Address,
+}
+
+#[post("/users")]
+async fn create_user(#[b(validate)] user: User) -> String {
+    format!("{} {}", user.name, user.address.city)
+}
+
+#[post("/users/form")]
+async fn create_user_form(#[b(form, validate)] user: Address) -> String {
+    user.city
+}
+
+#[get("/search")]
+async fn search(
+    #[q(validate(range(min = 1, max = 100)))] page: u32,
+    #[q(validate(length(min = 2)))] keyword: Option<String>,
+) -> String {
+    format!("{} {:?}", page, keyword)
+}
+
+#[get("/items")]
+async fn items(#[q(validate(range(min = 1)))] limit: u32) -> String {
+    limit.to_string()
+}
+
+fn post_json(path: &str, body: serde_json::Value) -> Request<Body> {
+    Request::post(path)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header("x-request-id", "req-1")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+fn router() -> Router {
+    collect_router()
+}
+
+#[tokio::test]
+async fn valid_bodies_pass() {
+    let res = send(&router(), post_json("/users", json!({"name": "ann", "age": 30, "address": {"city": "Oslo"}}))).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(text(res).await, "ann Oslo");
+}
+
+#[tokio::test]
+async fn body_failures_list_every_field() {
+    let user = json!({"name": "", "age": 12, "email": "nope", "address": {"city": "X"}});
+    let res = send(&router(), post_json("/users", user)).await;
+    assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        json(res).await,
+        json!({
+            "status": 422,
+            "code": "validation_failed",
+            "message": "Request validation failed",
+            "param": "user",
+            "request_id": "req-1",
+            "fields": [
+                {"field": "name", "code": "length", "message": "length must be between 1 and 20"},
+                {"field": "age", "code": "range", "message": "must be between 18 and 150"},
+                {"field": "email", "code": "email", "message": "must be a valid email address"},
+                {"field": "address.city", "code": "length", "message": "length must be at least 2"},
+            ],
+        })
+    );
+}
+
+#[tokio::test]
+async fn form_bodies_are_validated() {
+    let req = Request::post("/users/form")
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(Body::from("city=X"))
+        .unwrap();
+    let res = send(&router(), req).await;
+    assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = json(res).await;
+    assert_eq!(body["code"], "validation_failed");
+    assert_eq!(body["fields"][0]["field"], "city");
+}
+
+#[tokio::test]
+async fn query_failures_name_the_failing_parameter() {
+    let res = get(&router(), "/search?page=5&keyword=rust", &[]).await;
+    assert_eq!(text(res).await, "5 Some(\"rust\")");
+
+    let res = get(&router(), "/search?page=500&keyword=rust", &[]).await;
+    assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = json(res).await;
+    assert_eq!(body["code"], "validation_failed");
+    assert_eq!(body["param"], "page");
+    assert_eq!(body["fields"][0]["field"], "page");
+
+    // several parameters failed: the fields say which
+    let res = get(&router(), "/search?page=0&keyword=r", &[]).await;
+    let body = json(res).await;
+    assert!(body.get("param").is_none(), "{}", body);
+    assert_eq!(body["fields"].as_array().unwrap().len(), 2);
+
+    let res = get(&router(), "/items?limit=0", &[]).await;
+    assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(json(res).await["param"], "limit");
+}
+
+#[tokio::test]
+async fn unparsable_queries_are_bad_requests() {
+    let res = get(&router(), "/search?page=abc", &[]).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    let body = json(res).await;
+    assert_eq!(body["code"], "invalid_query");
+    assert_eq!(body["param"], "page");
+
+    // not the names of every query field joined together
+    let res = get(&router(), "/search", &[]).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    assert!(json(res).await.get("param").is_none());
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use axum::{
+    http::{header, HeaderName, StatusCode},
+    routing,
+    Router,
+};
+use common::{get, header_str};
+use exum::versioning::{route_versions_with, DeprecationConfig, VersionedRoute, VersioningConfig};
+
+static ROUTES: &[VersionedRoute] = &[
+    VersionedRoute {
+        path: "/users",
+        version: "1",
+        route: || routing::get(|| async { "v1" }),
+        handler: "list_users_v1",
+        module_path: "versioning_deprecation",
+        group: None,
+        profile: None,
+    },
+    VersionedRoute {
+        path: "/users",
+        version: "2",
+        route: || routing::get(|| async { "v2" }),
+        handler: "list_users_v2",
+        module_path: "versioning_deprecation",
+        group: None,
+        profile: None,
+    },
+];
+
+fn router(since: Option<&str>) -> Router {
+    let mut config = VersioningConfig::default();
+    config.deprecated.insert(
+        "1".to_string(),
+        DeprecationConfig {
+            since: since.map(str::to_string),
+            sunset: Some("Wed, 31 Dec 2025 23:59:59 GMT".to_string()),
+            link: Some("https://example.com/migrate".to_string()),
+        },
+    );
+    route_versions_with(&config, Router::new(), ROUTES)
+}
+
+fn deprecation() -> HeaderName {
+    HeaderName::from_static("deprecation")
+}
+
+#[tokio::test]
+async fn deprecated_versions_get_the_headers() {
+    let router = router(Some("@1735689599"));
+    let res = get(&router, "/v1/users", &[]).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(header_str(&res, deprecation()), "@1735689599");
+    assert_eq!(header_str(&res, HeaderName::from_static("sunset")), "Wed, 31 Dec 2025 23:59:59 GMT");
+    assert_eq!(header_str(&res, header::LINK), "<https://example.com/migrate>; rel=\"deprecation\"");
+
+    let res = get(&router, "/v2/users", &[]).await;
+    assert!(res.headers().get(deprecation()).is_none());
+}
+
+#[tokio::test]
+async fn http_dates_are_sent_as_epoch_seconds() {
+    let router = router(Some("Tue, 31 Dec 2024 23:59:59 GMT"));
+    let res = get(&router, "/v1/users", &[]).await;
+    assert_eq!(header_str(&res, deprecation()), "@1735689599");
+}
+
+#[test]
+#[should_panic(expected = "Deprecated API version 1 needs `since`")]
+fn since_is_required() {
+    let _ = router(None);
+}
+
+#[test]
+#[should_panic(expected = "Invalid `since` for deprecated API version 1: \"true\"")]
+fn since_must_be_a_date() {
+    let _ = router(Some("true"));
+}
@@ -0,0 +1,49 @@
+use exum::*;
+
+#[get("/users")]
+async fn list_users() -> &'static str {
+    "unversioned"
+}
+
+#[get("/users", version = "1")]
+async fn list_users_v1() -> &'static str {
+    "v1"
+}
+
+#[post("/items")]
+async fn create_item() -> &'static str {
+    "function"
+}
+
+#[derive(Controller)]
+struct ItemController;
+
+#[controller("/items")]
+impl ItemController {
+    #[post("/")]
+    async fn create() -> &'static str {
+        "controller"
+    }
+
+    #[get("/")]
+    async fn list() -> &'static str {
+        "controller"
+    }
+}
+
+#[test]
+#[should_panic(expected = "Routes `list_users` and `list_users_v1` (version 1) overlap on GET /users")]
+fn unversioned_route_on_the_default_version_path() {
+    let _ = collect_router_from(&RouteSelection::new().handler("list_users").handler("list_users_v1"));
+}
+
+#[test]
+#[should_panic(expected = "overlap on POST /items")]
+fn controller_and_function_on_the_same_route() {
+    let _ = collect_router_from(&RouteSelection::new().handler("create_item").controller::<ItemController>());
+}
+
+#[test]
+fn versions_alone_do_not_overlap() {
+    let _ = collect_router_from(&RouteSelection::new().handler("list_users_v1").controller::<ItemController>());
+}